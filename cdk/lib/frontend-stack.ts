@@ -1,5 +1,5 @@
 import { join } from 'path';
-import { RemovalPolicy, Stack, StackProps } from "aws-cdk-lib";
+import { CfnParameter, RemovalPolicy, Stack, StackProps } from "aws-cdk-lib";
 import { Construct } from "constructs";
 import { Role, ServicePrincipal } from 'aws-cdk-lib/aws-iam';
 import { DockerImageAsset } from 'aws-cdk-lib/aws-ecr-assets';
@@ -35,6 +35,21 @@ export class FrontEndStack extends Stack {
         })
         imageAsset.repository.grantPull(accessRole)
 
+        // sign in with the OIDC provider of the API tokens (authorization code flow with PKCE)
+        // the app URL has to be an allowed callback URL of the client
+        const authAuthorizeUrl = new CfnParameter(this, 'AuthAuthorizeUrl', {
+            type: 'String',
+            description: 'Authorization endpoint of the OIDC provider, e.g. https://<domain>.auth.<region>.amazoncognito.com/oauth2/authorize'
+        })
+        const authTokenUrl = new CfnParameter(this, 'AuthTokenUrl', {
+            type: 'String',
+            description: 'Token endpoint of the OIDC provider, e.g. https://<domain>.auth.<region>.amazoncognito.com/oauth2/token'
+        })
+        const authClientId = new CfnParameter(this, 'AuthClientId', {
+            type: 'String',
+            description: 'Client id (public client, without secret), the audience of the API tokens'
+        })
+
         // app runner
         const app = new CfnService(this, "RekognitionNextJs", {
             sourceConfiguration: {
@@ -47,6 +62,18 @@ export class FrontEndStack extends Stack {
                             {
                                 name: "API_ENDPOINT",
                                 value: this.endpointUrl
+                            },
+                            {
+                                name: "AUTH_AUTHORIZE_URL",
+                                value: authAuthorizeUrl.valueAsString
+                            },
+                            {
+                                name: "AUTH_TOKEN_URL",
+                                value: authTokenUrl.valueAsString
+                            },
+                            {
+                                name: "AUTH_CLIENT_ID",
+                                value: authClientId.valueAsString
                             }
                         ]
                     },
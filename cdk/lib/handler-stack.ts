@@ -2,11 +2,14 @@ import { join } from 'path';
 import { RustFunction } from 'cargo-lambda-cdk';
 import { EndpointType, LambdaRestApi } from 'aws-cdk-lib/aws-apigateway'
 import { Table } from 'aws-cdk-lib/aws-dynamodb';
-import { Duration, Size, Stack, StackProps } from "aws-cdk-lib";
+import { CfnParameter, Duration, Size, Stack, StackProps } from "aws-cdk-lib";
 import { Construct } from "constructs";
 import { Subscription, SubscriptionProtocol, Topic } from 'aws-cdk-lib/aws-sns';
 import { Effect, ManagedPolicy, PolicyStatement, Role, ServicePrincipal } from 'aws-cdk-lib/aws-iam';
 import { Bucket } from 'aws-cdk-lib/aws-s3';
+import { Rule, Schedule } from 'aws-cdk-lib/aws-events';
+import { LambdaFunction } from 'aws-cdk-lib/aws-events-targets';
+import { Secret } from 'aws-cdk-lib/aws-secretsmanager';
 
 
 export interface HandlerStackProps extends StackProps {
@@ -35,6 +38,26 @@ export class RekognitionHandlerStack extends Stack {
             ],
         })
 
+        // key for signing the pagination cursors of the job list
+        const cursorSecret = new Secret(this, 'CursorSecret', {
+            generateSecretString: { passwordLength: 64, excludePunctuation: true }
+        })
+
+        // OIDC provider of the API tokens (e.g. a Cognito user pool), its JWKS is passed as is so tokens are verified without fetching it
+        // the keys are fixed at deploy time: deploy again with the new JWKS when the provider rotates its keys
+        const authJwks = new CfnParameter(this, 'AuthJwks', {
+            type: 'String',
+            description: 'JWKS document of the OIDC provider (content of its jwks_uri)'
+        })
+        const authIssuer = new CfnParameter(this, 'AuthIssuer', {
+            type: 'String',
+            description: 'Issuer (iss) of the tokens'
+        })
+        const authAudience = new CfnParameter(this, 'AuthAudience', {
+            type: 'String',
+            description: 'Audience (aud) of the tokens, e.g. the client id'
+        })
+
         // apigateway lambda
         const apigatewayLambda = new RustFunction(this, 'RekognitionAPIGatewayLambda', {
             // Path to the root directory.
@@ -43,7 +66,12 @@ export class RekognitionHandlerStack extends Stack {
                 'TABLE_NAME': jobTable.tableName,
                 "BUCKET_NAME": s3Bucket.bucketName,
                 "TOPIC_ARN": snsTopic.topicArn,
-                "ROLE_ARN": rekognitionServiceRole.roleArn
+                "ROLE_ARN": rekognitionServiceRole.roleArn,
+                // read by the lambda at cold start, so the secret value is not part of the template
+                "CURSOR_SECRET_ARN": cursorSecret.secretArn,
+                "AUTH_JWKS": authJwks.valueAsString,
+                "AUTH_ISSUER": authIssuer.valueAsString,
+                "AUTH_AUDIENCE": authAudience.valueAsString
             },
             timeout: Duration.minutes(5),
             memorySize: 10000,
@@ -58,6 +86,7 @@ export class RekognitionHandlerStack extends Stack {
 
         s3Bucket.grantReadWrite(apigatewayLambda);
         jobTable.grantFullAccess(apigatewayLambda);
+        cursorSecret.grantRead(apigatewayLambda);
         apigatewayLambda.addToRolePolicy(new PolicyStatement({
             effect: Effect.ALLOW,
             actions: [
@@ -80,6 +109,7 @@ export class RekognitionHandlerStack extends Stack {
         const processResultsLambda = new RustFunction(this, 'RekognitionProcessResultLambda', {
             // Path to the root directory.
             manifestPath: join(__dirname, '..', '..', 'lambdas/process-results-lambda/'),
+            binaryName: 'process-results-lambda',
             environment: {
                 "TOPIC_ARN": snsTopic.topicArn,
                 'TABLE_NAME': jobTable.tableName,
@@ -112,5 +142,40 @@ export class RekognitionHandlerStack extends Stack {
             action: 'lambda:InvokeFunction',
         })
 
+        // finalizes jobs whose completion message was lost, by polling their status
+        const reconcileLambda = new RustFunction(this, 'RekognitionReconcileLambda', {
+            manifestPath: join(__dirname, '..', '..', 'lambdas/process-results-lambda/'),
+            binaryName: 'reconcile',
+            environment: {
+                'TABLE_NAME': jobTable.tableName,
+                "BUCKET_NAME": s3Bucket.bucketName,
+                "RECONCILE_AFTER_MINUTES": "30",
+                "JOB_TIMEOUT_MINUTES": "180"
+            },
+            timeout: Duration.minutes(15),
+            memorySize: 1000,
+            ephemeralStorageSize: Size.gibibytes(1)
+        });
+
+        s3Bucket.grantReadWrite(reconcileLambda);
+        jobTable.grantFullAccess(reconcileLambda);
+
+        // only reads the status (and results) of the Rekognition jobs
+        reconcileLambda.addToRolePolicy(new PolicyStatement({
+            effect: Effect.ALLOW,
+            actions: [
+                'rekognition:GetPersonTracking',
+                'rekognition:GetLabelDetection',
+                'rekognition:GetFaceDetection',
+                'rekognition:GetSegmentDetection'
+            ],
+            resources: ['*'],
+        }))
+
+        new Rule(this, 'RekognitionReconcileSchedule', {
+            schedule: Schedule.rate(Duration.minutes(15)),
+            targets: [new LambdaFunction(reconcileLambda)],
+        });
+
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,213 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use axum::extract::{Request, State};
+use axum::http::header::AUTHORIZATION;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::Response;
+use jsonwebtoken::jwk::{AlgorithmParameters, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use lib::env_keys::{AUTH_AUDIENCE_KEY, AUTH_ISSUER_KEY, AUTH_JWKS_KEY};
+use serde::{Deserialize, Serialize};
+
+use crate::handlers::build_error_response_with_status;
+
+
+// claims of the OIDC token used by the API, the user id is the subject
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Claims {
+    pub sub: String,
+    pub iss: String,
+    pub aud: Audience,
+    pub exp: u64,
+}
+
+// `aud` is a single audience or a list of them (RFC 7519), the expected one is checked by the validation
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Audience {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+// verified user of a request, set by `authenticate` for the handlers
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Caller {
+    pub user_id: String,
+}
+
+#[derive(Clone)]
+struct VerificationKey {
+    key_id: Option<String>,
+    algorithm: Option<String>,
+    key: DecodingKey,
+}
+
+// Verifies tokens of an OIDC provider against its public keys (JWKS), issuer and audience.
+// Keys are given as the JWKS document, so tokens are verified without fetching anything:
+// keys rotated by the provider are only known once the new document is deployed.
+#[derive(Clone)]
+pub struct JwtVerifier {
+    keys: Arc<Vec<VerificationKey>>,
+    issuer: String,
+    audience: String,
+}
+
+impl JwtVerifier {
+    // public keys only (RSA, EC, Ed25519), a shared secret would let any holder issue tokens
+    pub fn new(jwks: &JwkSet, issuer: &str, audience: &str) -> Result<Self> {
+        let mut keys: Vec<VerificationKey> = vec![];
+        for jwk in &jwks.keys {
+            if let AlgorithmParameters::OctetKey(_) = jwk.algorithm {
+                bail!("Symmetric keys are not supported: {:?}", jwk.common.key_id);
+            }
+            keys.push(VerificationKey {
+                key_id: jwk.common.key_id.clone(),
+                algorithm: jwk.common.key_algorithm.map(|algorithm| algorithm.to_string()),
+                key: DecodingKey::from_jwk(jwk).context("Invalid JWK")?,
+            });
+        }
+        if keys.is_empty() {
+            bail!("No keys in the JWKS");
+        }
+        Ok(Self { keys: Arc::new(keys), issuer: issuer.to_owned(), audience: audience.to_owned() })
+    }
+
+    pub fn from_env() -> Result<Self> {
+        let (Ok(jwks), Ok(issuer), Ok(audience)) = (std::env::var(AUTH_JWKS_KEY), std::env::var(AUTH_ISSUER_KEY), std::env::var(AUTH_AUDIENCE_KEY)) else {
+            bail!("Environment variables not defined: {}, {}, {}", AUTH_JWKS_KEY, AUTH_ISSUER_KEY, AUTH_AUDIENCE_KEY);
+        };
+        let jwks: JwkSet = serde_json::from_str(&jwks).context("Invalid JWKS")?;
+        Self::new(&jwks, &issuer, &audience)
+    }
+
+    pub fn verify(&self, token: &str) -> Result<Claims> {
+        let header = decode_header(token)?;
+        // key of the `kid`, or the only key for tokens without one
+        let key = match &header.kid {
+            Some(key_id) => self.keys.iter().find(|key| key.key_id.as_ref() == Some(key_id)),
+            None if self.keys.len() == 1 => self.keys.first(),
+            None => None,
+        };
+        let Some(key) = key else {
+            bail!("Unknown signing key: {:?}", header.kid);
+        };
+        if let Some(algorithm) = &key.algorithm {
+            if *algorithm != format!("{:?}", header.alg) {
+                bail!("Unexpected algorithm: {:?}", header.alg);
+            }
+        }
+        if matches!(header.alg, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) {
+            bail!("Unexpected algorithm: {:?}", header.alg);
+        }
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let claims = decode::<Claims>(token, &key.key, &validation)?.claims;
+        if claims.sub.is_empty() {
+            bail!("Empty subject");
+        }
+        Ok(claims)
+    }
+}
+
+// bearer token of the Authorization header
+fn bearer_token(request: &Request) -> Result<&str> {
+    let header = request.headers().get(AUTHORIZATION).ok_or(anyhow!("Missing Authorization header"))?;
+    let header = header.to_str().context("Invalid Authorization header")?;
+    header.strip_prefix("Bearer ").map(str::trim).filter(|token| !token.is_empty()).ok_or(anyhow!("Authorization header is not a bearer token"))
+}
+
+// middleware: 401 without a valid token, otherwise the `Caller` is added to the request extensions
+pub async fn authenticate(State(verifier): State<JwtVerifier>, mut request: Request, next: Next) -> Response {
+    let claims = match bearer_token(&request).and_then(|token| verifier.verify(token)) {
+        Ok(claims) => claims,
+        Err(err) => {
+            return build_error_response_with_status(&format!("Unauthorized: {}", err), StatusCode::UNAUTHORIZED);
+        },
+    };
+
+    request.extensions_mut().insert(Caller { user_id: claims.sub });
+    next.run(request).await
+}
+
+
+#[cfg(test)]
+pub mod tests {
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use lib::common_structs::current_timestamp;
+
+    use super::*;
+
+    pub const TEST_ISSUER: &str = "https://issuer.test";
+    pub const TEST_AUDIENCE: &str = "test-client";
+    const TEST_JWKS: &str = include_str!("../fixtures/test_auth_jwks.json");
+    const TEST_KEY: &[u8] = include_bytes!("../fixtures/test_auth_key.pem");
+
+    pub fn test_verifier() -> JwtVerifier {
+        JwtVerifier::new(&serde_json::from_str(TEST_JWKS).unwrap(), TEST_ISSUER, TEST_AUDIENCE).unwrap()
+    }
+
+    fn test_claims(user_id: &str) -> Claims {
+        Claims { sub: user_id.to_owned(), iss: TEST_ISSUER.to_owned(), aud: Audience::Single(TEST_AUDIENCE.to_owned()), exp: current_timestamp() + 3600 }
+    }
+
+    fn sign(claims: &Claims, key_id: Option<&str>) -> String {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = key_id.map(str::to_owned);
+        encode(&header, claims, &EncodingKey::from_rsa_pem(TEST_KEY).unwrap()).unwrap()
+    }
+
+    pub fn test_token(user_id: &str) -> String {
+        sign(&test_claims(user_id), Some("test-key"))
+    }
+
+    #[test]
+    fn verify_returns_claims_of_valid_token() {
+        let verifier = test_verifier();
+        assert_eq!(verifier.verify(&test_token("user-1")).unwrap().sub, "user-1");
+        // the only key is used without a kid
+        assert_eq!(verifier.verify(&sign(&test_claims("user-1"), None)).unwrap().sub, "user-1");
+        let audiences = Claims { aud: Audience::Multiple(vec!["another-client".to_owned(), TEST_AUDIENCE.to_owned()]), ..test_claims("user-1") };
+        assert_eq!(verifier.verify(&sign(&audiences, Some("test-key"))).unwrap().sub, "user-1");
+    }
+
+    #[test]
+    fn verify_rejects_invalid_tokens() {
+        let verifier = test_verifier();
+
+        let expired = Claims { exp: current_timestamp() - 3600, ..test_claims("user-1") };
+        let other_issuer = Claims { iss: "https://another-issuer.test".to_owned(), ..test_claims("user-1") };
+        let other_audience = Claims { aud: Audience::Single("another-client".to_owned()), ..test_claims("user-1") };
+        let other_audiences = Claims { aud: Audience::Multiple(vec!["another-client".to_owned()]), ..test_claims("user-1") };
+        for claims in [expired, other_issuer, other_audience, other_audiences, test_claims("")] {
+            assert!(verifier.verify(&sign(&claims, Some("test-key"))).is_err());
+        }
+        assert!(verifier.verify(&sign(&test_claims("user-1"), Some("another-key"))).is_err());
+
+        // modified payload
+        let token = test_token("user-1");
+        let other_token = test_token("user-2");
+        let parts: Vec<&str> = token.split('.').collect();
+        let other_parts: Vec<&str> = other_token.split('.').collect();
+        assert!(verifier.verify(&format!("{}.{}.{}", parts[0], other_parts[1], parts[2])).is_err());
+
+        // signed with the public key as an HMAC secret
+        let hmac_token = encode(&Header::new(Algorithm::HS256), &test_claims("user-1"), &EncodingKey::from_secret(TEST_JWKS.as_bytes())).unwrap();
+        assert!(verifier.verify(&hmac_token).is_err());
+
+        assert!(verifier.verify("not-a-token").is_err());
+    }
+
+    #[test]
+    fn verifier_rejects_symmetric_keys() {
+        let jwks: JwkSet = serde_json::from_value(serde_json::json!({
+            "keys": [{ "kty": "oct", "kid": "secret", "k": "c2VjcmV0" }]
+        })).unwrap();
+        assert!(JwtVerifier::new(&jwks, TEST_ISSUER, TEST_AUDIENCE).is_err());
+    }
+}
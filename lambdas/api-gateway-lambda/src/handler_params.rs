@@ -1,10 +1,31 @@
+use lib::analytics::heatmap::HeatmapConfig;
+use lib::analytics::occupancy::OccupancyBucketSize;
+use lib::analytics::tripwires::Tripwire;
+use lib::analytics::zones::Zone;
+use lib::common_structs::{AnalysisType, JobDetails, JobStatus, SortOrder, TrackingPostProcessing};
+use lib::exports::columnar::ColumnarFormat;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub struct GetJobsQueryParams {
-    pub job_id: String,
-    pub request_timestamp: u64
+    // next_cursor of the previous page
+    pub cursor: Option<String>,
+    // deprecated: last evaluated key of the previous page, use cursor
+    pub job_id: Option<String>,
+    pub request_timestamp: Option<u64>,
+    // jobs with this tag
+    pub tag: Option<String>,
+    pub status: Option<JobStatus>,
+    // request timestamp range (seconds), both inclusive
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    // person tracking jobs with at least this many detections
+    pub min_detections: Option<usize>,
+    // jobs read for the page, before the filters apply (1 to 100)
+    pub limit: Option<i32>,
+    // by request timestamp, default: desc
+    pub order: Option<SortOrder>
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -14,13 +35,110 @@ pub struct UploadPresignURLQueryParams {
     pub filename: String
 }
 
-// start_analysis
+// get_range_summary
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
+pub struct GetRangeSummaryQueryParams {
+    // in millisecond, default: beginning of the video
+    pub start_ms: Option<i64>,
+    // in millisecond, default: end of the video
+    pub end_ms: Option<i64>
+}
+
+// get_results_url
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultsFormat {
+    Json,
+    Csv,
+    // MOTChallenge gt.txt and det.txt
+    Mot,
+    Coco,
+    // only for jobs started with `columnar_formats`
+    Parquet,
+    Arrow
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub struct GetResultsURLQueryParams {
+    // default: json
+    pub format: Option<ResultsFormat>
+}
+
+// get_results_chunk_url
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub struct GetResultsChunkURLQueryParams {
+    // in millisecond
+    pub timestamp_ms: i64
+}
+
+// get_heatmap_url
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HeatmapFormat {
+    Png,
+    Json
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub struct GetHeatmapURLQueryParams {
+    // default: png
+    pub format: Option<HeatmapFormat>
+}
+
+// get_occupancy
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub struct GetOccupancyQueryParams {
+    // default: 1s
+    pub bucket: Option<OccupancyBucketSize>
+}
+
+// start_analysis, the job is registered for the user of the token
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
 pub struct StartAnalysisBodyParams {
-    pub user_id: String,
     pub s3_folder_name: String,
-    pub filename: String
+    pub filename: String,
+    // default: person_tracking
+    #[serde(default)]
+    pub analysis_type: Option<AnalysisType>,
+    // several analyses of the video in parallel (instead of analysis_type), one child job each
+    #[serde(default)]
+    pub analysis_types: Option<Vec<AnalysisType>>,
+    // options below are for person tracking only
+    // optional regions of interest
+    #[serde(default)]
+    pub zones: Option<Vec<Zone>>,
+    // optional line crossing counters
+    #[serde(default)]
+    pub tripwires: Option<Vec<Tripwire>>,
+    // optional heatmap grid resolution and anchor
+    #[serde(default)]
+    pub heatmap: Option<HeatmapConfig>,
+    // optional interpolation and smoothing of the tracking results
+    #[serde(default)]
+    pub post_processing: Option<TrackingPostProcessing>,
+    // optional columnar results (parquet, arrow) written next to the JSON results
+    #[serde(default)]
+    pub columnar_formats: Option<Vec<ColumnarFormat>>
+}
+
+// put_zones
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct PutZonesBodyParams {
+    pub zones: Vec<Zone>
+}
+
+// put_tripwires
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct PutTripwiresBodyParams {
+    pub tripwires: Vec<Tripwire>
 }
 
 // #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -30,7 +148,10 @@ pub struct StartAnalysisBodyParams {
 //     Get,
 // }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
-pub struct PutTitleParams {
-    pub title: String,
+// put_job: title, description, tags and location, fields not set are left unchanged
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct PutJobBodyParams {
+    #[serde(flatten)]
+    pub details: JobDetails
 }
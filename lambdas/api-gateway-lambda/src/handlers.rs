@@ -1,4 +1,8 @@
-use axum::extract::{Query, State};
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use axum::body::Bytes;
+use axum::extract::{Extension, Query, State};
 use axum::http::header::CONTENT_TYPE;
 use axum::http::{HeaderMap, StatusCode};
 use axum::response::{IntoResponse, Response};
@@ -6,18 +10,31 @@ use axum::{
     extract::Path,
     response::Json,
 };
-use lib::common_structs::{JobStatus, LastEvaluatedKey};
-use lib::constants::{PRESIGNED_VALID_DURATION_UPLOAD, PRESIGNED_VALID_DURATION_VIEW, RESULTS_JSON_KEY};
-use lib::env_keys::{ROLE_ARN_KEY, S3_BUCKET_NAME_KEY, TABLE_NAME_KEY, TOPIC_ARN_KEY};
-use lib::common_service::CommonService;
+use lib::analytics::occupancy::{Occupancy, OccupancyBucketSize};
+use lib::analytics::trajectories::Trajectory;
+use lib::analytics::tripwires::{Tripwire, TripwireResult, TripwireSummary};
+use lib::analytics::zones::{Zone, ZoneSummary};
+use lib::analytics::PersonSample;
+use lib::common_structs::{AnalysisType, ChildJob, CombinedSummary, JobAttempt, JobFilter, JobQuery, JobStatus, LastEvaluatedKey, RekognitionJobTableEntry, TrackingResults, TrackingSummary};
+use lib::exports::chunks::ResultsManifest;
+use lib::exports::columnar::ColumnarFormat;
+use lib::constants::{CAPTIONS_VTT_KEY, COCO_JSON_KEY, DETECTIONS_VTT_KEY, HEATMAP_JSON_KEY, HEATMAP_PNG_KEY, MAX_JOBS_PAGE_SIZE, MOT_DET_TXT_KEY, MOT_GT_TXT_KEY, OCCUPANCY_JSON_KEY, PERSON_SUMMARIES_CSV_KEY, PRESIGNED_VALID_DURATION_UPLOAD, PRESIGNED_VALID_DURATION_VIEW, RESULTS_ARROW_KEY, RESULTS_CSV_KEY, RESULTS_JSON_KEY, RESULTS_MANIFEST_JSON_KEY, RESULTS_PARQUET_KEY, TRAJECTORIES_JSON_KEY, TRIPWIRES_JSON_KEY, ZONES_JSON_KEY};
+use lib::env_keys::{CURSOR_SECRET_KEY, ROLE_ARN_KEY, S3_BUCKET_NAME_KEY, TABLE_NAME_KEY, TOPIC_ARN_KEY};
+use lib::common_service::{CommonService, JobRepository, ObjectStore, VideoAnalyzer};
+use lib::cursor::{decode_cursor, encode_cursor};
 use serde_json::json;
 use uuid::Uuid;
 
 
-use crate::handler_params::{ GetJobsQueryParams, StartAnalysisBodyParams, UploadPresignURLQueryParams};
+use crate::auth::Caller;
+use crate::handler_params::{ GetHeatmapURLQueryParams, GetResultsChunkURLQueryParams, GetResultsURLQueryParams, ResultsFormat, GetJobsQueryParams, HeatmapFormat, GetOccupancyQueryParams, GetRangeSummaryQueryParams, PutJobBodyParams, PutTripwiresBodyParams, PutZonesBodyParams, StartAnalysisBodyParams, UploadPresignURLQueryParams};
 
 
 fn build_error_response(message: &str) -> Response {
+    return build_error_response_with_status(message, StatusCode::BAD_REQUEST);
+}
+
+pub(crate) fn build_error_response_with_status(message: &str, status: StatusCode) -> Response {
     let mut json_header = HeaderMap::new();
     json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
 
@@ -25,20 +42,41 @@ fn build_error_response(message: &str) -> Response {
         "success": false,
         "message": message
     }).to_string());
-    *response.status_mut() = StatusCode::BAD_REQUEST;
+    *response.status_mut() = status;
     return (json_header, response).into_response();
 }
 
+// entry of a job of the caller, 403 for the jobs of other users
+async fn get_caller_entry<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: &CommonService<S, D, R>,
+    table_name: &str,
+    job_id: &str,
+    caller: &Caller
+) -> Result<RekognitionJobTableEntry, Response> {
+    let dynamo_entry = match service.dynamo.get_entry_single(table_name, job_id).await {
+        Ok(entry) => entry,
+        Err(err) => {
+            return Err(build_error_response(&format!("Error getting job. Error: {}", err)));
+        },
+    };
+    if dynamo_entry.user_id != caller.user_id {
+        return Err(build_error_response_with_status("The job belongs to another user", StatusCode::FORBIDDEN));
+    }
+    Ok(dynamo_entry)
+}
+
 
-pub async fn get_upload_url(
-    State(service): State<CommonService>,
+// the upload folder belongs to the caller: `{user_id}/{uuid}`, only the caller can start an analysis of it
+pub async fn get_upload_url<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    State(service): State<CommonService<S, D, R>>,
+    Extension(caller): Extension<Caller>,
     Query(params): Query<UploadPresignURLQueryParams>,
 ) -> Response {
     let Ok(bucket_name) = std::env::var(S3_BUCKET_NAME_KEY) else {
         return build_error_response("Environment variables not defined.");
     };
 
-    let s3_folder = Uuid::new_v4().to_string();
+    let s3_folder = format!("{}/{}", caller.user_id, Uuid::new_v4());
     let s3_key: String = format!("{}/{}", s3_folder, params.filename);
 
     let url = match service.s3.put_object_presigned(&bucket_name, &s3_key, &params.content_type).await {
@@ -64,33 +102,120 @@ pub async fn get_upload_url(
 
 
 
-pub async fn start_analysis(
-    State(service): State<CommonService>,
+pub async fn start_analysis<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    State(service): State<CommonService<S, D, R>>,
+    Extension(caller): Extension<Caller>,
     Json(params): Json<StartAnalysisBodyParams>
 ) -> Response {
     let (Ok(bucket_name), Ok(role_arn), Ok(topic_arn), Ok(table_name)) = (std::env::var(S3_BUCKET_NAME_KEY), std::env::var(ROLE_ARN_KEY), std::env::var(TOPIC_ARN_KEY), std::env::var(TABLE_NAME_KEY)) else {
         return build_error_response("Environment variables not defined.");
     };
 
-    let s3_key: String = format!("{}/{}", params.s3_folder_name, params.filename);
+    // upload folder of another user (or not issued by `get_upload_url`)
+    let is_caller_folder = params.s3_folder_name.strip_prefix(&format!("{}/", caller.user_id)).is_some_and(|folder| !folder.is_empty() && !folder.contains('/'));
+    if !is_caller_folder {
+        return build_error_response_with_status("The upload folder belongs to another user", StatusCode::FORBIDDEN);
+    }
 
-    let job_id = match service.rekognition.start_tracking(&bucket_name, &s3_key, &role_arn, &topic_arn).await {
-        Ok(job_id) => job_id,
-        Err(err) => {
-            return build_error_response(&format!("Error start tracking: {}", err.to_string()));
+    let analysis_types = match (params.analysis_type, &params.analysis_types) {
+        (Some(_), Some(_)) => {
+            return build_error_response("Set either analysis_type or analysis_types");
         },
+        (_, Some(analysis_types)) => analysis_types.clone(),
+        (analysis_type, None) => vec![analysis_type.unwrap_or_default()],
     };
+    if analysis_types.is_empty() {
+        return build_error_response("analysis_types should not be empty");
+    }
+    for (i, analysis_type) in analysis_types.iter().enumerate() {
+        if analysis_types[..i].contains(analysis_type) {
+            return build_error_response(&format!("Duplicated analysis type: {:?}", analysis_type));
+        }
+    }
 
-    let result = service.dynamo.register_entry(&table_name, &params.user_id, &job_id, &params.s3_folder_name, &params.filename).await;
-    if result.is_err() {
-        return build_error_response(&format!("Error putting to dynamo: {}", result.err().unwrap().to_string()));
+    // options apply to the person tracking job
+    let has_tracking_options = params.zones.is_some() || params.tripwires.is_some() || params.heatmap.is_some() || params.post_processing.is_some() || params.columnar_formats.is_some();
+    if !analysis_types.contains(&AnalysisType::PersonTracking) && has_tracking_options {
+        return build_error_response(&format!("Zones, tripwires, heatmap, post processing and columnar formats are not available for {:?} jobs", analysis_types[0]));
+    }
+
+    if let Some(zones) = &params.zones {
+        if let Err(err) = Zone::validate(zones) {
+            return build_error_response(&format!("Invalid zones: {}", err));
+        }
+    }
+    if let Some(tripwires) = &params.tripwires {
+        if let Err(err) = Tripwire::validate(tripwires) {
+            return build_error_response(&format!("Invalid tripwires: {}", err));
+        }
     }
+    if let Some(heatmap) = &params.heatmap {
+        if let Err(err) = heatmap.validate() {
+            return build_error_response(&format!("Invalid heatmap: {}", err));
+        }
+    }
+    if let Some(post_processing) = &params.post_processing {
+        if let Err(err) = post_processing.validate() {
+            return build_error_response(&format!("Invalid post processing: {}", err));
+        }
+    }
+
+    let s3_key: String = format!("{}/{}", params.s3_folder_name, params.filename);
 
     let mut json_header = HeaderMap::new();
     json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
 
+    if params.analysis_types.is_none() {
+        let job_id = match start_rekognition_job(&service.rekognition, analysis_types[0], &bucket_name, &s3_key, &role_arn, &topic_arn, None).await {
+            Ok(job_id) => job_id,
+            Err(err) => {
+                return build_error_response(&format!("Error start tracking: {}", err));
+            },
+        };
+        if let Err(err) = register_job(&service, &table_name, &caller.user_id, &params, &job_id, params.analysis_type).await {
+            return build_error_response(&err.to_string());
+        }
+
+        let response = Response::new(json!({
+            "job_id": job_id
+        }).to_string());
+
+        return (json_header, response).into_response();
+    }
+
+    // multi-analysis job: the parent entry tracks the status of a child job per analysis type
+    let parent_job_id = Uuid::new_v4().to_string();
+    let result = service.dynamo.register_entry(&table_name, &caller.user_id, &parent_job_id, &params.s3_folder_name, &params.filename).await;
+    if result.is_err() {
+        return build_error_response(&format!("Error putting to dynamo: {}", result.err().unwrap().to_string()));
+    }
+    if let Err(err) = service.dynamo.update_children(&table_name, &parent_job_id, &BTreeMap::new()).await {
+        return build_error_response(&format!("Error putting children to dynamo: {}", err));
+    }
+
+    // each child is registered as soon as its Rekognition job is started, so that no started job is left without an entry
+    // on any error, the started children are failed
+    let mut children: BTreeMap<String, ChildJob> = BTreeMap::new();
+    for analysis_type in analysis_types {
+        let result = match start_rekognition_job(&service.rekognition, analysis_type, &bucket_name, &s3_key, &role_arn, &topic_arn, None).await {
+            Ok(job_id) => {
+                let child = ChildJob { analysis_type, job_status: JobStatus::InProgress };
+                children.insert(job_id.clone(), child.clone());
+                register_child_job(&service, &table_name, &caller.user_id, &params, &parent_job_id, &job_id, &child).await
+            },
+            Err(err) => Err(anyhow!("Error start tracking: {}", err)),
+        };
+        if let Err(err) = result {
+            if let Err(err) = fail_started_children(&service, &table_name, &parent_job_id, &children).await {
+                return build_error_response(&format!("Error failing the started jobs of {}: {}", parent_job_id, err));
+            }
+            return build_error_response(&err.to_string());
+        }
+    }
+
     let response = Response::new(json!({
-        "job_id": job_id
+        "job_id": parent_job_id,
+        "children": children
     }).to_string());
 
     return (json_header, response).into_response();
@@ -98,19 +223,124 @@ pub async fn start_analysis(
 }
 
 
+// entry of a started child job, then its link to the multi-analysis job
+async fn register_child_job<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: &CommonService<S, D, R>,
+    table_name: &str,
+    user_id: &str,
+    params: &StartAnalysisBodyParams,
+    parent_job_id: &str,
+    job_id: &str,
+    child: &ChildJob
+) -> anyhow::Result<()> {
+    register_job(service, table_name, user_id, params, job_id, Some(child.analysis_type)).await?;
+    service.dynamo.update_parent_job_id(table_name, job_id, parent_job_id).await
+        .map_err(|err| anyhow!("Error putting parent job id to dynamo: {}", err))?;
+    service.dynamo.update_child_job(table_name, parent_job_id, job_id, child).await
+        .map_err(|err| anyhow!("Error putting children to dynamo: {}", err))?;
+    Ok(())
+}
+
+// a multi-analysis job failed to start: the started children are failed (and can be retried) with the multi-analysis job,
+// which is deleted if none was started
+// children whose entry was not registered are only failed in the multi-analysis job
+async fn fail_started_children<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: &CommonService<S, D, R>,
+    table_name: &str,
+    parent_job_id: &str,
+    children: &BTreeMap<String, ChildJob>
+) -> anyhow::Result<()> {
+    if children.is_empty() {
+        return service.dynamo.delete_entry(table_name, parent_job_id).await;
+    }
+    let mut failed_children: BTreeMap<String, ChildJob> = BTreeMap::new();
+    for (child_job_id, child) in children {
+        service.dynamo.update_job_status_if(table_name, child_job_id, JobStatus::Failed, JobStatus::InProgress).await?;
+        failed_children.insert(child_job_id.to_owned(), ChildJob { job_status: JobStatus::Failed, ..child.clone() });
+    }
+    service.dynamo.update_children(table_name, parent_job_id, &failed_children).await?;
+    service.dynamo.update_job_status(table_name, parent_job_id, JobStatus::Failed).await
+}
+
+
+// register the entry of a started Rekognition job with its options (person tracking only)
+async fn register_job<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: &CommonService<S, D, R>,
+    table_name: &str,
+    user_id: &str,
+    params: &StartAnalysisBodyParams,
+    job_id: &str,
+    analysis_type: Option<AnalysisType>
+) -> anyhow::Result<()> {
+    service.dynamo.register_entry(table_name, user_id, job_id, &params.s3_folder_name, &params.filename).await
+        .map_err(|err| anyhow!("Error putting to dynamo: {}", err))?;
+
+    if let Some(analysis_type) = analysis_type {
+        service.dynamo.update_analysis_type(table_name, job_id, analysis_type).await
+            .map_err(|err| anyhow!("Error putting analysis type to dynamo: {}", err))?;
+    }
+    if analysis_type.unwrap_or_default() != AnalysisType::PersonTracking {
+        return Ok(());
+    }
+
+    if let Some(zones) = &params.zones {
+        service.dynamo.update_zones(table_name, job_id, zones).await
+            .map_err(|err| anyhow!("Error putting zones to dynamo: {}", err))?;
+    }
+    if let Some(tripwires) = &params.tripwires {
+        service.dynamo.update_tripwires(table_name, job_id, tripwires).await
+            .map_err(|err| anyhow!("Error putting tripwires to dynamo: {}", err))?;
+    }
+    if let Some(heatmap) = &params.heatmap {
+        service.dynamo.update_heatmap_config(table_name, job_id, heatmap).await
+            .map_err(|err| anyhow!("Error putting heatmap to dynamo: {}", err))?;
+    }
+    if let Some(post_processing) = &params.post_processing {
+        service.dynamo.update_post_processing(table_name, job_id, post_processing).await
+            .map_err(|err| anyhow!("Error putting post processing to dynamo: {}", err))?;
+    }
+    if let Some(columnar_formats) = &params.columnar_formats {
+        service.dynamo.update_columnar_formats(table_name, job_id, columnar_formats).await
+            .map_err(|err| anyhow!("Error putting columnar formats to dynamo: {}", err))?;
+    }
+    Ok(())
+}
+
+
+// start the Rekognition job for the analysis type, returns the job id
+// job_tag: job id of the entry for a retried job
+async fn start_rekognition_job<R: VideoAnalyzer>(
+    rekognition: &R,
+    analysis_type: AnalysisType,
+    bucket_name: &str,
+    s3_key: &str,
+    role_arn: &str,
+    topic_arn: &str,
+    job_tag: Option<&str>
+) -> anyhow::Result<String> {
+    match analysis_type {
+        AnalysisType::PersonTracking => rekognition.start_tracking(bucket_name, s3_key, role_arn, topic_arn, job_tag).await,
+        AnalysisType::LabelDetection => rekognition.start_label_detection(bucket_name, s3_key, role_arn, topic_arn, job_tag).await,
+        AnalysisType::FaceDetection => rekognition.start_face_detection(bucket_name, s3_key, role_arn, topic_arn, job_tag).await,
+        AnalysisType::SegmentDetection => rekognition.start_segment_detection(bucket_name, s3_key, role_arn, topic_arn, job_tag).await,
+    }
+}
+
+
 // get video url for display
-pub async fn get_video_url(
-    State(service): State<CommonService>,
+pub async fn get_video_url<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    State(service): State<CommonService<S, D, R>>,
+    Extension(caller): Extension<Caller>,
     Path(job_id): Path<String>,
 ) -> Response {
     let (Ok(bucket_name), Ok(table_name)) = (std::env::var(S3_BUCKET_NAME_KEY), std::env::var(TABLE_NAME_KEY)) else {
         return build_error_response("Environment variables not defined.");
     };
 
-    let dynamo_entry = match service.dynamo.get_entry_single(&table_name, &job_id).await {
+    let dynamo_entry = match get_caller_entry(&service, &table_name, &job_id, &caller).await {
         Ok(entry) => entry,
-        Err(err) => {
-            return build_error_response(&format!("Error getting job. Error: {}", err));
+        Err(response) => {
+            return response;
         },
     };
 
@@ -123,11 +353,26 @@ pub async fn get_video_url(
         },
     };
 
+    // WebVTT tracks (`<track>` elements of the video), once the analysis has finished
+    let mut tracks = serde_json::Map::new();
+    if dynamo_entry.job_status == JobStatus::Succeeded && dynamo_entry.analysis_type.unwrap_or_default() == AnalysisType::PersonTracking && dynamo_entry.children.is_none() {
+        for (kind, key) in [("metadata", DETECTIONS_VTT_KEY), ("captions", CAPTIONS_VTT_KEY)] {
+            let s3_key = format!("{}/{}", dynamo_entry.s3_folder_name, key);
+            match service.s3.get_object_presigned(&bucket_name, &s3_key).await {
+                Ok(url) => tracks.insert(kind.to_owned(), url.into()),
+                Err(err) => {
+                    return build_error_response(&format!("Error getting presgined url: {}.", err));
+                },
+            };
+        }
+    }
+
     let mut json_header = HeaderMap::new();
     json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
 
     let response = Response::new(json!({
         "url": url,
+        "tracks": tracks,
         "expired_in": PRESIGNED_VALID_DURATION_VIEW
     }).to_string());
 
@@ -135,24 +380,172 @@ pub async fn get_video_url(
 
 }
 
+// files of each results format, the first one is the main file (`url`)
+fn results_keys(format: &ResultsFormat) -> Vec<&'static str> {
+    match format {
+        ResultsFormat::Json => vec![RESULTS_JSON_KEY],
+        ResultsFormat::Csv => vec![RESULTS_CSV_KEY, PERSON_SUMMARIES_CSV_KEY],
+        ResultsFormat::Mot => vec![MOT_GT_TXT_KEY, MOT_DET_TXT_KEY],
+        ResultsFormat::Coco => vec![COCO_JSON_KEY],
+        ResultsFormat::Parquet => vec![RESULTS_PARQUET_KEY],
+        ResultsFormat::Arrow => vec![RESULTS_ARROW_KEY],
+    }
+}
+
 // get job results
-pub async fn get_results_url(State(service): State<CommonService>, Path(job_id): Path<String>) -> Response {
+pub async fn get_results_url<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(State(service): State<CommonService<S, D, R>>, Extension(caller): Extension<Caller>, Path(job_id): Path<String>, Query(params): Query<GetResultsURLQueryParams>) -> Response {
 
     let (Ok(bucket_name), Ok(table_name)) = (std::env::var(S3_BUCKET_NAME_KEY), std::env::var(TABLE_NAME_KEY)) else {
         return build_error_response("Environment variables not defined.");
     };
 
-    let dynamo_entry = match service.dynamo.get_entry_single(&table_name, &job_id).await {
+    let dynamo_entry = match get_caller_entry(&service, &table_name, &job_id, &caller).await {
         Ok(entry) => entry,
-        Err(err) => {
-            return build_error_response(&format!("Error getting job. Error: {}", err));
+        Err(response) => {
+            return response;
+        },
+    };
+    // multi-analysis jobs: results of the succeeded children
+    let is_partial_parent = dynamo_entry.children.is_some() && dynamo_entry.job_status == JobStatus::PartiallySucceeded;
+    if dynamo_entry.job_status != JobStatus::Succeeded && !is_partial_parent {
+        return build_error_response(&format!("Cannot get results for {:?} jobs", dynamo_entry.job_status));
+    }
+
+    let format = params.format.unwrap_or(ResultsFormat::Json);
+    let analysis_type = dynamo_entry.analysis_type.unwrap_or_default();
+    if analysis_type != AnalysisType::PersonTracking && format != ResultsFormat::Json {
+        return build_error_response(&format!("Only json results are available for {:?} jobs", analysis_type));
+    }
+    if dynamo_entry.children.is_some() && format != ResultsFormat::Json {
+        return build_error_response("Only json results are available for multi-analysis jobs, other formats are on the person tracking child");
+    }
+    let columnar_format = match format {
+        ResultsFormat::Parquet => Some(ColumnarFormat::Parquet),
+        ResultsFormat::Arrow => Some(ColumnarFormat::Arrow),
+        _ => None,
+    };
+    if let Some(columnar_format) = columnar_format {
+        if !dynamo_entry.columnar_formats.unwrap_or_default().contains(&columnar_format) {
+            return build_error_response(&format!("{:?} results not enabled for the job", columnar_format));
+        }
+    }
+
+    // file name: url
+    let mut urls = serde_json::Map::new();
+    let keys = match (&dynamo_entry.children, analysis_type) {
+        (Some(children), _) => children
+            .values()
+            .filter(|child| child.job_status == JobStatus::Succeeded)
+            .map(|child| child.analysis_type.results_key())
+            .collect(),
+        (None, AnalysisType::PersonTracking) => results_keys(&format),
+        (None, _) => vec![analysis_type.results_key()],
+    };
+    for key in &keys {
+        let s3_key = format!("{}/{}", dynamo_entry.s3_folder_name, key);
+        match service.s3.get_object_presigned(&bucket_name, &s3_key).await {
+            Ok(url) => urls.insert(key.to_string(), url.into()),
+            Err(err) => {
+                return build_error_response(&format!("Error getting presgined url: {}.", err));
+            },
+        };
+    }
+
+    let mut json_header = HeaderMap::new();
+    json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+    let response = Response::new(json!({
+        "url": urls[keys[0]],
+        "urls": urls,
+        "expired_in": PRESIGNED_VALID_DURATION_VIEW
+    }).to_string());
+
+    return (json_header, response).into_response();
+}
+
+
+// get the URL of the results chunk covering a timestamp, to load the results during playback
+pub async fn get_results_chunk_url<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    State(service): State<CommonService<S, D, R>>,
+    Extension(caller): Extension<Caller>,
+    Path(job_id): Path<String>,
+    Query(params): Query<GetResultsChunkURLQueryParams>
+) -> Response {
+    let (Ok(bucket_name), Ok(table_name)) = (std::env::var(S3_BUCKET_NAME_KEY), std::env::var(TABLE_NAME_KEY)) else {
+        return build_error_response("Environment variables not defined.");
+    };
+
+    let dynamo_entry = match get_caller_entry(&service, &table_name, &job_id, &caller).await {
+        Ok(entry) => entry,
+        Err(response) => {
+            return response;
         },
     };
     if dynamo_entry.job_status != JobStatus::Succeeded {
         return build_error_response(&format!("Cannot get results for {:?} jobs", dynamo_entry.job_status));
     }
 
-    let s3_key = format!("{}/{}", dynamo_entry.s3_folder_name, RESULTS_JSON_KEY);
+    // jobs processed before chunks were introduced: use results_url
+    let manifest_key = format!("{}/{}", dynamo_entry.s3_folder_name, RESULTS_MANIFEST_JSON_KEY);
+    let manifest: ResultsManifest = match service.s3.get_object(&bucket_name, &manifest_key).await.and_then(|(bytes, _)| Ok(serde_json::from_slice(&bytes)?)) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            return build_error_response(&format!("Chunked results not available: {}", err));
+        },
+    };
+    let Some(chunk) = manifest.chunk_at(params.timestamp_ms) else {
+        return build_error_response(&format!("No results chunk for timestamp: {}", params.timestamp_ms));
+    };
+
+    let s3_key = format!("{}/{}", dynamo_entry.s3_folder_name, chunk.key);
+    let url = match service.s3.get_object_presigned(&bucket_name, &s3_key).await {
+        Ok(url) => url,
+        Err(err) => {
+            return build_error_response(&format!("Error getting presgined url: {}.", err));
+        },
+    };
+
+    let mut json_header = HeaderMap::new();
+    json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+    let response = Response::new(json!({
+        "url": url,
+        "chunk": chunk,
+        "chunk_count": manifest.chunks.len(),
+        "expired_in": PRESIGNED_VALID_DURATION_VIEW
+    }).to_string());
+
+    return (json_header, response).into_response();
+}
+
+
+// get heatmap URL (PNG image or JSON matrix)
+pub async fn get_heatmap_url<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    State(service): State<CommonService<S, D, R>>,
+    Extension(caller): Extension<Caller>,
+    Path(job_id): Path<String>,
+    Query(params): Query<GetHeatmapURLQueryParams>
+) -> Response {
+
+    let (Ok(bucket_name), Ok(table_name)) = (std::env::var(S3_BUCKET_NAME_KEY), std::env::var(TABLE_NAME_KEY)) else {
+        return build_error_response("Environment variables not defined.");
+    };
+
+    let dynamo_entry = match get_caller_entry(&service, &table_name, &job_id, &caller).await {
+        Ok(entry) => entry,
+        Err(response) => {
+            return response;
+        },
+    };
+    if dynamo_entry.job_status != JobStatus::Succeeded {
+        return build_error_response(&format!("Cannot get heatmap for {:?} jobs", dynamo_entry.job_status));
+    }
+
+    let key = match params.format.unwrap_or(HeatmapFormat::Png) {
+        HeatmapFormat::Png => HEATMAP_PNG_KEY,
+        HeatmapFormat::Json => HEATMAP_JSON_KEY,
+    };
+    let s3_key = format!("{}/{}", dynamo_entry.s3_folder_name, key);
 
     let url = match service.s3.get_object_presigned(&bucket_name, &s3_key).await {
         Ok(url) => url,
@@ -174,94 +567,1744 @@ pub async fn get_results_url(State(service): State<CommonService>, Path(job_id):
 
 
 // get job summary
-pub async fn get_summary(State(service): State<CommonService>, Path(job_id): Path<String>) -> Response {
+pub async fn get_summary<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(State(service): State<CommonService<S, D, R>>, Extension(caller): Extension<Caller>, Path(job_id): Path<String>) -> Response {
 
     let Ok(table_name) = std::env::var(TABLE_NAME_KEY) else {
         return build_error_response("Environment variables not defined.");
     };
 
-    let dynamo_entry = match service.dynamo.get_entry_single(&table_name, &job_id).await {
+    let dynamo_entry = match get_caller_entry(&service, &table_name, &job_id, &caller).await {
         Ok(entry) => entry,
-        Err(err) => {
-            return build_error_response(&format!("Error getting job. Error: {}", err));
+        Err(response) => {
+            return response;
         },
     };
 
     let mut json_header = HeaderMap::new();
     json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
 
+    // multi-analysis job: child entries and their merged results
+    if let Some(child_job_ids) = dynamo_entry.children.as_ref().map(|children| children.keys().cloned().collect::<Vec<String>>()) {
+        let mut children: Vec<RekognitionJobTableEntry> = vec![];
+        for child_job_id in child_job_ids {
+            match service.dynamo.get_entry_single(&table_name, &child_job_id).await {
+                Ok(entry) => children.push(entry),
+                Err(err) => {
+                    return build_error_response(&format!("Error getting child job. Error: {}", err));
+                },
+            };
+        }
+
+        let response = Response::new(json!({
+            "job": dynamo_entry,
+            "retryable": children.iter().any(|child| child.job_status.is_retryable()),
+            "children": children,
+            "combined_summary": CombinedSummary::new(&children),
+        }).to_string());
+
+        return (json_header, response).into_response();
+    }
+
     let response = Response::new(json!({
         "job": dynamo_entry,
+        // failed, timed out or cancelled
+        "retryable": dynamo_entry.job_status.is_retryable(),
     }).to_string());
 
     return (json_header, response).into_response();
 }
 
 
-// get all jobs
-// pub async fn get_all_jobs(State(service): State<CommonService>, Path(user_id): Path<String>, last_evaluated_key: Option<Json<Option<LastEvaluatedKey>>>) -> Response {
-pub async fn get_all_jobs(State(service): State<CommonService>, Path(user_id): Path<String>, last_evaluated_key: Option<Query<GetJobsQueryParams>>) -> Response {
+// tracking summary within a time range of a finished job, tracks are clipped to the range
+pub async fn get_range_summary<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    State(service): State<CommonService<S, D, R>>,
+    Extension(caller): Extension<Caller>,
+    Path(job_id): Path<String>,
+    Query(params): Query<GetRangeSummaryQueryParams>
+) -> Response {
+    let (Ok(bucket_name), Ok(table_name)) = (std::env::var(S3_BUCKET_NAME_KEY), std::env::var(TABLE_NAME_KEY)) else {
+        return build_error_response("Environment variables not defined.");
+    };
 
-    let Ok(table_name) = std::env::var(TABLE_NAME_KEY) else {
+    let start_ms = params.start_ms.unwrap_or(0);
+    let end_ms = params.end_ms.unwrap_or(i64::MAX);
+    if start_ms > end_ms {
+        return build_error_response("start_ms cannot be after end_ms.");
+    }
+
+    let dynamo_entry = match get_caller_entry(&service, &table_name, &job_id, &caller).await {
+        Ok(entry) => entry,
+        Err(response) => {
+            return response;
+        },
+    };
+    if dynamo_entry.job_status != JobStatus::Succeeded {
+        return build_error_response(&format!("Cannot get summary for {:?} jobs", dynamo_entry.job_status));
+    }
+
+    let samples = match load_samples(&service, &bucket_name, &dynamo_entry).await {
+        Ok(samples) => samples,
+        Err(err) => {
+            return build_error_response(&format!("Error getting tracking results: {}", err));
+        },
+    };
+    let tracking_summary = TrackingSummary::from_samples_in_range(&samples, start_ms, end_ms);
+
+    let mut json_header = HeaderMap::new();
+    json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+    let response = Response::new(json!({
+        "start_ms": params.start_ms,
+        "end_ms": params.end_ms,
+        "tracking_summary": tracking_summary
+    }).to_string());
+
+    return (json_header, response).into_response();
+}
+
+
+// number of persons present at the same time, per bucket, for a finished job
+pub async fn get_occupancy<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    State(service): State<CommonService<S, D, R>>,
+    Extension(caller): Extension<Caller>,
+    Path(job_id): Path<String>,
+    Query(params): Query<GetOccupancyQueryParams>
+) -> Response {
+    let (Ok(bucket_name), Ok(table_name)) = (std::env::var(S3_BUCKET_NAME_KEY), std::env::var(TABLE_NAME_KEY)) else {
         return build_error_response("Environment variables not defined.");
     };
 
-    let last_evaluated_key:Option<LastEvaluatedKey> = if let Some(last_evaluated_key) = last_evaluated_key {
-        // last_evaluated_key.0
-        Some(LastEvaluatedKey::new(&last_evaluated_key.0.job_id, &user_id, &last_evaluated_key.0.request_timestamp))
-    } else {
-        None
+    let dynamo_entry = match get_caller_entry(&service, &table_name, &job_id, &caller).await {
+        Ok(entry) => entry,
+        Err(response) => {
+            return response;
+        },
     };
+    if dynamo_entry.job_status != JobStatus::Succeeded {
+        return build_error_response(&format!("Cannot get occupancy for {:?} jobs", dynamo_entry.job_status));
+    }
 
-    let (jobs, last_evaluated_key) = match service.dynamo.query_entries(&table_name, &user_id, last_evaluated_key).await {
-        Ok(result) => result,
+    let occupancy = match get_or_build_occupancy(&service, &bucket_name, &dynamo_entry).await {
+        Ok(occupancy) => occupancy,
         Err(err) => {
-            return build_error_response(&format!("Error getting jobs: {}.", err));
+            return build_error_response(&format!("Error getting occupancy: {}", err));
         },
     };
 
+    let bucket_size = params.bucket.unwrap_or(OccupancyBucketSize::OneSecond);
+    let buckets = occupancy.series(bucket_size).map(|series| series.buckets.clone()).unwrap_or_default();
+
     let mut json_header = HeaderMap::new();
     json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
 
     let response = Response::new(json!({
-        "jobs": jobs,
-        "last_evaluated_key": last_evaluated_key
+        "bucket": bucket_size,
+        "peak": occupancy.peak,
+        "buckets": buckets
     }).to_string());
 
     return (json_header, response).into_response();
 }
 
+// saved occupancy, or calculated from the tracking results for jobs processed before it was introduced
+async fn get_or_build_occupancy<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: &CommonService<S, D, R>,
+    bucket_name: &str,
+    dynamo_entry: &RekognitionJobTableEntry
+) -> anyhow::Result<Occupancy> {
+    let occupancy_key = format!("{}/{}", dynamo_entry.s3_folder_name, OCCUPANCY_JSON_KEY);
+    if let Ok((bytes, _)) = service.s3.get_object(bucket_name, &occupancy_key).await {
+        return Ok(serde_json::from_slice(&bytes)?);
+    }
+
+    let samples = load_samples(service, bucket_name, dynamo_entry).await?;
+    let duration = dynamo_entry.video_metadata.as_ref().map(|metadata| metadata.duration).unwrap_or(0);
+    Ok(Occupancy::new(&samples, duration))
+}
 
 
-pub async fn delete_job(State(service): State<CommonService>, Path(job_id): Path<String>) -> Response {
+// path, speed, and first/last seen of a single person of a finished job
+pub async fn get_person<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(State(service): State<CommonService<S, D, R>>, Extension(caller): Extension<Caller>, Path((job_id, index)): Path<(String, i64)>) -> Response {
 
     let (Ok(bucket_name), Ok(table_name)) = (std::env::var(S3_BUCKET_NAME_KEY), std::env::var(TABLE_NAME_KEY)) else {
         return build_error_response("Environment variables not defined.");
     };
 
-    let dynamo_entry = match service.dynamo.get_entry_single(&table_name, &job_id).await {
+    let dynamo_entry = match get_caller_entry(&service, &table_name, &job_id, &caller).await {
         Ok(entry) => entry,
+        Err(response) => {
+            return response;
+        },
+    };
+    if dynamo_entry.job_status != JobStatus::Succeeded {
+        return build_error_response(&format!("Cannot get persons for {:?} jobs", dynamo_entry.job_status));
+    }
+
+    let trajectories = match get_or_build_trajectories(&service, &bucket_name, &dynamo_entry).await {
+        Ok(trajectories) => trajectories,
         Err(err) => {
-            return build_error_response(&format!("Error deleting job. Error: {}", err));
+            return build_error_response(&format!("Error getting trajectories: {}", err));
         },
     };
+    let Some(trajectory) = trajectories.into_iter().find(|trajectory| trajectory.index == index) else {
+        return build_error_response(&format!("Person does not exist for index: {}", index));
+    };
 
-    // delete s3
-    if service.s3.delete_object(&bucket_name, &dynamo_entry.s3_folder_name).await.is_err() {
-        return build_error_response(&format!("Error deleting s3 object"))
+    let mut json_header = HeaderMap::new();
+    json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+    let response = Response::new(json!({
+        "person": trajectory,
+    }).to_string());
+
+    return (json_header, response).into_response();
+}
+
+// saved trajectories, or calculated from the tracking results for jobs processed before they were introduced
+async fn get_or_build_trajectories<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: &CommonService<S, D, R>,
+    bucket_name: &str,
+    dynamo_entry: &RekognitionJobTableEntry
+) -> anyhow::Result<Vec<Trajectory>> {
+    let trajectories_key = format!("{}/{}", dynamo_entry.s3_folder_name, TRAJECTORIES_JSON_KEY);
+    if let Ok((bytes, _)) = service.s3.get_object(bucket_name, &trajectories_key).await {
+        return Ok(serde_json::from_slice(&bytes)?);
+    }
+
+    let samples = load_samples(service, bucket_name, dynamo_entry).await?;
+    Ok(Trajectory::new_vec(&samples))
+}
+
+
+// set regions of interest for a job
+// zone summaries are calculated when the job finishes, or right away if it has already finished
+pub async fn put_zones<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    State(service): State<CommonService<S, D, R>>,
+    Extension(caller): Extension<Caller>,
+    Path(job_id): Path<String>,
+    Json(params): Json<PutZonesBodyParams>
+) -> Response {
+    let (Ok(bucket_name), Ok(table_name)) = (std::env::var(S3_BUCKET_NAME_KEY), std::env::var(TABLE_NAME_KEY)) else {
+        return build_error_response("Environment variables not defined.");
     };
 
-    // delete dynamo
-    if service.dynamo.delete_entry(&table_name, &job_id).await.is_err() {
-        return build_error_response(&format!("Error deleting dynamo entry"))
+    if let Err(err) = Zone::validate(&params.zones) {
+        return build_error_response(&format!("Invalid zones: {}", err));
+    }
+
+    let dynamo_entry = match get_caller_entry(&service, &table_name, &job_id, &caller).await {
+        Ok(entry) => entry,
+        Err(response) => {
+            return response;
+        },
     };
+    if let Some(analysis_type) = dynamo_entry.analysis_type.filter(|analysis_type| *analysis_type != AnalysisType::PersonTracking) {
+        return build_error_response(&format!("Zones are not available for {:?} jobs", analysis_type));
+    }
+    if dynamo_entry.children.is_some() {
+        return build_error_response("Zones are set on the person tracking child of multi-analysis jobs");
+    }
+
+    if let Err(err) = service.dynamo.update_zones(&table_name, &job_id, &params.zones).await {
+        return build_error_response(&format!("Error putting zones to dynamo: {}", err));
+    }
+
+    let mut zone_summaries: Option<Vec<ZoneSummary>> = None;
+    if dynamo_entry.job_status == JobStatus::Succeeded {
+        zone_summaries = match update_zone_summaries(&service, &bucket_name, &table_name, &dynamo_entry, &params.zones).await {
+            Ok(zone_summaries) => Some(zone_summaries),
+            Err(err) => {
+                return build_error_response(&format!("Error calculating zone summaries: {}", err));
+            },
+        };
+    }
 
     let mut json_header = HeaderMap::new();
     json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
 
     let response = Response::new(json!({
-        "success": true,
+        "zones": params.zones,
+        "zone_summaries": zone_summaries
+    }).to_string());
+
+    return (json_header, response).into_response();
+}
+
+// recalculate zone summaries from the saved results
+async fn update_zone_summaries<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: &CommonService<S, D, R>,
+    bucket_name: &str,
+    table_name: &str,
+    dynamo_entry: &RekognitionJobTableEntry,
+    zones: &[Zone]
+) -> anyhow::Result<Vec<ZoneSummary>> {
+    let samples = load_samples(service, bucket_name, dynamo_entry).await?;
+    let (zone_summaries, zone_events) = ZoneSummary::new_vec(zones, &samples);
+
+    let zones_key = format!("{}/{}", dynamo_entry.s3_folder_name, ZONES_JSON_KEY);
+    service.s3.put_object(bucket_name, &zones_key, Bytes::from(serde_json::to_vec(&zone_events)?), "application/json").await?;
+    service.dynamo.update_zone_summaries(table_name, &dynamo_entry.job_id, &zone_summaries).await?;
+
+    Ok(zone_summaries)
+}
+
+
+// set line crossing counters for a job
+// crossings are counted when the job finishes, or right away if it has already finished
+pub async fn put_tripwires<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    State(service): State<CommonService<S, D, R>>,
+    Extension(caller): Extension<Caller>,
+    Path(job_id): Path<String>,
+    Json(params): Json<PutTripwiresBodyParams>
+) -> Response {
+    let (Ok(bucket_name), Ok(table_name)) = (std::env::var(S3_BUCKET_NAME_KEY), std::env::var(TABLE_NAME_KEY)) else {
+        return build_error_response("Environment variables not defined.");
+    };
+
+    if let Err(err) = Tripwire::validate(&params.tripwires) {
+        return build_error_response(&format!("Invalid tripwires: {}", err));
+    }
+
+    let dynamo_entry = match get_caller_entry(&service, &table_name, &job_id, &caller).await {
+        Ok(entry) => entry,
+        Err(response) => {
+            return response;
+        },
+    };
+    if let Some(analysis_type) = dynamo_entry.analysis_type.filter(|analysis_type| *analysis_type != AnalysisType::PersonTracking) {
+        return build_error_response(&format!("Tripwires are not available for {:?} jobs", analysis_type));
+    }
+    if dynamo_entry.children.is_some() {
+        return build_error_response("Tripwires are set on the person tracking child of multi-analysis jobs");
+    }
+
+    if let Err(err) = service.dynamo.update_tripwires(&table_name, &job_id, &params.tripwires).await {
+        return build_error_response(&format!("Error putting tripwires to dynamo: {}", err));
+    }
+
+    let mut tripwire_summaries: Option<Vec<TripwireSummary>> = None;
+    if dynamo_entry.job_status == JobStatus::Succeeded {
+        tripwire_summaries = match update_tripwire_results(&service, &bucket_name, &table_name, &dynamo_entry, &params.tripwires).await {
+            Ok(results) => Some(results.iter().map(TripwireResult::summary).collect()),
+            Err(err) => {
+                return build_error_response(&format!("Error counting tripwire crossings: {}", err));
+            },
+        };
+    }
+
+    let mut json_header = HeaderMap::new();
+    json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+    let response = Response::new(json!({
+        "tripwires": params.tripwires,
+        "tripwire_summaries": tripwire_summaries
     }).to_string());
 
     return (json_header, response).into_response();
-}
\ No newline at end of file
+}
+
+// in/out totals, per-minute counts and crossing events for each tripwire of a finished job
+pub async fn get_tripwires<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(State(service): State<CommonService<S, D, R>>, Extension(caller): Extension<Caller>, Path(job_id): Path<String>) -> Response {
+
+    let (Ok(bucket_name), Ok(table_name)) = (std::env::var(S3_BUCKET_NAME_KEY), std::env::var(TABLE_NAME_KEY)) else {
+        return build_error_response("Environment variables not defined.");
+    };
+
+    let dynamo_entry = match get_caller_entry(&service, &table_name, &job_id, &caller).await {
+        Ok(entry) => entry,
+        Err(response) => {
+            return response;
+        },
+    };
+    if dynamo_entry.job_status != JobStatus::Succeeded {
+        return build_error_response(&format!("Cannot get tripwires for {:?} jobs", dynamo_entry.job_status));
+    }
+
+    let tripwires = dynamo_entry.tripwires.clone().unwrap_or_default();
+    let mut results: Vec<TripwireResult> = vec![];
+    if !tripwires.is_empty() {
+        let s3_key = format!("{}/{}", dynamo_entry.s3_folder_name, TRIPWIRES_JSON_KEY);
+        results = match service.s3.get_object(&bucket_name, &s3_key).await.and_then(|(bytes, _)| Ok(serde_json::from_slice(&bytes)?)) {
+            Ok(results) => results,
+            Err(err) => {
+                return build_error_response(&format!("Error getting tripwire results: {}", err));
+            },
+        };
+    }
+
+    let mut json_header = HeaderMap::new();
+    json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+    let response = Response::new(json!({
+        "tripwires": tripwires,
+        "results": results
+    }).to_string());
+
+    return (json_header, response).into_response();
+}
+
+// recount tripwire crossings from the saved results
+async fn update_tripwire_results<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: &CommonService<S, D, R>,
+    bucket_name: &str,
+    table_name: &str,
+    dynamo_entry: &RekognitionJobTableEntry,
+    tripwires: &[Tripwire]
+) -> anyhow::Result<Vec<TripwireResult>> {
+    let samples = load_samples(service, bucket_name, dynamo_entry).await?;
+    let duration = dynamo_entry.video_metadata.as_ref().map(|metadata| metadata.duration).unwrap_or(0);
+    let results = TripwireResult::new_vec(tripwires, &samples, duration);
+    let summaries: Vec<TripwireSummary> = results.iter().map(TripwireResult::summary).collect();
+
+    let tripwires_key = format!("{}/{}", dynamo_entry.s3_folder_name, TRIPWIRES_JSON_KEY);
+    service.s3.put_object(bucket_name, &tripwires_key, Bytes::from(serde_json::to_vec(&results)?), "application/json").await?;
+    service.dynamo.update_tripwire_summaries(table_name, &dynamo_entry.job_id, &summaries).await?;
+
+    Ok(results)
+}
+
+// person samples from the saved tracking results of a finished job
+async fn load_samples<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: &CommonService<S, D, R>,
+    bucket_name: &str,
+    dynamo_entry: &RekognitionJobTableEntry
+) -> anyhow::Result<Vec<PersonSample>> {
+    let results_key = format!("{}/{}", dynamo_entry.s3_folder_name, RESULTS_JSON_KEY);
+    let (bytes, _) = service.s3.get_object(bucket_name, &results_key).await?;
+    let frame_rate = dynamo_entry.video_metadata.as_ref().map(|metadata| metadata.frame_rate).unwrap_or(30.0);
+    let tracking_results = TrackingResults::from_slice(&bytes, frame_rate)?;
+    Ok(PersonSample::from_tracking_results(&tracking_results.results))
+}
+
+
+// get all jobs of the caller
+// filters apply to each page after `limit` jobs are read: pages can be shorter (or empty) while `next_cursor` is set
+// pub async fn get_all_jobs(State(service): State<CommonService>, Path(user_id): Path<String>, last_evaluated_key: Option<Json<Option<LastEvaluatedKey>>>) -> Response {
+pub async fn get_all_jobs<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(State(service): State<CommonService<S, D, R>>, Extension(caller): Extension<Caller>, Query(params): Query<GetJobsQueryParams>) -> Response {
+    let user_id = caller.user_id;
+
+    let (Ok(table_name), Ok(cursor_secret)) = (std::env::var(TABLE_NAME_KEY), std::env::var(CURSOR_SECRET_KEY)) else {
+        return build_error_response("Environment variables not defined.");
+    };
+
+    let last_evaluated_key:Option<LastEvaluatedKey> = match (&params.cursor, &params.job_id, &params.request_timestamp) {
+        (Some(cursor), None, None) => match decode_cursor(cursor, &user_id, cursor_secret.as_bytes()) {
+            Ok(last_evaluated_key) => Some(last_evaluated_key),
+            Err(err) => {
+                return build_error_response(&format!("Invalid cursor: {}", err));
+            },
+        },
+        (Some(_), _, _) => {
+            return build_error_response("Set either cursor or job_id and request_timestamp");
+        },
+        // deprecated: raw last evaluated key, until all clients use the cursor
+        (None, Some(job_id), Some(request_timestamp)) => Some(LastEvaluatedKey::new(job_id, &user_id, request_timestamp)),
+        (None, None, None) => None,
+        _ => {
+            return build_error_response("Set both job_id and request_timestamp of the last evaluated key");
+        },
+    };
+    if let Some(JobStatus::Unknown(status)) = &params.status {
+        return build_error_response(&format!("Unknown job status: {}", status));
+    }
+    if let (Some(from), Some(to)) = (params.from, params.to) {
+        if from > to {
+            return build_error_response("from should not be after to");
+        }
+    }
+    if params.limit.is_some_and(|limit| !(1..=MAX_JOBS_PAGE_SIZE).contains(&limit)) {
+        return build_error_response(&format!("limit should be between 1 and {}", MAX_JOBS_PAGE_SIZE));
+    }
+
+    let query = JobQuery {
+        filter: JobFilter {
+            tag: params.tag,
+            job_status: params.status,
+            requested_from: params.from,
+            requested_to: params.to,
+            min_detections: params.min_detections,
+        },
+        limit: params.limit,
+        sort_order: params.order.unwrap_or_default(),
+    };
+
+    let (jobs, last_evaluated_key) = match service.dynamo.query_entries(&table_name, &user_id, &query, last_evaluated_key).await {
+        Ok(result) => result,
+        Err(err) => {
+            return build_error_response(&format!("Error getting jobs: {}.", err));
+        },
+    };
+
+    let next_cursor = match last_evaluated_key.as_ref().map(|key| encode_cursor(key, cursor_secret.as_bytes())).transpose() {
+        Ok(next_cursor) => next_cursor,
+        Err(err) => {
+            return build_error_response(&format!("Error building cursor: {}.", err));
+        },
+    };
+
+    let mut json_header = HeaderMap::new();
+    json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+    let response = Response::new(json!({
+        "jobs": jobs,
+        "next_cursor": next_cursor,
+        // deprecated: use next_cursor
+        "last_evaluated_key": last_evaluated_key
+    }).to_string());
+
+    return (json_header, response).into_response();
+}
+
+// deprecated: `/:user_id/jobs` of clients from before the token, the user id must be the caller's
+pub async fn get_user_jobs<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(State(service): State<CommonService<S, D, R>>, Extension(caller): Extension<Caller>, Path(user_id): Path<String>, query: Query<GetJobsQueryParams>) -> Response {
+    if user_id != caller.user_id {
+        return build_error_response_with_status("Jobs of another user", StatusCode::FORBIDDEN);
+    }
+    return get_all_jobs(State(service), Extension(caller), query).await;
+}
+
+
+
+// title, description, tags and location of a job
+pub async fn put_job<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    State(service): State<CommonService<S, D, R>>,
+    Extension(caller): Extension<Caller>,
+    Path(job_id): Path<String>,
+    Json(params): Json<PutJobBodyParams>
+) -> Response {
+    let Ok(table_name) = std::env::var(TABLE_NAME_KEY) else {
+        return build_error_response("Environment variables not defined.");
+    };
+
+    let details = params.details.normalized();
+    if let Err(err) = details.validate() {
+        return build_error_response(&format!("Invalid job details: {}", err));
+    }
+
+    if let Err(response) = get_caller_entry(&service, &table_name, &job_id, &caller).await {
+        return response;
+    }
+
+    let dynamo_entry = match service.dynamo.update_job_details(&table_name, &job_id, &details).await {
+        Ok(entry) => entry,
+        Err(err) => {
+            return build_error_response(&format!("Error updating job. Error: {}", err));
+        },
+    };
+
+    let mut json_header = HeaderMap::new();
+    json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+    let response = Response::new(json!({
+        "success": true,
+        "job": dynamo_entry
+    }).to_string());
+
+    return (json_header, response).into_response();
+}
+
+
+// start Rekognition again on the same video, the job id stays the same and the previous attempt is kept in `attempts`
+// multi-analysis jobs retry their failed, timed out and cancelled children
+pub async fn retry_job<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(State(service): State<CommonService<S, D, R>>, Extension(caller): Extension<Caller>, Path(job_id): Path<String>) -> Response {
+    let (Ok(bucket_name), Ok(role_arn), Ok(topic_arn), Ok(table_name)) = (std::env::var(S3_BUCKET_NAME_KEY), std::env::var(ROLE_ARN_KEY), std::env::var(TOPIC_ARN_KEY), std::env::var(TABLE_NAME_KEY)) else {
+        return build_error_response("Environment variables not defined.");
+    };
+
+    let dynamo_entry = match get_caller_entry(&service, &table_name, &job_id, &caller).await {
+        Ok(entry) => entry,
+        Err(response) => {
+            return response;
+        },
+    };
+    // a partially succeeded multi-analysis job can retry its other children
+    let retryable = match &dynamo_entry.children {
+        Some(children) => children.values().any(|child| child.job_status.is_retryable()),
+        None => dynamo_entry.job_status.is_retryable(),
+    };
+    if !retryable {
+        return build_error_response(&format!("Only failed, timed out or cancelled jobs can be retried, job status: {:?}", dynamo_entry.job_status));
+    }
+
+    let mut entries: Vec<RekognitionJobTableEntry> = vec![];
+    if let Some(children) = &dynamo_entry.children {
+        for (child_job_id, child) in children {
+            if !child.job_status.is_retryable() {
+                continue;
+            }
+            match service.dynamo.get_entry_single(&table_name, child_job_id).await {
+                Ok(entry) => entries.push(entry),
+                Err(err) => {
+                    return build_error_response(&format!("Error getting child job. Error: {}", err));
+                },
+            };
+        }
+    } else {
+        entries.push(dynamo_entry);
+    }
+
+    // job_id: new Rekognition job id, or why the job was not restarted
+    // children that restarted are returned even if others did not
+    let mut rekognition_job_ids: BTreeMap<String, String> = BTreeMap::new();
+    let mut errors: BTreeMap<String, String> = BTreeMap::new();
+    for entry in entries {
+        match restart_entry(&service, &table_name, &bucket_name, &role_arn, &topic_arn, &entry).await {
+            Ok(rekognition_job_id) => {
+                rekognition_job_ids.insert(entry.job_id, rekognition_job_id);
+            },
+            Err(err) => {
+                errors.insert(entry.job_id, err.to_string());
+            },
+        }
+    }
+    if rekognition_job_ids.is_empty() {
+        return build_error_response(&errors.into_values().collect::<Vec<String>>().join(", "));
+    }
+
+    let mut json_header = HeaderMap::new();
+    json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+    let response = Response::new(json!({
+        "job_id": job_id,
+        "rekognition_job_ids": rekognition_job_ids,
+        "errors": errors
+    }).to_string());
+
+    return (json_header, response).into_response();
+}
+
+// the entry is claimed before its Rekognition job is started, so that a concurrent retry does not start another one
+// returns the new Rekognition job id
+async fn restart_entry<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: &CommonService<S, D, R>,
+    table_name: &str,
+    bucket_name: &str,
+    role_arn: &str,
+    topic_arn: &str,
+    entry: &RekognitionJobTableEntry
+) -> anyhow::Result<String> {
+    let previous_attempt = JobAttempt::new(entry);
+    if !service.dynamo.claim_retry(table_name, &entry.job_id, &previous_attempt).await? {
+        return Err(anyhow!("Job was retried or updated in the meantime: {}", entry.job_id));
+    }
+
+    let s3_key = format!("{}/{}", entry.s3_folder_name, entry.filename);
+    let rekognition_job_id = match start_rekognition_job(&service.rekognition, entry.analysis_type.unwrap_or_default(), bucket_name, &s3_key, role_arn, topic_arn, Some(&entry.job_id)).await {
+        Ok(rekognition_job_id) => rekognition_job_id,
+        Err(err) => {
+            // released for a later retry
+            service.dynamo.update_job_status_if(table_name, &entry.job_id, previous_attempt.job_status, JobStatus::InProgress).await?;
+            return Err(anyhow!("Error start tracking: {}", err));
+        },
+    };
+    // cancelled while starting: the completion of the new Rekognition job is ignored
+    if !service.dynamo.restart_job(table_name, &entry.job_id, &rekognition_job_id, &previous_attempt).await? {
+        return Err(anyhow!("Job was cancelled in the meantime: {}", entry.job_id));
+    }
+    service.update_parent_job(table_name, &entry.job_id, JobStatus::InProgress).await
+        .map_err(|err| anyhow!("Error updating multi-analysis job: {}", err))?;
+    Ok(rekognition_job_id)
+}
+
+
+// mark an in progress job as cancelled, its completion notification is ignored
+// multi-analysis jobs cancel their in progress children and finish with them
+pub async fn cancel_job<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(State(service): State<CommonService<S, D, R>>, Extension(caller): Extension<Caller>, Path(job_id): Path<String>) -> Response {
+    let Ok(table_name) = std::env::var(TABLE_NAME_KEY) else {
+        return build_error_response("Environment variables not defined.");
+    };
+
+    let dynamo_entry = match get_caller_entry(&service, &table_name, &job_id, &caller).await {
+        Ok(entry) => entry,
+        Err(response) => {
+            return response;
+        },
+    };
+    if dynamo_entry.job_status != JobStatus::InProgress {
+        return build_error_response(&format!("Only in progress jobs can be cancelled, job status: {:?}", dynamo_entry.job_status));
+    }
+
+    let job_ids: Vec<String> = match &dynamo_entry.children {
+        Some(children) => children.iter().filter(|(_, child)| child.job_status == JobStatus::InProgress).map(|(child_job_id, _)| child_job_id.to_owned()).collect(),
+        None => vec![job_id.clone()],
+    };
+    // jobs finishing in the meantime keep their status
+    for job_id in &job_ids {
+        match service.finish_job(&table_name, job_id, JobStatus::Cancelled).await {
+            Ok(true) => {},
+            Ok(false) if dynamo_entry.children.is_none() => {
+                return build_error_response("Only in progress jobs can be cancelled, the job finished in the meantime");
+            },
+            Ok(false) => {},
+            Err(err) => {
+                return build_error_response(&format!("Error putting job status to dynamo: {}", err));
+            },
+        }
+    }
+
+    let job_status = match service.dynamo.get_entry_single(&table_name, &job_id).await {
+        Ok(entry) => entry.job_status,
+        Err(err) => {
+            return build_error_response(&format!("Error getting job. Error: {}", err));
+        },
+    };
+
+    let mut json_header = HeaderMap::new();
+    json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+    let response = Response::new(json!({
+        "success": true,
+        "job_id": job_id,
+        "job_status": job_status
+    }).to_string());
+
+    return (json_header, response).into_response();
+}
+
+
+pub async fn delete_job<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(State(service): State<CommonService<S, D, R>>, Extension(caller): Extension<Caller>, Path(job_id): Path<String>) -> Response {
+
+    let (Ok(bucket_name), Ok(table_name)) = (std::env::var(S3_BUCKET_NAME_KEY), std::env::var(TABLE_NAME_KEY)) else {
+        return build_error_response("Environment variables not defined.");
+    };
+
+    let dynamo_entry = match get_caller_entry(&service, &table_name, &job_id, &caller).await {
+        Ok(entry) => entry,
+        Err(response) => {
+            return response;
+        },
+    };
+    if let Some(parent_job_id) = &dynamo_entry.parent_job_id {
+        return build_error_response(&format!("Child jobs are deleted with their multi-analysis job: {}", parent_job_id));
+    }
+
+    // delete s3
+    if service.s3.delete_object(&bucket_name, &dynamo_entry.s3_folder_name).await.is_err() {
+        return build_error_response(&format!("Error deleting s3 object"))
+    };
+
+    // delete dynamo, with the children of a multi-analysis job (sharing the same s3 folder)
+    for child_job_id in dynamo_entry.children.unwrap_or_default().keys() {
+        if service.dynamo.delete_entry(&table_name, child_job_id).await.is_err() {
+            return build_error_response("Error deleting dynamo entry")
+        };
+    }
+    if service.dynamo.delete_entry(&table_name, &job_id).await.is_err() {
+        return build_error_response(&format!("Error deleting dynamo entry"))
+    };
+
+    let mut json_header = HeaderMap::new();
+    json_header.insert(CONTENT_TYPE, "application/json".parse().unwrap());
+
+    let response = Response::new(json!({
+        "success": true,
+    }).to_string());
+
+    return (json_header, response).into_response();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Once;
+
+    use axum::body::{to_bytes, Body};
+    use axum::http::header::AUTHORIZATION;
+    use axum::http::{Method, Request};
+    use lib::common_service::memory_service::{InMemoryJobRepository, InMemoryObjectStore, InMemoryVideoAnalyzer};
+    use lib::constants::LABELS_JSON_KEY;
+    use lib::exports::chunks::RESULTS_CHUNK_MILLIS;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use crate::auth::tests::{test_token, test_verifier};
+    use crate::router::build_router;
+    use super::*;
+
+    const BUCKET_NAME: &str = "test-bucket";
+    const TABLE_NAME: &str = "test-table";
+    const USER_ID: &str = "user-1";
+
+    type TestService = CommonService<InMemoryObjectStore, InMemoryJobRepository, InMemoryVideoAnalyzer>;
+
+    static SET_ENV: Once = Once::new();
+
+    fn test_service() -> TestService {
+        // the handlers read the environment: set once, before any test gets a service, instead of from each (parallel) test
+        SET_ENV.call_once(|| {
+            std::env::set_var(S3_BUCKET_NAME_KEY, BUCKET_NAME);
+            std::env::set_var(TABLE_NAME_KEY, TABLE_NAME);
+            std::env::set_var(ROLE_ARN_KEY, "test-role");
+            std::env::set_var(TOPIC_ARN_KEY, "test-topic");
+            std::env::set_var(CURSOR_SECRET_KEY, "test-secret");
+        });
+        CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), InMemoryVideoAnalyzer::new(vec![], None))
+    }
+
+    async fn send(service: &TestService, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+        send_as(service, Some(USER_ID), method, uri, body).await
+    }
+
+    // user_id: user of the token, None for a request without one
+    async fn send_as(service: &TestService, user_id: Option<&str>, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+        let mut builder = Request::builder().method(method).uri(uri);
+        if let Some(user_id) = user_id {
+            builder = builder.header(AUTHORIZATION, format!("Bearer {}", test_token(user_id)));
+        }
+        let body = match body {
+            Some(body) => {
+                builder = builder.header(CONTENT_TYPE, "application/json");
+                Body::from(body.to_string())
+            },
+            None => Body::empty(),
+        };
+
+        let response = build_router(service.clone(), test_verifier()).oneshot(builder.body(body).unwrap()).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    async fn start_job(service: &TestService) -> String {
+        let (status, body) = send(service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4"
+        }))).await;
+        assert_eq!(status, StatusCode::OK);
+        body["job_id"].as_str().unwrap().to_owned()
+    }
+
+    #[tokio::test]
+    async fn get_upload_url_returns_presigned_url() {
+        let service = test_service();
+        let (status, body) = send(&service, Method::GET, "/upload_url?content_type=video/mp4&filename=video.mp4", None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        let folder = body["object_folder"].as_str().unwrap();
+        assert!(folder.starts_with(&format!("{}/", USER_ID)));
+        assert_eq!(body["filename"], "video.mp4");
+        assert_eq!(body["url"], format!("memory://{}/{}/video.mp4", BUCKET_NAME, folder));
+    }
+
+    #[tokio::test]
+    async fn get_upload_url_requires_query_params() {
+        let service = test_service();
+        let response = build_router(service, test_verifier())
+            .oneshot(Request::builder().uri("/upload_url").header(AUTHORIZATION, format!("Bearer {}", test_token(USER_ID))).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn start_analysis_registers_job() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["job"]["user_id"], USER_ID);
+        assert_eq!(body["job"]["s3_folder_name"], "user-1/folder-1");
+        assert_eq!(body["job"]["job_status"], "INPROGRESS");
+        assert_eq!(body["retryable"], false);
+    }
+
+    #[tokio::test]
+    async fn get_summary_surfaces_timed_out_job() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+        service.dynamo.update_timeout_reason(TABLE_NAME, &job_id, "Rekognition job still InProgress at the job timeout").await.unwrap();
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::TimedOut).await.unwrap();
+
+        let (_, body) = send(&service, Method::GET, &format!("/{}", job_id), None).await;
+        assert_eq!(body["job"]["job_status"], "TIMEDOUT");
+        assert_eq!(body["job"]["timeout_reason"], "Rekognition job still InProgress at the job timeout");
+        assert_eq!(body["retryable"], true);
+
+        let (_, body) = send(&service, Method::GET, "/jobs", None).await;
+        assert_eq!(body["jobs"][0]["job_status"], "TIMEDOUT");
+    }
+
+    #[tokio::test]
+    async fn retry_job_restarts_failed_job() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+
+        // still in progress
+        let (status, _) = send(&service, Method::POST, &format!("/{}/retry", job_id), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::Failed).await.unwrap();
+        let (status, body) = send(&service, Method::POST, &format!("/{}/retry", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["job_id"], job_id.as_str());
+        let rekognition_job_id = body["rekognition_job_ids"][&job_id].as_str().unwrap();
+        assert_ne!(rekognition_job_id, job_id);
+        assert!(service.rekognition.get_persons_detection_results(rekognition_job_id).await.is_ok());
+
+        let (_, body) = send(&service, Method::GET, &format!("/{}", job_id), None).await;
+        assert_eq!(body["job"]["job_status"], "INPROGRESS");
+        assert_eq!(body["job"]["rekognition_job_id"], rekognition_job_id);
+        assert_eq!(body["job"]["attempts"][0]["rekognition_job_id"], job_id.as_str());
+        assert_eq!(body["job"]["attempts"][0]["job_status"], "FAILED");
+        assert_eq!(body["retryable"], false);
+    }
+
+    #[tokio::test]
+    async fn concurrent_retry_is_not_restarted_twice() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::Failed).await.unwrap();
+        // read by a concurrent retry before this one restarts the job
+        let entry = service.dynamo.get_entry_single(TABLE_NAME, &job_id).await.unwrap();
+
+        let (status, _) = send(&service, Method::POST, &format!("/{}/retry", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(!service.dynamo.claim_retry(TABLE_NAME, &job_id, &JobAttempt::new(&entry)).await.unwrap());
+        assert!(!service.dynamo.restart_job(TABLE_NAME, &job_id, "other-rekognition-job", &JobAttempt::new(&entry)).await.unwrap());
+
+        // also once the retried job failed again
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::Failed).await.unwrap();
+        assert!(!service.dynamo.claim_retry(TABLE_NAME, &job_id, &JobAttempt::new(&entry)).await.unwrap());
+        assert_ne!(service.dynamo.get_entry_single(TABLE_NAME, &job_id).await.unwrap().rekognition_job_id(), "other-rekognition-job");
+    }
+
+    #[tokio::test]
+    async fn cancel_job_marks_job_cancelled() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+
+        let (status, body) = send(&service, Method::POST, &format!("/{}/cancel", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["job_status"], "CANCELLED");
+
+        let (status, _) = send(&service, Method::POST, &format!("/{}/cancel", job_id), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // cancelled jobs can be started again
+        let (_, body) = send(&service, Method::GET, &format!("/{}", job_id), None).await;
+        assert_eq!(body["retryable"], true);
+        let (status, _) = send(&service, Method::POST, &format!("/{}/retry", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn cancel_and_retry_multi_analysis_job() {
+        let service = test_service();
+        let (_, body) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "analysis_types": ["person_tracking", "label_detection"]
+        }))).await;
+        let parent_job_id = body["job_id"].as_str().unwrap().to_owned();
+        let child_job_ids: Vec<String> = body["children"].as_object().unwrap().keys().cloned().collect();
+
+        // one child finished before the cancellation
+        service.dynamo.update_job_status(TABLE_NAME, &child_job_ids[0], JobStatus::Succeeded).await.unwrap();
+        service.update_parent_job(TABLE_NAME, &child_job_ids[0], JobStatus::Succeeded).await.unwrap();
+
+        let (status, body) = send(&service, Method::POST, &format!("/{}/cancel", parent_job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["job_status"], "PARTIALLYSUCCEEDED");
+        assert_eq!(service.dynamo.get_entry_single(TABLE_NAME, &child_job_ids[1]).await.unwrap().job_status, JobStatus::Cancelled);
+        let (_, body) = send(&service, Method::GET, &format!("/{}", parent_job_id), None).await;
+        assert_eq!(body["retryable"], true);
+
+        // only the cancelled child starts again
+        let (status, body) = send(&service, Method::POST, &format!("/{}/retry", parent_job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        let rekognition_job_ids = body["rekognition_job_ids"].as_object().unwrap();
+        assert_eq!(rekognition_job_ids.keys().collect::<Vec<_>>(), vec![&child_job_ids[1]]);
+
+        let parent = service.dynamo.get_entry_single(TABLE_NAME, &parent_job_id).await.unwrap();
+        assert_eq!(parent.job_status, JobStatus::InProgress);
+        assert_eq!(parent.children.unwrap()[&child_job_ids[1]].job_status, JobStatus::InProgress);
+    }
+
+    #[tokio::test]
+    async fn retry_returns_the_children_that_restarted() {
+        let service = test_service();
+        let (_, body) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "analysis_types": ["person_tracking", "label_detection"]
+        }))).await;
+        let parent_job_id = body["job_id"].as_str().unwrap().to_owned();
+        for child_job_id in body["children"].as_object().unwrap().keys() {
+            service.dynamo.update_job_status(TABLE_NAME, child_job_id, JobStatus::Failed).await.unwrap();
+            service.update_parent_job(TABLE_NAME, child_job_id, JobStatus::Failed).await.unwrap();
+        }
+
+        // the label detection job fails to start again
+        let analyzer = InMemoryVideoAnalyzer::new(vec![], None).with_failing_starts(vec![AnalysisType::LabelDetection]);
+        let service = CommonService::with_services(service.s3, service.dynamo, analyzer);
+        let (status, body) = send(&service, Method::POST, &format!("/{}/retry", parent_job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["rekognition_job_ids"].as_object().unwrap().len(), 1);
+        let label_job_id = body["errors"].as_object().unwrap().keys().next().unwrap().to_owned();
+
+        // released for a later retry
+        assert_eq!(service.dynamo.get_entry_single(TABLE_NAME, &label_job_id).await.unwrap().job_status, JobStatus::Failed);
+        let (_, body) = send(&service, Method::GET, &format!("/{}", parent_job_id), None).await;
+        assert_eq!(body["retryable"], true);
+    }
+
+    #[tokio::test]
+    async fn get_summary_fails_for_unknown_job() {
+        let service = test_service();
+        let (status, body) = send(&service, Method::GET, "/unknown-job", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["success"], false);
+    }
+
+    #[tokio::test]
+    async fn get_video_url_points_to_uploaded_video() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}/video_url", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["url"], format!("memory://{}/user-1/folder-1/video.mp4", BUCKET_NAME));
+        assert_eq!(body["tracks"], json!({}));
+
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::Succeeded).await.unwrap();
+        let (_, body) = send(&service, Method::GET, &format!("/{}/video_url", job_id), None).await;
+        assert_eq!(body["tracks"]["metadata"], format!("memory://{}/user-1/folder-1/{}", BUCKET_NAME, DETECTIONS_VTT_KEY));
+        assert_eq!(body["tracks"]["captions"], format!("memory://{}/user-1/folder-1/{}", BUCKET_NAME, CAPTIONS_VTT_KEY));
+    }
+
+    #[tokio::test]
+    async fn get_results_url_requires_succeeded_job() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+
+        let (status, _) = send(&service, Method::GET, &format!("/{}/results_url", job_id), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::Succeeded).await.unwrap();
+        let (status, body) = send(&service, Method::GET, &format!("/{}/results_url", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["url"], format!("memory://{}/user-1/folder-1/{}", BUCKET_NAME, RESULTS_JSON_KEY));
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}/results_url?format=csv", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["url"], format!("memory://{}/user-1/folder-1/{}", BUCKET_NAME, RESULTS_CSV_KEY));
+        assert_eq!(body["urls"][PERSON_SUMMARIES_CSV_KEY], format!("memory://{}/user-1/folder-1/{}", BUCKET_NAME, PERSON_SUMMARIES_CSV_KEY));
+
+        let (_, body) = send(&service, Method::GET, &format!("/{}/results_url?format=mot", job_id), None).await;
+        assert_eq!(body["url"], format!("memory://{}/user-1/folder-1/{}", BUCKET_NAME, MOT_GT_TXT_KEY));
+        assert_eq!(body["urls"][MOT_DET_TXT_KEY], format!("memory://{}/user-1/folder-1/{}", BUCKET_NAME, MOT_DET_TXT_KEY));
+        let (_, body) = send(&service, Method::GET, &format!("/{}/results_url?format=coco", job_id), None).await;
+        assert_eq!(body["url"], format!("memory://{}/user-1/folder-1/{}", BUCKET_NAME, COCO_JSON_KEY));
+    }
+
+    #[tokio::test]
+    async fn get_results_chunk_url_points_to_chunk_covering_timestamp() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::Succeeded).await.unwrap();
+
+        // processed before chunks were introduced
+        let (status, _) = send(&service, Method::GET, &format!("/{}/results_chunk_url?timestamp_ms=0", job_id), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (manifest, _) = ResultsManifest::new(&[], 150000, RESULTS_CHUNK_MILLIS);
+        let bytes = Bytes::from(serde_json::to_vec(&manifest).unwrap());
+        service.s3.put_object(BUCKET_NAME, &format!("user-1/folder-1/{}", RESULTS_MANIFEST_JSON_KEY), bytes, "application/json").await.unwrap();
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}/results_chunk_url?timestamp_ms=61000", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["url"], format!("memory://{}/user-1/folder-1/{}", BUCKET_NAME, ResultsManifest::chunk_key(1)));
+        assert_eq!(body["chunk"]["start_ms"], 60000);
+        assert_eq!(body["chunk"]["end_ms"], 120000);
+        assert_eq!(body["chunk_count"], 3);
+
+        let (status, _) = send(&service, Method::GET, &format!("/{}/results_chunk_url?timestamp_ms=150000", job_id), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn get_results_url_requires_columnar_format_enabled() {
+        let service = test_service();
+        let (status, body) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "columnar_formats": ["parquet"]
+        }))).await;
+        assert_eq!(status, StatusCode::OK);
+        let job_id = body["job_id"].as_str().unwrap();
+        service.dynamo.update_job_status(TABLE_NAME, job_id, JobStatus::Succeeded).await.unwrap();
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}/results_url?format=parquet", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["url"], format!("memory://{}/user-1/folder-1/{}", BUCKET_NAME, RESULTS_PARQUET_KEY));
+
+        let (status, _) = send(&service, Method::GET, &format!("/{}/results_url?format=arrow", job_id), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    fn left_zone() -> Value {
+        json!({
+            "name": "left",
+            "polygon": [{"x": 0.0, "y": 0.0}, {"x": 0.5, "y": 0.0}, {"x": 0.5, "y": 1.0}, {"x": 0.0, "y": 1.0}]
+        })
+    }
+
+    #[tokio::test]
+    async fn start_analysis_saves_zones() {
+        let service = test_service();
+        let (status, body) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "zones": [left_zone()]
+        }))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let job_id = body["job_id"].as_str().unwrap();
+        let entry = service.dynamo.get_entry_single(TABLE_NAME, job_id).await.unwrap();
+        assert_eq!(entry.zones.unwrap()[0].name, "left");
+    }
+
+    #[tokio::test]
+    async fn put_zones_rejects_invalid_zones() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+
+        let (status, _) = send(&service, Method::PUT, &format!("/{}/zones", job_id), Some(json!({
+            "zones": [{"name": "line", "polygon": [{"x": 0.0, "y": 0.0}, {"x": 0.5, "y": 0.0}]}]
+        }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn put_zones_recalculates_finished_job() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+
+        // person 0 on the left half at frame 0 and 30 (1 second at 30 fps), person 1 on the right half
+        let results = json!([
+            {"frame": 0, "persons": [
+                {"index": 0, "bounding_box": {"width": 0.1, "height": 0.2, "left": 0.1, "top": 0.5}},
+                {"index": 1, "bounding_box": {"width": 0.1, "height": 0.2, "left": 0.8, "top": 0.5}}
+            ]},
+            {"frame": 30, "persons": [
+                {"index": 0, "bounding_box": {"width": 0.1, "height": 0.2, "left": 0.2, "top": 0.5}}
+            ]}
+        ]);
+        service.s3.put_object(BUCKET_NAME, &format!("user-1/folder-1/{}", RESULTS_JSON_KEY), Bytes::from(results.to_string()), "application/json").await.unwrap();
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::Succeeded).await.unwrap();
+
+        let (status, body) = send(&service, Method::PUT, &format!("/{}/zones", job_id), Some(json!({ "zones": [left_zone()] }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["zone_summaries"][0]["unique_visitors"], 1);
+        assert_eq!(body["zone_summaries"][0]["total_dwell_time"], 1.0);
+
+        let entry = service.dynamo.get_entry_single(TABLE_NAME, &job_id).await.unwrap();
+        assert_eq!(entry.zone_summaries.unwrap()[0].unique_visitors, 1);
+        assert!(service.s3.contains_object(BUCKET_NAME, &format!("user-1/folder-1/{}", ZONES_JSON_KEY)));
+    }
+
+    #[tokio::test]
+    async fn put_tripwires_counts_finished_job() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+
+        // person 0 walks from the right to the left half between frame 0 and 30 (1 second at 30 fps)
+        let results = json!([
+            {"frame": 0, "persons": [
+                {"index": 0, "bounding_box": {"width": 0.1, "height": 0.2, "left": 0.7, "top": 0.5}}
+            ]},
+            {"frame": 30, "persons": [
+                {"index": 0, "bounding_box": {"width": 0.1, "height": 0.2, "left": 0.2, "top": 0.5}}
+            ]}
+        ]);
+        service.s3.put_object(BUCKET_NAME, &format!("user-1/folder-1/{}", RESULTS_JSON_KEY), Bytes::from(results.to_string()), "application/json").await.unwrap();
+
+        // vertical line drawn top to bottom: right to left is A to B (in)
+        let tripwire = json!({"name": "door", "start": {"x": 0.5, "y": 0.0}, "end": {"x": 0.5, "y": 1.0}});
+        let (status, _) = send(&service, Method::PUT, &format!("/{}/tripwires", job_id), Some(json!({ "tripwires": [tripwire] }))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // not finished yet
+        let (status, _) = send(&service, Method::GET, &format!("/{}/tripwires", job_id), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::Succeeded).await.unwrap();
+        let (status, body) = send(&service, Method::PUT, &format!("/{}/tripwires", job_id), Some(json!({ "tripwires": [tripwire] }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["tripwire_summaries"][0]["in_count"], 1);
+        assert_eq!(body["tripwire_summaries"][0]["out_count"], 0);
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}/tripwires", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["tripwires"][0]["name"], "door");
+        assert_eq!(body["results"][0]["crossings"][0]["direction"], "a_to_b");
+        assert_eq!(body["results"][0]["crossings"][0]["index"], 0);
+        assert_eq!(body["results"][0]["per_minute"][0]["in_count"], 1);
+    }
+
+    #[tokio::test]
+    async fn get_occupancy_returns_requested_buckets() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+
+        let (status, _) = send(&service, Method::GET, &format!("/{}/occupancy", job_id), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // no occupancy.json: calculated from the tracking results
+        // persons 0 and 1 at frame 0 and 15 (0.5 second at 30 fps), person 0 only at frame 60
+        let person = |index: i64| json!({"index": index, "bounding_box": {"width": 0.1, "height": 0.2, "left": 0.1, "top": 0.5}});
+        let results = json!([
+            {"frame": 0, "persons": [person(0), person(1)]},
+            {"frame": 15, "persons": [person(0), person(1)]},
+            {"frame": 60, "persons": [person(0)]}
+        ]);
+        service.s3.put_object(BUCKET_NAME, &format!("user-1/folder-1/{}", RESULTS_JSON_KEY), Bytes::from(results.to_string()), "application/json").await.unwrap();
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::Succeeded).await.unwrap();
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}/occupancy", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["bucket"], "1s");
+        assert_eq!(body["peak"]["count"], 2);
+        assert_eq!(body["peak"]["timestamp"], 0);
+        assert_eq!(body["buckets"].as_array().unwrap().len(), 3);
+        assert_eq!(body["buckets"][0]["max"], 2);
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}/occupancy?bucket=1min", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["bucket"], "1min");
+        assert_eq!(body["buckets"].as_array().unwrap().len(), 1);
+
+        let response = build_router(service, test_verifier())
+            .oneshot(Request::builder().uri(format!("/{}/occupancy?bucket=5s", job_id)).header(AUTHORIZATION, format!("Bearer {}", test_token(USER_ID))).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn get_range_summary_clips_tracks() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+
+        // at 30 fps: person 0 from 0 to 2000 ms, person 1 from 1000 to 3000 ms
+        let person = |index: i64| json!({"index": index, "bounding_box": {"width": 0.1, "height": 0.2, "left": 0.1, "top": 0.5}});
+        let results = json!([
+            {"frame": 0, "persons": [person(0)]},
+            {"frame": 30, "persons": [person(1)]},
+            {"frame": 60, "persons": [person(0)]},
+            {"frame": 90, "persons": [person(1)]}
+        ]);
+        service.s3.put_object(BUCKET_NAME, &format!("user-1/folder-1/{}", RESULTS_JSON_KEY), Bytes::from(results.to_string()), "application/json").await.unwrap();
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::Succeeded).await.unwrap();
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}/summary", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["tracking_summary"]["total_detection_count"], 2);
+        assert_eq!(body["tracking_summary"]["average_tracking_time"], 2.0);
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}/summary?start_ms=1500&end_ms=2500", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["start_ms"], 1500);
+        assert_eq!(body["tracking_summary"]["total_detection_count"], 2);
+        // person 0: 1500 to 2000, person 1: 1500 to 2500
+        assert_eq!(body["tracking_summary"]["average_tracking_time"], 0.75);
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}/summary?end_ms=500", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["tracking_summary"]["total_detection_count"], 1);
+
+        let (status, _) = send(&service, Method::GET, &format!("/{}/summary?start_ms=2000&end_ms=1000", job_id), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn start_analysis_saves_heatmap_config() {
+        let service = test_service();
+        let (status, body) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "heatmap": {"columns": 16, "rows": 9, "anchor": "center"}
+        }))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let job_id = body["job_id"].as_str().unwrap();
+        let heatmap_config = service.dynamo.get_entry_single(TABLE_NAME, job_id).await.unwrap().heatmap_config.unwrap();
+        assert_eq!((heatmap_config.columns, heatmap_config.rows), (16, 9));
+
+        let (status, _) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "heatmap": {"columns": 0, "rows": 9}
+        }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn start_analysis_starts_analysis_type() {
+        let service = test_service();
+        let (status, body) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "analysis_type": "label_detection"
+        }))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let job_id = body["job_id"].as_str().unwrap();
+        // started as a label detection job on the analyzer
+        assert!(service.rekognition.get_label_detection_results(job_id).await.is_ok());
+        assert!(service.rekognition.get_persons_detection_results(job_id).await.is_err());
+        let entry = service.dynamo.get_entry_single(TABLE_NAME, job_id).await.unwrap();
+        assert_eq!(entry.analysis_type, Some(AnalysisType::LabelDetection));
+
+        service.dynamo.update_job_status(TABLE_NAME, job_id, JobStatus::Succeeded).await.unwrap();
+        let (status, body) = send(&service, Method::GET, &format!("/{}/results_url", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["url"], format!("memory://{}/user-1/folder-1/{}", BUCKET_NAME, LABELS_JSON_KEY));
+        let (status, _) = send(&service, Method::GET, &format!("/{}/results_url?format=csv", job_id), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (_, body) = send(&service, Method::GET, &format!("/{}/video_url", job_id), None).await;
+        assert_eq!(body["tracks"], json!({}));
+
+        let (status, _) = send(&service, Method::PUT, &format!("/{}/zones", job_id), Some(json!({ "zones": [left_zone()] }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn start_analysis_fans_out_analysis_types() {
+        let service = test_service();
+        let (status, body) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "analysis_types": ["person_tracking", "label_detection"],
+            "zones": [left_zone()]
+        }))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let parent_job_id = body["job_id"].as_str().unwrap().to_owned();
+        let children = body["children"].as_object().unwrap();
+        assert_eq!(children.len(), 2);
+        let child_job_id = |analysis_type: &str| children.iter().find(|(_, child)| child["analysis_type"] == analysis_type).unwrap().0.clone();
+        let (tracking_job_id, label_job_id) = (child_job_id("person_tracking"), child_job_id("label_detection"));
+        assert!(service.rekognition.get_label_detection_results(&label_job_id).await.is_ok());
+
+        // options are set on the person tracking child
+        let tracking = service.dynamo.get_entry_single(TABLE_NAME, &tracking_job_id).await.unwrap();
+        assert_eq!(tracking.parent_job_id.as_deref(), Some(parent_job_id.as_str()));
+        assert!(tracking.zones.is_some());
+        assert!(service.dynamo.get_entry_single(TABLE_NAME, &label_job_id).await.unwrap().zones.is_none());
+
+        // only the parent is listed
+        let (_, body) = send(&service, Method::GET, "/jobs", None).await;
+        let job_ids: Vec<&str> = body["jobs"].as_array().unwrap().iter().map(|job| job["job_id"].as_str().unwrap()).collect();
+        assert_eq!(job_ids, vec![parent_job_id.as_str()]);
+
+        // label detection failed, person tracking succeeded
+        service.dynamo.update_job_status(TABLE_NAME, &tracking_job_id, JobStatus::Succeeded).await.unwrap();
+        service.dynamo.update_summary(TABLE_NAME, &tracking_job_id, &TrackingSummary { total_detection_count: 2, average_tracking_time: 1.0 }).await.unwrap();
+        service.dynamo.update_job_status(TABLE_NAME, &label_job_id, JobStatus::Failed).await.unwrap();
+        for (job_id, analysis_type, job_status) in [(&tracking_job_id, AnalysisType::PersonTracking, JobStatus::Succeeded), (&label_job_id, AnalysisType::LabelDetection, JobStatus::Failed)] {
+            service.dynamo.update_child_job(TABLE_NAME, &parent_job_id, job_id, &ChildJob { analysis_type, job_status }).await.unwrap();
+        }
+        service.dynamo.update_job_status(TABLE_NAME, &parent_job_id, JobStatus::PartiallySucceeded).await.unwrap();
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}", parent_job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["job"]["job_status"], "PARTIALLYSUCCEEDED");
+        assert_eq!(body["children"].as_array().unwrap().len(), 2);
+        assert_eq!(body["combined_summary"]["tracking_summary"]["total_detection_count"], 2);
+        assert!(body["combined_summary"].get("labels").is_none());
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}/results_url", parent_job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["urls"].as_object().unwrap().keys().collect::<Vec<_>>(), vec![RESULTS_JSON_KEY]);
+
+        // children go with their parent
+        let (status, _) = send(&service, Method::DELETE, &format!("/{}", label_job_id), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = send(&service, Method::DELETE, &format!("/{}", parent_job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(service.dynamo.get_entry_single(TABLE_NAME, &tracking_job_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn start_analysis_validates_analysis_types() {
+        let service = test_service();
+        for analysis_types in [json!([]), json!(["label_detection", "label_detection"]), json!(["label_detection", "face_detection"])] {
+            let (status, _) = send(&service, Method::POST, "/start_analysis", Some(json!({
+                "s3_folder_name": "user-1/folder-1",
+                "filename": "video.mp4",
+                "analysis_types": analysis_types,
+                "heatmap": {"columns": 4, "rows": 4}
+            }))).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+        let (status, _) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "analysis_type": "label_detection",
+            "analysis_types": ["face_detection"]
+        }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(service.rekognition.job_ids().is_empty());
+    }
+
+    #[tokio::test]
+    async fn start_analysis_fails_started_children_when_a_start_fails() {
+        let service = test_service();
+        let analyzer = InMemoryVideoAnalyzer::new(vec![], None).with_failing_starts(vec![AnalysisType::LabelDetection]);
+        let service = CommonService::with_services(service.s3, service.dynamo, analyzer);
+
+        let (status, _) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "analysis_types": ["person_tracking", "label_detection"]
+        }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // the started person tracking job is registered and failed, so is the listed multi-analysis job
+        let tracking_job_id = service.rekognition.job_ids().pop().unwrap();
+        let tracking = service.dynamo.get_entry_single(TABLE_NAME, &tracking_job_id).await.unwrap();
+        assert_eq!(tracking.job_status, JobStatus::Failed);
+        let parent = service.dynamo.get_entry_single(TABLE_NAME, &tracking.parent_job_id.unwrap()).await.unwrap();
+        assert_eq!(parent.job_status, JobStatus::Failed);
+
+        // nothing started: nothing is left
+        let (status, _) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "analysis_types": ["label_detection", "person_tracking"]
+        }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (_, body) = send(&service, Method::GET, "/jobs", None).await;
+        assert_eq!(body["jobs"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn start_analysis_fails_started_children_when_a_repository_write_fails() {
+        let service = test_service();
+        let service = CommonService::with_services(service.s3, InMemoryJobRepository::new().with_failing_parent_job_id_updates(), service.rekognition);
+
+        let (status, _) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "analysis_types": ["person_tracking", "label_detection"]
+        }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // the started person tracking job is failed, in its entry and in the multi-analysis job, and nothing else is started
+        assert_eq!(service.rekognition.job_ids().len(), 1);
+        let tracking_job_id = service.rekognition.job_ids().pop().unwrap();
+        let tracking = service.dynamo.get_entry_single(TABLE_NAME, &tracking_job_id).await.unwrap();
+        assert_eq!(tracking.job_status, JobStatus::Failed);
+        let entries = service.dynamo.entries().remove(TABLE_NAME).unwrap();
+        let parent = entries.iter().find(|entry| entry.job_id != tracking_job_id).unwrap();
+        assert_eq!(parent.job_status, JobStatus::Failed);
+        assert_eq!(parent.children.as_ref().unwrap()[&tracking_job_id].job_status, JobStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn start_analysis_rejects_tracking_options_for_other_types() {
+        let service = test_service();
+        let (status, _) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "analysis_type": "face_detection",
+            "zones": [left_zone()]
+        }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(service.rekognition.job_ids().is_empty());
+    }
+
+    #[tokio::test]
+    async fn start_analysis_validates_post_processing() {
+        let service = test_service();
+        let (status, body) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "post_processing": {"max_interpolation_gap_ms": 500, "smoothing_factor": 0.5}
+        }))).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let job_id = body["job_id"].as_str().unwrap();
+        let post_processing = service.dynamo.get_entry_single(TABLE_NAME, job_id).await.unwrap().post_processing.unwrap();
+        assert_eq!(post_processing.max_interpolation_gap_ms, Some(500));
+
+        let (status, _) = send(&service, Method::POST, "/start_analysis", Some(json!({
+            "s3_folder_name": "user-1/folder-1",
+            "filename": "video.mp4",
+            "post_processing": {"smoothing_factor": 0.0}
+        }))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn get_heatmap_url_points_to_requested_format() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::Succeeded).await.unwrap();
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}/heatmap_url", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["url"], format!("memory://{}/user-1/folder-1/{}", BUCKET_NAME, HEATMAP_PNG_KEY));
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}/heatmap_url?format=json", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["url"], format!("memory://{}/user-1/folder-1/{}", BUCKET_NAME, HEATMAP_JSON_KEY));
+    }
+
+    #[tokio::test]
+    async fn get_person_returns_single_track() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+
+        // at 30 fps: person 0 moves 0.3 to the right in 1 second
+        let person = |index: i64, left: f32| json!({"index": index, "bounding_box": {"width": 0.1, "height": 0.2, "left": left, "top": 0.5}});
+        let results = json!([
+            {"frame": 0, "persons": [person(0, 0.1), person(1, 0.8)]},
+            {"frame": 30, "persons": [person(0, 0.4)]}
+        ]);
+        service.s3.put_object(BUCKET_NAME, &format!("user-1/folder-1/{}", RESULTS_JSON_KEY), Bytes::from(results.to_string()), "application/json").await.unwrap();
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::Succeeded).await.unwrap();
+
+        let (status, body) = send(&service, Method::GET, &format!("/{}/persons/0", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["person"]["index"], 0);
+        assert_eq!(body["person"]["first_seen"], 0);
+        assert_eq!(body["person"]["last_seen"], 1000);
+        assert_eq!(body["person"]["path"].as_array().unwrap().len(), 2);
+        assert!((body["person"]["average_speed"].as_f64().unwrap() - 0.3).abs() < 1e-6);
+
+        let (status, _) = send(&service, Method::GET, &format!("/{}/persons/5", job_id), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn get_all_jobs_lists_user_jobs() {
+        let service = test_service();
+        let first_job_id = start_job(&service).await;
+        let second_job_id = start_job(&service).await;
+
+        let (status, body) = send(&service, Method::GET, "/jobs", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let mut job_ids: Vec<&str> = body["jobs"].as_array().unwrap().iter().map(|job| job["job_id"].as_str().unwrap()).collect();
+        job_ids.sort();
+        let mut expected = vec![first_job_id.as_str(), second_job_id.as_str()];
+        expected.sort();
+        assert_eq!(job_ids, expected);
+
+        let (_, body) = send_as(&service, Some("another-user"), Method::GET, "/jobs", None).await;
+        assert!(body["jobs"].as_array().unwrap().is_empty());
+
+        // deprecated path of the user id, only for the caller
+        let (status, body) = send(&service, Method::GET, &format!("/{}/jobs", USER_ID), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["jobs"].as_array().unwrap().len(), 2);
+        let (status, _) = send(&service, Method::GET, "/another-user/jobs", None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // half a last evaluated key
+        let (status, _) = send(&service, Method::GET, &format!("/jobs?job_id={}", first_job_id), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn requests_without_valid_token_are_unauthorized() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+
+        for uri in ["/upload_url?content_type=video/mp4&filename=video.mp4", "/jobs", &format!("/{}", job_id)] {
+            let (status, body) = send_as(&service, None, Method::GET, uri, None).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(body["success"], false);
+        }
+
+        let request = Request::builder().uri("/jobs").header(AUTHORIZATION, "Bearer not-a-token").body(Body::empty()).unwrap();
+        let response = build_router(service.clone(), test_verifier()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn jobs_of_other_users_are_forbidden() {
+        let service = test_service();
+        // the user id of the body is ignored
+        let (status, body) = send_as(&service, Some("another-user"), Method::POST, "/start_analysis", Some(json!({
+            "user_id": USER_ID,
+            "s3_folder_name": "another-user/folder-1",
+            "filename": "video.mp4"
+        }))).await;
+        assert_eq!(status, StatusCode::OK);
+        let job_id = body["job_id"].as_str().unwrap();
+        let (_, body) = send_as(&service, Some("another-user"), Method::GET, &format!("/{}", job_id), None).await;
+        assert_eq!(body["job"]["user_id"], "another-user");
+
+        let requests = [
+            (Method::GET, format!("/{}", job_id), None),
+            (Method::GET, format!("/{}/video_url", job_id), None),
+            (Method::GET, format!("/{}/results_url", job_id), None),
+            (Method::GET, format!("/{}/summary", job_id), None),
+            (Method::PUT, format!("/{}", job_id), Some(json!({ "title": "mine" }))),
+            (Method::PUT, format!("/{}/zones", job_id), Some(json!({ "zones": [] }))),
+            (Method::POST, format!("/{}/retry", job_id), None),
+            (Method::POST, format!("/{}/cancel", job_id), None),
+            (Method::DELETE, format!("/{}", job_id), None),
+        ];
+        for (method, uri, body) in requests {
+            let (status, body) = send(&service, method, &uri, body).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{}", uri);
+            assert_eq!(body["success"], false);
+        }
+
+        // nothing changed
+        let entry = service.dynamo.get_entry_single(TABLE_NAME, job_id).await.unwrap();
+        assert_eq!(entry.job_status, JobStatus::InProgress);
+        assert!(entry.title.is_none());
+        assert!(entry.zones.is_none());
+
+        // videos uploaded by another user can not be analyzed
+        for s3_folder_name in ["another-user/folder-1", "folder-1", "user-1/another-user/folder-1"] {
+            let (status, _) = send(&service, Method::POST, "/start_analysis", Some(json!({
+                "s3_folder_name": s3_folder_name,
+                "filename": "video.mp4"
+            }))).await;
+            assert_eq!(status, StatusCode::FORBIDDEN, "{}", s3_folder_name);
+        }
+    }
+
+    #[tokio::test]
+    async fn put_job_updates_details() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+
+        let (status, body) = send(&service, Method::PUT, &format!("/{}", job_id), Some(json!({
+            "title": " Entrance ",
+            "tags": ["lobby", "morning", "lobby"],
+            "location": "Camera 1"
+        }))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["job"]["title"], "Entrance");
+        assert_eq!(body["job"]["tags"], json!(["lobby", "morning"]));
+
+        // other fields are left unchanged
+        let (status, body) = send(&service, Method::PUT, &format!("/{}", job_id), Some(json!({"description": "First floor"}))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["job"]["title"], "Entrance");
+        assert_eq!(body["job"]["description"], "First floor");
+        assert_eq!(body["job"]["job_status"], "INPROGRESS");
+
+        for params in [json!({}), json!({"tags": [""]}), json!({"title": "a".repeat(201)})] {
+            let (status, _) = send(&service, Method::PUT, &format!("/{}", job_id), Some(params)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+
+        // no entry is created for unknown jobs
+        let (status, _) = send(&service, Method::PUT, "/unknown-job", Some(json!({"title": "Entrance"}))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(service.dynamo.get_entry_single(TABLE_NAME, "unknown-job").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn get_all_jobs_filters_and_sorts() {
+        let service = test_service();
+        let mut job_ids: Vec<String> = vec![];
+        for (request_timestamp, job_status, detections) in [(1000, JobStatus::Failed, 0), (2000, JobStatus::Succeeded, 150), (3000, JobStatus::Succeeded, 50), (4000, JobStatus::Failed, 0)] {
+            let job_id = start_job(&service).await;
+            service.dynamo.update_entry(TABLE_NAME, &job_id, |entry| entry.request_timestamp = request_timestamp).unwrap();
+            service.dynamo.update_job_status(TABLE_NAME, &job_id, job_status).await.unwrap();
+            service.dynamo.update_summary(TABLE_NAME, &job_id, &TrackingSummary { total_detection_count: detections, average_tracking_time: 1.0 }).await.unwrap();
+            job_ids.push(job_id);
+        }
+        let listed = |body: &Value| -> Vec<String> {
+            body["jobs"].as_array().unwrap().iter().map(|job| job["job_id"].as_str().unwrap().to_owned()).collect()
+        };
+
+        let (_, body) = send(&service, Method::GET, "/jobs", None).await;
+        assert_eq!(listed(&body), vec![job_ids[3].clone(), job_ids[2].clone(), job_ids[1].clone(), job_ids[0].clone()]);
+        let (_, body) = send(&service, Method::GET, "/jobs?order=asc", None).await;
+        assert_eq!(listed(&body), job_ids);
+
+        // failed jobs in a time range
+        let (status, body) = send(&service, Method::GET, "/jobs?status=FAILED&from=500&to=3500", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed(&body), vec![job_ids[0].clone()]);
+
+        let (_, body) = send(&service, Method::GET, "/jobs?min_detections=100", None).await;
+        assert_eq!(listed(&body), vec![job_ids[1].clone()]);
+
+        // the limit applies before the filters
+        let (_, body) = send(&service, Method::GET, "/jobs?status=SUCCEEDED&limit=1", None).await;
+        assert!(listed(&body).is_empty());
+        assert_eq!(body["last_evaluated_key"]["job_id"], job_ids[3].as_str());
+        let cursor = body["next_cursor"].as_str().unwrap();
+        let (_, body) = send(&service, Method::GET, &format!("/jobs?status=SUCCEEDED&limit=2&cursor={}", cursor), None).await;
+        assert_eq!(listed(&body), vec![job_ids[2].clone(), job_ids[1].clone()]);
+        assert_eq!(body["last_evaluated_key"]["job_id"], job_ids[1].as_str());
+
+        // the raw last evaluated key still works during the migration
+        let (_, body) = send(&service, Method::GET, &format!("/jobs?limit=2&job_id={}&request_timestamp=2000", job_ids[1]), None).await;
+        assert_eq!(listed(&body), vec![job_ids[0].clone()]);
+        assert!(body["last_evaluated_key"].is_null());
+        assert!(body["next_cursor"].is_null());
+
+        for query in ["status=UNKNOWN", "from=2000&to=1000", "limit=0", "limit=101"] {
+            let (status, _) = send(&service, Method::GET, &format!("/jobs?{}", query), None).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", query);
+        }
+    }
+
+    #[tokio::test]
+    async fn get_all_jobs_rejects_invalid_cursors() {
+        let service = test_service();
+        start_job(&service).await;
+        start_job(&service).await;
+        let (_, body) = send(&service, Method::GET, "/jobs?limit=1", None).await;
+        let cursor = body["next_cursor"].as_str().unwrap().to_owned();
+        let (status, _) = send(&service, Method::GET, &format!("/jobs?limit=1&cursor={}", cursor), None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // issued for another user
+        let (status, body) = send_as(&service, Some("another-user"), Method::GET, &format!("/jobs?cursor={}", cursor), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["message"].as_str().unwrap().starts_with("Invalid cursor"));
+
+        // modified payload
+        let (payload, signature) = cursor.split_once('.').unwrap();
+        let mut payload = payload.to_owned().into_bytes();
+        payload[4] = if payload[4] == b'A' { b'B' } else { b'A' };
+        let tampered = format!("{}.{}", String::from_utf8(payload).unwrap(), signature);
+        let (status, _) = send(&service, Method::GET, &format!("/jobs?cursor={}", tampered), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = send(&service, Method::GET, &format!("/jobs?cursor={}&job_id=job-1&request_timestamp=1", cursor), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn get_all_jobs_filters_by_tag() {
+        let service = test_service();
+        let lobby_job_id = start_job(&service).await;
+        let other_job_id = start_job(&service).await;
+        send(&service, Method::PUT, &format!("/{}", lobby_job_id), Some(json!({"tags": ["lobby"]}))).await;
+        send(&service, Method::PUT, &format!("/{}", other_job_id), Some(json!({"tags": ["parking"]}))).await;
+
+        let (status, body) = send(&service, Method::GET, "/jobs?tag=lobby", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let job_ids: Vec<&str> = body["jobs"].as_array().unwrap().iter().map(|job| job["job_id"].as_str().unwrap()).collect();
+        assert_eq!(job_ids, vec![lobby_job_id.as_str()]);
+
+        let (_, body) = send(&service, Method::GET, "/jobs?tag=unknown", None).await;
+        assert!(body["jobs"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn delete_job_removes_entry() {
+        let service = test_service();
+        let job_id = start_job(&service).await;
+
+        let (status, body) = send(&service, Method::DELETE, &format!("/{}", job_id), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["success"], true);
+
+        let (status, _) = send(&service, Method::GET, &format!("/{}", job_id), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod auth;
+pub mod handlers;
+pub mod handler_params;
+pub mod router;
@@ -1,16 +1,10 @@
-use axum::Router;
-use axum::extract::DefaultBodyLimit;
-use axum::routing::delete;
-use axum::routing::{get, post};
-use handlers::{ delete_job, get_all_jobs, get_results_url, get_summary, get_upload_url, get_video_url, start_analysis};
+use api_gateway_lambda::auth::JwtVerifier;
+use api_gateway_lambda::router::build_router;
 use lambda_http::{run, tracing, Error};
 use lib::common_service::CommonService;
-use tower_http::limit::RequestBodyLimitLayer;
+use lib::env_keys::{CURSOR_SECRET_ARN_KEY, CURSOR_SECRET_KEY};
 use std::env::set_var;
 
-pub mod handlers;
-pub mod handler_params;
-
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     set_var("AWS_LAMBDA_HTTP_IGNORE_STAGE_IN_PATH", "true");
@@ -18,30 +12,20 @@ async fn main() -> Result<(), Error> {
     tracing::init_default_subscriber();
 
     let config = aws_config::load_from_env().await;
-    let common_service = CommonService::new(&config);
-
-    let app = Router::new()
-        // start a job
-        .route("/upload_url", get(get_upload_url))
-        .route("/start_analysis", post(start_analysis))
 
-        // get results for a job
-        .route("/:job_id", get(get_summary))
-        .route("/:job_id/video_url", get(get_video_url))
-        .route("/:job_id/results_url", get(get_results_url))
+    // the handlers read the cursor secret from the environment, it is only fetched once per cold start
+    if let Ok(secret_arn) = std::env::var(CURSOR_SECRET_ARN_KEY) {
+        let secret = aws_sdk_secretsmanager::Client::new(&config).get_secret_value().secret_id(secret_arn).send().await?;
+        let Some(cursor_secret) = secret.secret_string() else {
+            return Err("Cursor secret has no string value".into());
+        };
+        set_var(CURSOR_SECRET_KEY, cursor_secret);
+    }
 
-        // list all jobs for a user
-        .route("/:user_id/jobs", get(get_all_jobs))
-
-        // delete job
-        .route("/:job_id", delete(delete_job))
-
-        // states
-        .with_state(common_service)
+    let common_service = CommonService::new(&config);
+    let verifier = JwtVerifier::from_env()?;
 
-        // Set a different limit: 1GB
-        .layer(DefaultBodyLimit::disable())
-        .layer(RequestBodyLimitLayer::new(10 * 1000 * 1000));
+    let app = build_router(common_service, verifier);
 
     run(app).await
-}
\ No newline at end of file
+}
@@ -0,0 +1,60 @@
+use axum::Router;
+use axum::extract::DefaultBodyLimit;
+use axum::middleware::from_fn_with_state;
+use axum::routing::delete;
+use axum::routing::{get, post, put};
+use lib::common_service::{CommonService, JobRepository, ObjectStore, VideoAnalyzer};
+use tower_http::limit::RequestBodyLimitLayer;
+
+use crate::auth::{authenticate, JwtVerifier};
+use crate::handlers::{ cancel_job, delete_job, get_all_jobs, get_heatmap_url, get_occupancy, get_person, get_range_summary, get_results_chunk_url, get_results_url, get_summary, get_tripwires, get_upload_url, get_user_jobs, get_video_url, put_job, put_tripwires, put_zones, retry_job, start_analysis};
+
+
+// every route requires a valid token, handlers act for the user of the token
+pub fn build_router<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(common_service: CommonService<S, D, R>, verifier: JwtVerifier) -> Router {
+    Router::new()
+        // start a job
+        .route("/upload_url", get(get_upload_url::<S, D, R>))
+        .route("/start_analysis", post(start_analysis::<S, D, R>))
+
+        // get results for a job
+        .route("/:job_id", get(get_summary::<S, D, R>))
+        .route("/:job_id/video_url", get(get_video_url::<S, D, R>))
+        .route("/:job_id/results_url", get(get_results_url::<S, D, R>))
+        .route("/:job_id/results_chunk_url", get(get_results_chunk_url::<S, D, R>))
+        .route("/:job_id/heatmap_url", get(get_heatmap_url::<S, D, R>))
+        .route("/:job_id/summary", get(get_range_summary::<S, D, R>))
+        .route("/:job_id/occupancy", get(get_occupancy::<S, D, R>))
+        .route("/:job_id/persons/:index", get(get_person::<S, D, R>))
+
+        // regions of interest for a job
+        .route("/:job_id/zones", put(put_zones::<S, D, R>))
+
+        // line crossing counts for a job
+        .route("/:job_id/tripwires", get(get_tripwires::<S, D, R>).put(put_tripwires::<S, D, R>))
+
+        // title, description, tags and location of a job
+        .route("/:job_id", put(put_job::<S, D, R>))
+
+        // start a finished job again, or stop waiting for it
+        .route("/:job_id/retry", post(retry_job::<S, D, R>))
+        .route("/:job_id/cancel", post(cancel_job::<S, D, R>))
+
+        // list all jobs of the caller
+        .route("/jobs", get(get_all_jobs::<S, D, R>))
+        // deprecated: the user id must be the caller's
+        .route("/:user_id/jobs", get(get_user_jobs::<S, D, R>))
+
+        // delete job
+        .route("/:job_id", delete(delete_job::<S, D, R>))
+
+        // states
+        .with_state(common_service)
+
+        // 401 without a valid token
+        .route_layer(from_fn_with_state(verifier, authenticate))
+
+        // Set a different limit: 1GB
+        .layer(DefaultBodyLimit::disable())
+        .layer(RequestBodyLimitLayer::new(10 * 1000 * 1000))
+}
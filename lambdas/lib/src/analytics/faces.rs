@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common_structs::PersonBoundingBox;
+
+
+// A face detected at a point in time, saved as `faces.json` for all detections.
+// Faces are not tracked: the same person is detected again in each sampled frame.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct FaceDetection {
+    // in millisecond
+    pub timestamp: i64,
+    pub bounding_box: PersonBoundingBox,
+    // estimated age range, in years
+    pub age_low: Option<i32>,
+    pub age_high: Option<i32>,
+    // e.g. "Female", "Male"
+    pub gender: Option<String>,
+    // most confident emotion, e.g. "HAPPY", "CALM"
+    pub emotion: Option<String>,
+    // 0 - 100
+    pub confidence: f32,
+}
+
+// demographics and emotions aggregated over all face detections of a video
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct FaceSummary {
+    pub detection_count: usize,
+    // middle of the estimated age ranges
+    pub average_age: Option<f64>,
+    // gender: detection count
+    pub genders: BTreeMap<String, usize>,
+    // emotion: detection count
+    pub emotions: BTreeMap<String, usize>,
+}
+
+impl FaceSummary {
+    pub fn new(detections: &[FaceDetection]) -> Self {
+        let mut genders: BTreeMap<String, usize> = BTreeMap::new();
+        let mut emotions: BTreeMap<String, usize> = BTreeMap::new();
+        let (mut age_sum, mut age_count) = (0.0, 0);
+        for detection in detections {
+            if let Some(gender) = &detection.gender {
+                *genders.entry(gender.clone()).or_default() += 1;
+            }
+            if let Some(emotion) = &detection.emotion {
+                *emotions.entry(emotion.clone()).or_default() += 1;
+            }
+            if let (Some(low), Some(high)) = (detection.age_low, detection.age_high) {
+                age_sum += (low + high) as f64 / 2.0;
+                age_count += 1;
+            }
+        }
+
+        Self {
+            detection_count: detections.len(),
+            average_age: (age_count > 0).then(|| age_sum / age_count as f64),
+            genders,
+            emotions,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detection(age: Option<(i32, i32)>, gender: Option<&str>, emotion: Option<&str>) -> FaceDetection {
+        FaceDetection {
+            timestamp: 0,
+            bounding_box: PersonBoundingBox { width: 0.1, height: 0.1, left: 0.1, top: 0.1 },
+            age_low: age.map(|age| age.0),
+            age_high: age.map(|age| age.1),
+            gender: gender.map(str::to_owned),
+            emotion: emotion.map(str::to_owned),
+            confidence: 99.0,
+        }
+    }
+
+    #[test]
+    fn aggregates_demographics_and_emotions() {
+        let detections = vec![
+            detection(Some((20, 30)), Some("Female"), Some("HAPPY")),
+            detection(Some((40, 50)), Some("Male"), Some("HAPPY")),
+            detection(None, Some("Female"), Some("CALM")),
+            detection(None, None, None),
+        ];
+
+        let summary = FaceSummary::new(&detections);
+        assert_eq!(summary.detection_count, 4);
+        assert_eq!(summary.average_age, Some(35.0));
+        assert_eq!(summary.genders, BTreeMap::from([("Female".to_owned(), 2), ("Male".to_owned(), 1)]));
+        assert_eq!(summary.emotions, BTreeMap::from([("CALM".to_owned(), 1), ("HAPPY".to_owned(), 2)]));
+
+        assert_eq!(FaceSummary::new(&[]).average_age, None);
+    }
+}
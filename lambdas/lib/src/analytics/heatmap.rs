@@ -0,0 +1,189 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{PersonSample, Point, MAX_GAP_MILLIS};
+
+
+const MAX_GRID_SIZE: usize = 200;
+// width of the rendered PNG, each cell is drawn as a square of (MAX_IMAGE_WIDTH / columns) pixels
+const MAX_IMAGE_WIDTH: usize = 640;
+
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HeatmapAnchor {
+    // bounding box center
+    Center,
+    // bounding box bottom center: where people stand and walk
+    #[default]
+    Feet,
+}
+
+impl HeatmapAnchor {
+    fn point(&self, sample: &PersonSample) -> Point {
+        match self {
+            Self::Center => sample.bounding_box.center(),
+            Self::Feet => sample.bounding_box.bottom_center(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub struct HeatmapConfig {
+    pub columns: usize,
+    pub rows: usize,
+    #[serde(default)]
+    pub anchor: HeatmapAnchor,
+}
+
+impl Default for HeatmapConfig {
+    fn default() -> Self {
+        Self { columns: 32, rows: 18, anchor: HeatmapAnchor::Feet }
+    }
+}
+
+impl HeatmapConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=MAX_GRID_SIZE).contains(&self.columns) || !(1..=MAX_GRID_SIZE).contains(&self.rows) {
+            bail!("Heatmap columns and rows should be between 1 and {}.", MAX_GRID_SIZE)
+        }
+        Ok(())
+    }
+}
+
+
+// Time spent in each cell of a grid over the frame, saved as `heatmap.json` (and rendered as `heatmap.png`).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct Heatmap {
+    pub columns: usize,
+    pub rows: usize,
+    pub anchor: HeatmapAnchor,
+    // in second, cells[row][column] with row 0 at the top
+    pub cells: Vec<Vec<f64>>,
+}
+
+impl Heatmap {
+    // Each sample adds the time until the next sample of the same person (up to MAX_GAP_MILLIS) to its cell.
+    pub fn new(config: &HeatmapConfig, samples: &[PersonSample]) -> Self {
+        let mut cells = vec![vec![0.0; config.columns]; config.rows];
+        for person_samples in PersonSample::group_by_person(samples).values() {
+            for pair in person_samples.windows(2) {
+                let millis = (pair[1].timestamp - pair[0].timestamp).min(MAX_GAP_MILLIS);
+                let point = config.anchor.point(pair[0]);
+                let column = Self::cell_index(point.x, config.columns);
+                let row = Self::cell_index(point.y, config.rows);
+                cells[row][column] += millis as f64 / 1000.0;
+            }
+        }
+
+        Self { columns: config.columns, rows: config.rows, anchor: config.anchor, cells }
+    }
+
+    // points outside of the frame (bounding boxes partially outside) are counted in the border cells
+    fn cell_index(value: f32, count: usize) -> usize {
+        ((value.clamp(0.0, 1.0) * count as f32) as usize).min(count - 1)
+    }
+
+    // RGBA image with transparent empty cells, to be drawn over the video
+    pub fn to_png(&self) -> Result<Vec<u8>> {
+        let scale = (MAX_IMAGE_WIDTH / self.columns).max(1);
+        let (width, height) = (self.columns * scale, self.rows * scale);
+        let max = self.cells.iter().flatten().cloned().fold(0.0, f64::max);
+
+        let mut data: Vec<u8> = Vec::with_capacity(width * height * 4);
+        for y in 0..height {
+            for x in 0..width {
+                let value = self.cells[y / scale][x / scale];
+                if value <= 0.0 {
+                    data.extend_from_slice(&[0, 0, 0, 0]);
+                } else {
+                    let ratio = value / max;
+                    let (r, g, b) = Self::color(ratio);
+                    data.extend_from_slice(&[r, g, b, (80.0 + ratio * 160.0) as u8]);
+                }
+            }
+        }
+
+        let mut bytes: Vec<u8> = vec![];
+        let mut encoder = png::Encoder::new(&mut bytes, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(bytes)
+    }
+
+    // blue (low) -> cyan -> green -> yellow -> red (high)
+    fn color(ratio: f64) -> (u8, u8, u8) {
+        const STOPS: [(f64, f64, f64); 5] = [(0.0, 0.0, 255.0), (0.0, 255.0, 255.0), (0.0, 255.0, 0.0), (255.0, 255.0, 0.0), (255.0, 0.0, 0.0)];
+        let position = ratio.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+        let index = (position as usize).min(STOPS.len() - 2);
+        let t = position - index as f64;
+        let (from, to) = (STOPS[index], STOPS[index + 1]);
+        (
+            (from.0 + (to.0 - from.0) * t) as u8,
+            (from.1 + (to.1 - from.1) * t) as u8,
+            (from.2 + (to.2 - from.2) * t) as u8,
+        )
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::common_structs::PersonBoundingBox;
+
+    use super::*;
+
+    // box center at (x, 0.5), feet at (x, 0.9)
+    fn sample(timestamp: i64, index: i64, x: f32) -> PersonSample {
+        PersonSample {
+            timestamp,
+            index,
+            bounding_box: PersonBoundingBox { width: 0.1, height: 0.8, left: x - 0.05, top: 0.1 },
+        }
+    }
+
+    #[test]
+    fn accumulates_time_per_cell() {
+        let samples = vec![
+            // person 0: 1s on the left, then 0.5s on the right
+            sample(0, 0, 0.25),
+            sample(1000, 0, 0.75),
+            sample(1500, 0, 0.75),
+            // person 1: lost for 5s on the left, counted as MAX_GAP_MILLIS
+            sample(0, 1, 0.25),
+            sample(5000, 1, 0.25),
+        ];
+
+        let config = HeatmapConfig { columns: 2, rows: 2, anchor: HeatmapAnchor::Feet };
+        let heatmap = Heatmap::new(&config, &samples);
+        assert_eq!(heatmap.cells, vec![vec![0.0, 0.0], vec![2.0, 0.5]]);
+
+        let config = HeatmapConfig { columns: 2, rows: 2, anchor: HeatmapAnchor::Center };
+        let heatmap = Heatmap::new(&config, &samples);
+        // y = 0.5 falls in the bottom row
+        assert_eq!(heatmap.cells, vec![vec![0.0, 0.0], vec![2.0, 0.5]]);
+
+        let config = HeatmapConfig { columns: 1, rows: 4, anchor: HeatmapAnchor::Center };
+        let heatmap = Heatmap::new(&config, &samples);
+        assert_eq!(heatmap.cells, vec![vec![0.0], vec![0.0], vec![2.5], vec![0.0]]);
+    }
+
+    #[test]
+    fn renders_png() {
+        let heatmap = Heatmap::new(&HeatmapConfig::default(), &[sample(0, 0, 0.5), sample(1000, 0, 0.5)]);
+        let bytes = heatmap.to_png().unwrap();
+        assert_eq!(&bytes[1..4], b"PNG");
+    }
+
+    #[test]
+    fn validates_config() {
+        assert!(HeatmapConfig::default().validate().is_ok());
+        assert!(HeatmapConfig { columns: 0, rows: 10, anchor: HeatmapAnchor::Feet }.validate().is_err());
+        assert!(HeatmapConfig { columns: 10, rows: 1000, anchor: HeatmapAnchor::Feet }.validate().is_err());
+    }
+}
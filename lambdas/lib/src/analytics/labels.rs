@@ -0,0 +1,97 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common_structs::PersonBoundingBox;
+
+
+// A label (object, scene or activity) detected at a point in time, saved as `labels.json` for all detections.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct LabelDetection {
+    // in millisecond
+    pub timestamp: i64,
+    pub name: String,
+    // 0 - 100
+    pub confidence: f32,
+    // one per instance, only for objects (e.g. carts, strollers, pets)
+    pub bounding_boxes: Vec<PersonBoundingBox>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct LabelSummary {
+    pub name: String,
+    // in millisecond
+    pub first_seen: i64,
+    pub last_seen: i64,
+    pub detection_count: usize,
+    // most instances at the same time
+    pub max_instance_count: usize,
+    pub max_confidence: f32,
+}
+
+impl LabelSummary {
+    // most detected labels first
+    pub fn new_vec(detections: &[LabelDetection]) -> Vec<Self> {
+        let mut summaries: BTreeMap<&str, LabelSummary> = BTreeMap::new();
+        for detection in detections {
+            let summary = summaries.entry(&detection.name).or_insert_with(|| LabelSummary {
+                name: detection.name.clone(),
+                first_seen: detection.timestamp,
+                last_seen: detection.timestamp,
+                detection_count: 0,
+                max_instance_count: 0,
+                max_confidence: 0.0,
+            });
+            summary.first_seen = summary.first_seen.min(detection.timestamp);
+            summary.last_seen = summary.last_seen.max(detection.timestamp);
+            summary.detection_count += 1;
+            summary.max_instance_count = summary.max_instance_count.max(detection.bounding_boxes.len());
+            summary.max_confidence = summary.max_confidence.max(detection.confidence);
+        }
+
+        let mut summaries: Vec<LabelSummary> = summaries.into_values().collect();
+        // stable: same count sorted by name
+        summaries.sort_by_key(|summary| std::cmp::Reverse(summary.detection_count));
+        summaries
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detection(timestamp: i64, name: &str, instance_count: usize) -> LabelDetection {
+        LabelDetection {
+            timestamp,
+            name: name.to_owned(),
+            confidence: 80.0 + timestamp as f32 / 1000.0,
+            bounding_boxes: vec![PersonBoundingBox { width: 0.1, height: 0.1, left: 0.1, top: 0.1 }; instance_count],
+        }
+    }
+
+    #[test]
+    fn summarizes_labels() {
+        let detections = vec![
+            detection(0, "Cart", 1),
+            detection(500, "Dog", 1),
+            detection(1000, "Cart", 3),
+            detection(2000, "Cart", 0),
+            detection(3000, "Bicycle", 1),
+        ];
+
+        let summaries = LabelSummary::new_vec(&detections);
+        let names: Vec<&str> = summaries.iter().map(|summary| summary.name.as_str()).collect();
+        assert_eq!(names, vec!["Cart", "Bicycle", "Dog"]);
+        assert_eq!(summaries[0], LabelSummary {
+            name: "Cart".to_owned(),
+            first_seen: 0,
+            last_seen: 2000,
+            detection_count: 3,
+            max_instance_count: 3,
+            max_confidence: 82.0,
+        });
+    }
+}
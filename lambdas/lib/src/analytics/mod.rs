@@ -0,0 +1,92 @@
+use std::collections::BTreeMap;
+
+use aws_sdk_rekognition::types::PersonDetection;
+use serde::{Deserialize, Serialize};
+
+use crate::common_structs::{PersonBoundingBox, PersonDetectionResult, TrackingResult};
+
+pub mod faces;
+pub mod heatmap;
+pub mod labels;
+pub mod occupancy;
+pub mod segments;
+pub mod trajectories;
+pub mod tripwires;
+pub mod zones;
+
+
+// A person is considered present between two of their samples if they are at most this far apart (millisecond).
+// Longer gaps (occlusion, leaving the frame) are counted as absent.
+pub const MAX_GAP_MILLIS: i64 = 1000;
+
+
+// normalized frame coordinates: (0, 0) top left, (1, 1) bottom right
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl PersonBoundingBox {
+    pub fn center(&self) -> Point {
+        Point { x: self.left + self.width / 2.0, y: self.top + self.height / 2.0 }
+    }
+
+    // feet position
+    pub fn bottom_center(&self) -> Point {
+        Point { x: self.left + self.width / 2.0, y: self.top + self.height }
+    }
+}
+
+
+// A single person position at a point in time: the common input of the analytics.
+#[derive(Debug, Clone)]
+pub struct PersonSample {
+    // in millisecond
+    pub timestamp: i64,
+    pub index: i64,
+    pub bounding_box: PersonBoundingBox,
+}
+
+impl PersonSample {
+    pub fn from_detections(person_detections: &[PersonDetection]) -> Vec<Self> {
+        person_detections
+            .iter()
+            .filter_map(|detection| {
+                PersonDetectionResult::new(detection).map(|person| Self {
+                    timestamp: detection.timestamp,
+                    index: person.index,
+                    bounding_box: person.bounding_box,
+                })
+            })
+            .collect()
+    }
+
+    // for results already saved as `persons.json`, interpolated boxes are skipped
+    pub fn from_tracking_results(tracking_results: &[TrackingResult]) -> Vec<Self> {
+        tracking_results
+            .iter()
+            .flat_map(|result| {
+                let timestamp = result.timestamp_ms;
+                result.persons.iter().filter(|person| !person.interpolated).map(move |person| Self {
+                    timestamp,
+                    index: person.index,
+                    bounding_box: person.bounding_box.clone(),
+                })
+            })
+            .collect()
+    }
+
+    // index: samples sorted by timestamp
+    pub fn group_by_person(samples: &[PersonSample]) -> BTreeMap<i64, Vec<&PersonSample>> {
+        let mut persons: BTreeMap<i64, Vec<&PersonSample>> = BTreeMap::new();
+        for sample in samples {
+            persons.entry(sample.index).or_default().push(sample);
+        }
+        for person_samples in persons.values_mut() {
+            person_samples.sort_by_key(|sample| sample.timestamp);
+        }
+        persons
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::{PersonSample, MAX_GAP_MILLIS};
+
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OccupancyBucketSize {
+    #[serde(rename = "1s")]
+    OneSecond,
+    #[serde(rename = "10s")]
+    TenSeconds,
+    #[serde(rename = "1min")]
+    OneMinute,
+}
+
+impl OccupancyBucketSize {
+    pub const ALL: [OccupancyBucketSize; 3] = [Self::OneSecond, Self::TenSeconds, Self::OneMinute];
+
+    pub fn millis(&self) -> i64 {
+        match self {
+            Self::OneSecond => 1000,
+            Self::TenSeconds => 10000,
+            Self::OneMinute => 60000,
+        }
+    }
+}
+
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct OccupancyBucket {
+    // in millisecond, [start, end)
+    pub start: i64,
+    pub end: i64,
+    // number of persons present at the same time
+    pub min: i64,
+    pub max: i64,
+    // time weighted
+    pub mean: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct OccupancyPeak {
+    pub count: i64,
+    // in millisecond, first time the peak is reached
+    pub timestamp: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct OccupancySeries {
+    pub bucket_size: OccupancyBucketSize,
+    pub buckets: Vec<OccupancyBucket>,
+}
+
+// occupancy for all bucket sizes, saved as `occupancy.json`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct Occupancy {
+    pub peak: OccupancyPeak,
+    pub series: Vec<OccupancySeries>,
+}
+
+impl Occupancy {
+    // `duration`: video duration in millisecond, the buckets cover the whole video
+    pub fn new(samples: &[PersonSample], duration: i64) -> Self {
+        let end = samples.iter().map(|sample| sample.timestamp + 1).max().unwrap_or(0).max(duration);
+        let segments = Self::segments(samples, end);
+
+        let mut peak = OccupancyPeak { count: 0, timestamp: 0 };
+        for &(start, _, count) in &segments {
+            if count > peak.count {
+                peak = OccupancyPeak { count, timestamp: start };
+            }
+        }
+
+        let series = OccupancyBucketSize::ALL
+            .iter()
+            .map(|bucket_size| OccupancySeries {
+                bucket_size: *bucket_size,
+                buckets: Self::buckets(&segments, end, bucket_size.millis()),
+            })
+            .collect();
+
+        Self { peak, series }
+    }
+
+    pub fn series(&self, bucket_size: OccupancyBucketSize) -> Option<&OccupancySeries> {
+        self.series.iter().find(|series| series.bucket_size == bucket_size)
+    }
+
+    // (start, end, count) covering [0, end), count constant within each segment
+    fn segments(samples: &[PersonSample], end: i64) -> Vec<(i64, i64, i64)> {
+        // count changes at each millisecond
+        let mut deltas: BTreeMap<i64, i64> = BTreeMap::new();
+        for person_samples in PersonSample::group_by_person(samples).values() {
+            let mut presence: Option<(i64, i64)> = None;
+            for sample in person_samples {
+                presence = match presence {
+                    Some((start, last)) if sample.timestamp - last <= MAX_GAP_MILLIS => Some((start, sample.timestamp)),
+                    Some((start, last)) => {
+                        *deltas.entry(start).or_default() += 1;
+                        *deltas.entry(last + 1).or_default() -= 1;
+                        Some((sample.timestamp, sample.timestamp))
+                    },
+                    None => Some((sample.timestamp, sample.timestamp)),
+                };
+            }
+            if let Some((start, last)) = presence {
+                *deltas.entry(start).or_default() += 1;
+                *deltas.entry(last + 1).or_default() -= 1;
+            }
+        }
+
+        let mut segments: Vec<(i64, i64, i64)> = vec![];
+        let (mut cursor, mut count) = (0, 0);
+        for (time, delta) in deltas {
+            if time > cursor {
+                segments.push((cursor, time, count));
+                cursor = time;
+            }
+            count += delta;
+        }
+        if end > cursor {
+            segments.push((cursor, end, count));
+        }
+        segments
+    }
+
+    fn buckets(segments: &[(i64, i64, i64)], end: i64, bucket_millis: i64) -> Vec<OccupancyBucket> {
+        let mut buckets: Vec<OccupancyBucket> = vec![];
+        let mut first_segment = 0;
+        let mut bucket_start = 0;
+        while bucket_start < end {
+            let bucket_end = (bucket_start + bucket_millis).min(end);
+            while segments[first_segment].1 <= bucket_start {
+                first_segment += 1;
+            }
+
+            let (mut min, mut max, mut weighted_sum) = (i64::MAX, 0, 0);
+            for &(start, segment_end, count) in segments[first_segment..].iter().take_while(|segment| segment.0 < bucket_end) {
+                min = min.min(count);
+                max = max.max(count);
+                weighted_sum += count * (segment_end.min(bucket_end) - start.max(bucket_start));
+            }
+
+            buckets.push(OccupancyBucket {
+                start: bucket_start,
+                end: bucket_end,
+                min,
+                max,
+                mean: weighted_sum as f64 / (bucket_end - bucket_start) as f64,
+            });
+            bucket_start = bucket_end;
+        }
+        buckets
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::common_structs::PersonBoundingBox;
+
+    use super::*;
+
+    fn sample(timestamp: i64, index: i64) -> PersonSample {
+        PersonSample {
+            timestamp,
+            index,
+            bounding_box: PersonBoundingBox { width: 0.1, height: 0.2, left: 0.1, top: 0.1 },
+        }
+    }
+
+    #[test]
+    fn counts_concurrent_persons() {
+        let samples = vec![
+            // person 0: present from 0 to 2000
+            sample(0, 0),
+            sample(1000, 0),
+            sample(2000, 0),
+            // person 1: present from 500 to 1499, then lost for more than MAX_GAP_MILLIS
+            sample(500, 1),
+            sample(1499, 1),
+            sample(5000, 1),
+        ];
+
+        let occupancy = Occupancy::new(&samples, 10000);
+        assert_eq!(occupancy.peak, OccupancyPeak { count: 2, timestamp: 500 });
+
+        let series = occupancy.series(OccupancyBucketSize::OneSecond).unwrap();
+        assert_eq!(series.buckets.len(), 10);
+        assert_eq!(series.buckets[0], OccupancyBucket { start: 0, end: 1000, min: 1, max: 2, mean: 1.5 });
+        assert_eq!(series.buckets[1], OccupancyBucket { start: 1000, end: 2000, min: 1, max: 2, mean: 1.5 });
+        assert_eq!(series.buckets[2].max, 1);
+        assert_eq!(series.buckets[3], OccupancyBucket { start: 3000, end: 4000, min: 0, max: 0, mean: 0.0 });
+        assert_eq!(series.buckets[5].max, 1);
+
+        let series = occupancy.series(OccupancyBucketSize::OneMinute).unwrap();
+        assert_eq!(series.buckets.len(), 1);
+        assert_eq!(series.buckets[0].end, 10000);
+        assert_eq!(series.buckets[0].max, 2);
+    }
+
+    #[test]
+    fn empty_occupancy() {
+        let occupancy = Occupancy::new(&[], 0);
+        assert_eq!(occupancy.peak.count, 0);
+        assert!(occupancy.series.iter().all(|series| series.buckets.is_empty()));
+    }
+}
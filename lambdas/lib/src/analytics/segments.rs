@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SegmentKind {
+    // continuous sequence of frames from a single camera
+    Shot,
+    // black frames, color bars, credits, ...
+    TechnicalCue,
+}
+
+// A video segment, saved as `segments.json` for all segments.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct Segment {
+    pub kind: SegmentKind,
+    // in millisecond
+    pub start_ms: i64,
+    pub end_ms: i64,
+    // shots only, 0-based
+    pub shot_index: Option<i64>,
+    // technical cues only, e.g. "BlackFrames", "EndCredits"
+    pub technical_cue: Option<String>,
+    // 0 - 100
+    pub confidence: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct SegmentSummary {
+    pub shot_count: usize,
+    // in second
+    pub average_shot_duration: f64,
+    // technical cue: count
+    pub technical_cues: BTreeMap<String, usize>,
+}
+
+impl SegmentSummary {
+    pub fn new(segments: &[Segment]) -> Self {
+        let shots: Vec<&Segment> = segments.iter().filter(|segment| segment.kind == SegmentKind::Shot).collect();
+        let shot_millis: i64 = shots.iter().map(|shot| shot.end_ms - shot.start_ms).sum();
+
+        let mut technical_cues: BTreeMap<String, usize> = BTreeMap::new();
+        for technical_cue in segments.iter().filter_map(|segment| segment.technical_cue.as_ref()) {
+            *technical_cues.entry(technical_cue.clone()).or_default() += 1;
+        }
+
+        Self {
+            shot_count: shots.len(),
+            average_shot_duration: if shots.is_empty() { 0.0 } else { shot_millis as f64 / 1000.0 / shots.len() as f64 },
+            technical_cues,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shot(index: i64, start_ms: i64, end_ms: i64) -> Segment {
+        Segment { kind: SegmentKind::Shot, start_ms, end_ms, shot_index: Some(index), technical_cue: None, confidence: 99.0 }
+    }
+
+    #[test]
+    fn summarizes_segments() {
+        let segments = vec![
+            shot(0, 0, 4000),
+            Segment { kind: SegmentKind::TechnicalCue, start_ms: 0, end_ms: 500, shot_index: None, technical_cue: Some("BlackFrames".to_owned()), confidence: 90.0 },
+            shot(1, 4000, 6000),
+        ];
+
+        let summary = SegmentSummary::new(&segments);
+        assert_eq!(summary.shot_count, 2);
+        assert_eq!(summary.average_shot_duration, 3.0);
+        assert_eq!(summary.technical_cues, BTreeMap::from([("BlackFrames".to_owned(), 1)]));
+
+        assert_eq!(SegmentSummary::new(&[]).average_shot_duration, 0.0);
+    }
+}
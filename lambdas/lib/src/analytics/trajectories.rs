@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+use super::PersonSample;
+
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct TrajectoryPoint {
+    // in millisecond
+    pub timestamp: i64,
+    // bounding box center, normalized coordinates
+    pub x: f32,
+    pub y: f32,
+}
+
+// Path of a single person, saved as `trajectories.json` for all persons.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct Trajectory {
+    pub index: i64,
+    // in millisecond
+    pub first_seen: i64,
+    pub last_seen: i64,
+    // in normalized units
+    pub path_length: f64,
+    // in normalized units per second
+    pub average_speed: f64,
+    pub max_speed: f64,
+    pub path: Vec<TrajectoryPoint>,
+}
+
+impl Trajectory {
+    // sorted by index
+    pub fn new_vec(samples: &[PersonSample]) -> Vec<Self> {
+        PersonSample::group_by_person(samples)
+            .into_iter()
+            .filter_map(|(index, person_samples)| {
+                let path: Vec<TrajectoryPoint> = person_samples
+                    .iter()
+                    .map(|sample| {
+                        let center = sample.bounding_box.center();
+                        TrajectoryPoint { timestamp: sample.timestamp, x: center.x, y: center.y }
+                    })
+                    .collect();
+                Self::new(index, path)
+            })
+            .collect()
+    }
+
+    fn new(index: i64, path: Vec<TrajectoryPoint>) -> Option<Self> {
+        let first_seen = path.first()?.timestamp;
+        let last_seen = path.last()?.timestamp;
+
+        let mut path_length = 0.0;
+        let mut max_speed: f64 = 0.0;
+        for pair in path.windows(2) {
+            let distance = ((pair[1].x - pair[0].x) as f64).hypot((pair[1].y - pair[0].y) as f64);
+            path_length += distance;
+            // several detections at the same timestamp: no speed
+            let seconds = (pair[1].timestamp - pair[0].timestamp) as f64 / 1000.0;
+            if seconds > 0.0 {
+                max_speed = max_speed.max(distance / seconds);
+            }
+        }
+
+        let seconds = (last_seen - first_seen) as f64 / 1000.0;
+        let average_speed = if seconds > 0.0 { path_length / seconds } else { 0.0 };
+
+        Some(Self { index, first_seen, last_seen, path_length, average_speed, max_speed, path })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::common_structs::PersonBoundingBox;
+
+    use super::*;
+
+    // box center at (x, y)
+    fn sample(timestamp: i64, index: i64, x: f32, y: f32) -> PersonSample {
+        PersonSample {
+            timestamp,
+            index,
+            bounding_box: PersonBoundingBox { width: 0.2, height: 0.2, left: x - 0.1, top: y - 0.1 },
+        }
+    }
+
+    #[test]
+    fn builds_trajectories_per_person() {
+        let samples = vec![
+            sample(2000, 0, 0.5, 0.5),
+            sample(0, 0, 0.25, 0.5),
+            // 0.3 in 0.5 second
+            sample(2500, 0, 0.5, 0.8),
+            sample(1000, 1, 0.5, 0.5),
+        ];
+
+        let trajectories = Trajectory::new_vec(&samples);
+        assert_eq!(trajectories.len(), 2);
+
+        let trajectory = &trajectories[0];
+        assert_eq!(trajectory.index, 0);
+        assert_eq!((trajectory.first_seen, trajectory.last_seen), (0, 2500));
+        let timestamps: Vec<i64> = trajectory.path.iter().map(|point| point.timestamp).collect();
+        assert_eq!(timestamps, vec![0, 2000, 2500]);
+        assert!((trajectory.path_length - 0.55).abs() < 1e-6);
+        assert!((trajectory.average_speed - 0.22).abs() < 1e-6);
+        assert!((trajectory.max_speed - 0.6).abs() < 1e-6);
+
+        // single detection
+        let trajectory = &trajectories[1];
+        assert_eq!(trajectory.path.len(), 1);
+        assert_eq!(trajectory.path_length, 0.0);
+        assert_eq!(trajectory.average_speed, 0.0);
+    }
+}
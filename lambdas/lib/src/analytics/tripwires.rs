@@ -0,0 +1,219 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{PersonSample, Point};
+
+
+// Virtual line segment (doorway, gate, ...) counted with the bottom center (feet) of the bounding box.
+// Side A is on the left and side B on the right when looking from `start` to `end` on screen.
+// Crossing from A to B counts as "in", from B to A as "out".
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct Tripwire {
+    pub name: String,
+    // normalized coordinates
+    pub start: Point,
+    pub end: Point,
+}
+
+impl Tripwire {
+    pub fn validate(tripwires: &[Tripwire]) -> Result<()> {
+        let mut names: HashSet<&str> = HashSet::new();
+        for tripwire in tripwires {
+            if tripwire.name.trim().is_empty() {
+                bail!("Tripwire name cannot be empty.")
+            }
+            if !names.insert(&tripwire.name) {
+                bail!("Duplicate tripwire name: {}.", tripwire.name)
+            }
+            if tripwire.start == tripwire.end {
+                bail!("Tripwire {} needs two distinct points.", tripwire.name)
+            }
+            if [tripwire.start, tripwire.end].iter().any(|point| !(0.0..=1.0).contains(&point.x) || !(0.0..=1.0).contains(&point.y)) {
+                bail!("Tripwire {} has points outside of the normalized range [0, 1].", tripwire.name)
+            }
+        }
+        Ok(())
+    }
+
+    // > 0: side B, < 0: side A, 0: on the line
+    fn side(&self, point: &Point) -> f32 {
+        (self.end.x - self.start.x) * (point.y - self.start.y) - (self.end.y - self.start.y) * (point.x - self.start.x)
+    }
+
+    // direction and position (0 to 1) along the movement `from` -> `to` if it crosses the segment
+    fn crossing(&self, from: &Point, to: &Point) -> Option<(CrossingDirection, f32)> {
+        let (side_from, side_to) = (self.side(from), self.side(to));
+        if side_from == 0.0 || side_to == 0.0 || (side_from > 0.0) == (side_to > 0.0) {
+            return None;
+        }
+
+        // the tripwire end points have to be on different sides of the movement
+        let movement = Tripwire { name: String::new(), start: *from, end: *to };
+        let (side_start, side_end) = (movement.side(&self.start), movement.side(&self.end));
+        if (side_start > 0.0 && side_end > 0.0) || (side_start < 0.0 && side_end < 0.0) {
+            return None;
+        }
+
+        let position = side_from / (side_from - side_to);
+        let direction = if side_from < 0.0 { CrossingDirection::AToB } else { CrossingDirection::BToA };
+        Some((direction, position))
+    }
+}
+
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossingDirection {
+    // in
+    AToB,
+    // out
+    BToA,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct TripwireCrossing {
+    pub index: i64,
+    pub direction: CrossingDirection,
+    // in millisecond, interpolated between the two samples around the crossing
+    pub timestamp: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct CrossingCount {
+    // minutes from the beginning of the video
+    pub minute: i64,
+    pub in_count: usize,
+    pub out_count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct TripwireSummary {
+    pub name: String,
+    pub in_count: usize,
+    pub out_count: usize,
+}
+
+// summary, per-minute counts and all crossings of a tripwire, saved as `tripwires.json`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct TripwireResult {
+    pub name: String,
+    pub in_count: usize,
+    pub out_count: usize,
+    pub per_minute: Vec<CrossingCount>,
+    pub crossings: Vec<TripwireCrossing>,
+}
+
+impl TripwireResult {
+    // Crossings are checked between consecutive samples of each person.
+    // `duration`: video duration in millisecond, the per-minute counts cover the whole video
+    pub fn new_vec(tripwires: &[Tripwire], samples: &[PersonSample], duration: i64) -> Vec<TripwireResult> {
+        let persons = PersonSample::group_by_person(samples);
+        let last_timestamp = samples.iter().map(|sample| sample.timestamp).max().unwrap_or(0).max(duration);
+
+        tripwires.iter().map(|tripwire| {
+            let mut crossings: Vec<TripwireCrossing> = vec![];
+            for (index, person_samples) in persons.iter() {
+                for pair in person_samples.windows(2) {
+                    let (from, to) = (pair[0], pair[1]);
+                    let Some((direction, position)) = tripwire.crossing(&from.bounding_box.bottom_center(), &to.bounding_box.bottom_center()) else {
+                        continue;
+                    };
+                    let timestamp = from.timestamp + ((to.timestamp - from.timestamp) as f32 * position).round() as i64;
+                    crossings.push(TripwireCrossing { index: *index, direction, timestamp });
+                }
+            }
+            crossings.sort_by_key(|crossing| crossing.timestamp);
+
+            let mut per_minute: Vec<CrossingCount> = (0..=last_timestamp / 60000)
+                .map(|minute| CrossingCount { minute, in_count: 0, out_count: 0 })
+                .collect();
+            for crossing in &crossings {
+                let count = &mut per_minute[(crossing.timestamp / 60000) as usize];
+                match crossing.direction {
+                    CrossingDirection::AToB => count.in_count += 1,
+                    CrossingDirection::BToA => count.out_count += 1,
+                }
+            }
+
+            let in_count = crossings.iter().filter(|crossing| crossing.direction == CrossingDirection::AToB).count();
+            TripwireResult {
+                name: tripwire.name.clone(),
+                in_count,
+                out_count: crossings.len() - in_count,
+                per_minute,
+                crossings,
+            }
+        }).collect()
+    }
+
+    pub fn summary(&self) -> TripwireSummary {
+        TripwireSummary { name: self.name.clone(), in_count: self.in_count, out_count: self.out_count }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::common_structs::PersonBoundingBox;
+
+    use super::*;
+
+    // vertical line in the middle of the frame, drawn top to bottom: side A is on the right, side B on the left
+    fn door() -> Tripwire {
+        Tripwire { name: "door".to_owned(), start: Point { x: 0.5, y: 0.2 }, end: Point { x: 0.5, y: 0.8 } }
+    }
+
+    // sample with feet at (x, y)
+    fn sample(timestamp: i64, index: i64, x: f32, y: f32) -> PersonSample {
+        PersonSample {
+            timestamp,
+            index,
+            bounding_box: PersonBoundingBox { width: 0.1, height: 0.2, left: x - 0.05, top: y - 0.2 },
+        }
+    }
+
+    #[test]
+    fn validates_tripwires() {
+        assert!(Tripwire::validate(&[door()]).is_ok());
+        assert!(Tripwire::validate(&[door(), door()]).is_err());
+        let point = Tripwire { name: "point".to_owned(), start: Point { x: 0.5, y: 0.5 }, end: Point { x: 0.5, y: 0.5 } };
+        assert!(Tripwire::validate(&[point]).is_err());
+    }
+
+    #[test]
+    fn counts_crossings_with_direction() {
+        let samples = vec![
+            // person 0: right to left (A -> B) at 1500, then back (B -> A) at 61000
+            sample(1000, 0, 0.7, 0.5),
+            sample(2000, 0, 0.3, 0.5),
+            sample(60000, 0, 0.4, 0.5),
+            sample(62000, 0, 0.6, 0.5),
+            // person 1: crosses the line extension below the segment, not counted
+            sample(0, 1, 0.7, 0.9),
+            sample(1000, 1, 0.3, 0.9),
+            // person 2: walks along side A
+            sample(0, 2, 0.7, 0.3),
+            sample(1000, 2, 0.7, 0.7),
+        ];
+
+        let results = TripwireResult::new_vec(&[door()], &samples, 90000);
+        let result = &results[0];
+        assert_eq!(result.in_count, 1);
+        assert_eq!(result.out_count, 1);
+        assert_eq!(result.crossings, vec![
+            TripwireCrossing { index: 0, direction: CrossingDirection::AToB, timestamp: 1500 },
+            TripwireCrossing { index: 0, direction: CrossingDirection::BToA, timestamp: 61000 },
+        ]);
+        assert_eq!(result.per_minute, vec![
+            CrossingCount { minute: 0, in_count: 1, out_count: 0 },
+            CrossingCount { minute: 1, in_count: 0, out_count: 1 },
+        ]);
+    }
+}
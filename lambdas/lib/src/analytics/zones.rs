@@ -0,0 +1,251 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{PersonSample, Point};
+
+
+// Named region of interest (shelf, entrance, checkout, ...).
+// A person is in the zone when the bottom center (feet) of the bounding box is inside the polygon.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct Zone {
+    pub name: String,
+    // polygon vertices in normalized coordinates
+    pub polygon: Vec<Point>,
+}
+
+impl Zone {
+    pub fn validate(zones: &[Zone]) -> Result<()> {
+        let mut names: HashSet<&str> = HashSet::new();
+        for zone in zones {
+            if zone.name.trim().is_empty() {
+                bail!("Zone name cannot be empty.")
+            }
+            if !names.insert(&zone.name) {
+                bail!("Duplicate zone name: {}.", zone.name)
+            }
+            if zone.polygon.len() < 3 {
+                bail!("Zone {} needs at least 3 points.", zone.name)
+            }
+            if zone.polygon.iter().any(|point| !(0.0..=1.0).contains(&point.x) || !(0.0..=1.0).contains(&point.y)) {
+                bail!("Zone {} has points outside of the normalized range [0, 1].", zone.name)
+            }
+        }
+        Ok(())
+    }
+
+    // ray casting
+    pub fn contains(&self, point: &Point) -> bool {
+        let mut inside = false;
+        let count = self.polygon.len();
+        for i in 0..count {
+            let a = self.polygon[i];
+            let b = self.polygon[(i + count - 1) % count];
+            if (a.y > point.y) != (b.y > point.y) {
+                let intersect_x = a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y);
+                if point.x < intersect_x {
+                    inside = !inside;
+                }
+            }
+        }
+        inside
+    }
+}
+
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoneEventType {
+    Entry,
+    Exit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct ZoneEvent {
+    pub index: i64,
+    pub event_type: ZoneEventType,
+    // in millisecond
+    pub timestamp: i64,
+}
+
+// all entry/exit events of a zone, saved as `zones.json`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct ZoneEvents {
+    pub name: String,
+    pub events: Vec<ZoneEvent>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct ZoneSummary {
+    pub name: String,
+    // number of persons that entered the zone at least once
+    pub unique_visitors: usize,
+    pub entry_count: usize,
+    pub exit_count: usize,
+    // in second
+    pub total_dwell_time: f64,
+    // in second, per visitor
+    pub average_dwell_time: f64,
+}
+
+impl ZoneSummary {
+    // A visit starts at the first sample inside the zone, and ends at the next sample outside of it.
+    // If the person is lost while inside, the visit ends at the last sample (no exit event).
+    pub fn new_vec(zones: &[Zone], samples: &[PersonSample]) -> (Vec<ZoneSummary>, Vec<ZoneEvents>) {
+        let persons = PersonSample::group_by_person(samples);
+        let mut summaries: Vec<ZoneSummary> = vec![];
+        let mut zone_events: Vec<ZoneEvents> = vec![];
+
+        for zone in zones {
+            let mut events: Vec<ZoneEvent> = vec![];
+            let mut visitors: HashSet<i64> = HashSet::new();
+            let mut total_dwell_millis: i64 = 0;
+
+            for (index, person_samples) in persons.iter() {
+                // entry timestamp of the current visit
+                let mut entered_at: Option<i64> = None;
+                for sample in person_samples {
+                    let inside = zone.contains(&sample.bounding_box.bottom_center());
+                    match (inside, entered_at) {
+                        (true, None) => {
+                            entered_at = Some(sample.timestamp);
+                            visitors.insert(*index);
+                            events.push(ZoneEvent { index: *index, event_type: ZoneEventType::Entry, timestamp: sample.timestamp });
+                        },
+                        (false, Some(entry_timestamp)) => {
+                            entered_at = None;
+                            total_dwell_millis += sample.timestamp - entry_timestamp;
+                            events.push(ZoneEvent { index: *index, event_type: ZoneEventType::Exit, timestamp: sample.timestamp });
+                        },
+                        _ => {},
+                    }
+                }
+                if let (Some(entry_timestamp), Some(last)) = (entered_at, person_samples.last()) {
+                    total_dwell_millis += last.timestamp - entry_timestamp;
+                }
+            }
+
+            events.sort_by_key(|event| event.timestamp);
+            let entry_count = events.iter().filter(|event| event.event_type == ZoneEventType::Entry).count();
+            let exit_count = events.len() - entry_count;
+            let total_dwell_time = total_dwell_millis as f64 / 1000.0;
+            let average_dwell_time = if visitors.is_empty() { 0.0 } else { total_dwell_time / visitors.len() as f64 };
+
+            summaries.push(ZoneSummary {
+                name: zone.name.clone(),
+                unique_visitors: visitors.len(),
+                entry_count,
+                exit_count,
+                total_dwell_time,
+                average_dwell_time,
+            });
+            zone_events.push(ZoneEvents { name: zone.name.clone(), events });
+        }
+
+        (summaries, zone_events)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::common_structs::PersonBoundingBox;
+
+    use super::*;
+
+    // square zone on the left half of the frame
+    fn left_zone() -> Zone {
+        Zone {
+            name: "left".to_owned(),
+            polygon: vec![
+                Point { x: 0.0, y: 0.0 },
+                Point { x: 0.5, y: 0.0 },
+                Point { x: 0.5, y: 1.0 },
+                Point { x: 0.0, y: 1.0 },
+            ],
+        }
+    }
+
+    // sample with feet at (x, 0.9)
+    fn sample(timestamp: i64, index: i64, x: f32) -> PersonSample {
+        PersonSample {
+            timestamp,
+            index,
+            bounding_box: PersonBoundingBox { width: 0.1, height: 0.4, left: x - 0.05, top: 0.5 },
+        }
+    }
+
+    #[test]
+    fn contains_points_inside_polygon() {
+        let zone = left_zone();
+        assert!(zone.contains(&Point { x: 0.25, y: 0.5 }));
+        assert!(!zone.contains(&Point { x: 0.75, y: 0.5 }));
+
+        let triangle = Zone {
+            name: "triangle".to_owned(),
+            polygon: vec![Point { x: 0.0, y: 0.0 }, Point { x: 1.0, y: 0.0 }, Point { x: 0.0, y: 1.0 }],
+        };
+        assert!(triangle.contains(&Point { x: 0.2, y: 0.2 }));
+        assert!(!triangle.contains(&Point { x: 0.8, y: 0.8 }));
+    }
+
+    #[test]
+    fn validates_zones() {
+        assert!(Zone::validate(&[left_zone()]).is_ok());
+        assert!(Zone::validate(&[left_zone(), left_zone()]).is_err());
+
+        let mut zone = left_zone();
+        zone.polygon.truncate(2);
+        assert!(Zone::validate(&[zone]).is_err());
+
+        let mut zone = left_zone();
+        zone.polygon[0].x = 1.5;
+        assert!(Zone::validate(&[zone]).is_err());
+    }
+
+    #[test]
+    fn counts_visitors_and_dwell_time() {
+        let samples = vec![
+            // person 0: enters at 1000, leaves at 3000, comes back at 4000 and is lost at 5000
+            sample(0, 0, 0.8),
+            sample(1000, 0, 0.4),
+            sample(2000, 0, 0.3),
+            sample(3000, 0, 0.7),
+            sample(4000, 0, 0.2),
+            sample(5000, 0, 0.2),
+            // person 1: never in the zone
+            sample(0, 1, 0.9),
+            sample(2000, 1, 0.6),
+            // person 2: in the zone from 500 to 1500
+            sample(500, 2, 0.1),
+            sample(1500, 2, 0.6),
+        ];
+
+        let (summaries, zone_events) = ZoneSummary::new_vec(&[left_zone()], &samples);
+        let summary = &summaries[0];
+        assert_eq!(summary.name, "left");
+        assert_eq!(summary.unique_visitors, 2);
+        assert_eq!(summary.entry_count, 3);
+        assert_eq!(summary.exit_count, 2);
+        // person 0: 2s + 1s, person 2: 1s
+        assert_eq!(summary.total_dwell_time, 4.0);
+        assert_eq!(summary.average_dwell_time, 2.0);
+
+        let events = &zone_events[0].events;
+        let timestamps: Vec<i64> = events.iter().map(|event| event.timestamp).collect();
+        assert_eq!(timestamps, vec![500, 1000, 1500, 3000, 4000]);
+        assert_eq!(events[0], ZoneEvent { index: 2, event_type: ZoneEventType::Entry, timestamp: 500 });
+    }
+
+    #[test]
+    fn empty_zone_summary() {
+        let (summaries, _) = ZoneSummary::new_vec(&[left_zone()], &[sample(0, 0, 0.9)]);
+        assert_eq!(summaries[0].unique_visitors, 0);
+        assert_eq!(summaries[0].average_dwell_time, 0.0);
+    }
+}
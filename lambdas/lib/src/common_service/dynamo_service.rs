@@ -1,11 +1,18 @@
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use anyhow::{bail, Context, Result};
-use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::operation::update_item::UpdateItemError;
+use aws_sdk_dynamodb::types::{AttributeValue, ReturnValue};
+use serde::Serialize;
 use serde_dynamo::{from_item, from_items, to_attribute_value, to_item};
 
-use crate::common_structs::{JobStatus, LastEvaluatedKey, RekognitionJobTableEntry, TrackingSummary, VideoMetadata};
+use crate::analytics::heatmap::HeatmapConfig;
+use crate::analytics::tripwires::{Tripwire, TripwireSummary};
+use crate::analytics::zones::{Zone, ZoneSummary};
+use crate::exports::columnar::ColumnarFormat;
+use crate::common_structs::{current_timestamp, AnalysisSummary, AnalysisType, ChildJob, JobAttempt, JobDetails, JobQuery, JobStatus, LastEvaluatedKey, RekognitionJobTableEntry, SortOrder, TrackingPostProcessing, TrackingSummary, VideoMetadata};
+use super::traits::JobRepository;
 
 #[derive(Debug, Clone)]
 pub struct DynamoService {
@@ -19,7 +26,49 @@ impl DynamoService {
         }
     }
 
-    pub async fn register_entry(&self, table_name: &str, user_id: &str, job_id: &str, s3_folder_name: &str, file_name: &str) -> Result<()>{
+    // set a single (top level) attribute of a job entry
+    async fn set_attribute<T: Serialize + ?Sized>(&self, table_name: &str, job_id: &str, name: &str, value: &T) -> Result<()> {
+        let attribute_value: AttributeValue = to_attribute_value(value)?;
+
+        self
+            .client.clone()
+            .update_item()
+            .table_name(table_name)
+            .key("job_id", AttributeValue::S(job_id.to_owned()))
+            .update_expression("set #name = :value")
+            .expression_attribute_names("#name", name)
+            .expression_attribute_values(":value", attribute_value)
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    // set the job status if the condition (on `:condition`) holds, returns false if it does not
+    async fn set_job_status_if(&self, table_name: &str, job_id: &str, status: JobStatus, condition_expression: &str, condition_value: AttributeValue) -> Result<bool> {
+        let attribute_value: AttributeValue = to_attribute_value(&status)?;
+
+        let result = self
+            .client.clone()
+            .update_item()
+            .table_name(table_name)
+            .key("job_id", AttributeValue::S(job_id.to_owned()))
+            .update_expression("set job_status = :value")
+            .condition_expression(condition_expression)
+            .expression_attribute_values(":value", attribute_value)
+            .expression_attribute_values(":condition", condition_value)
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(err) if matches!(err.as_service_error(), Some(UpdateItemError::ConditionalCheckFailedException(_))) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl JobRepository for DynamoService {
+    async fn register_entry(&self, table_name: &str, user_id: &str, job_id: &str, s3_folder_name: &str, file_name: &str) -> Result<()>{
         let entry = RekognitionJobTableEntry::new(job_id, user_id, s3_folder_name, file_name);
         self
             .client.clone()
@@ -32,7 +81,7 @@ impl DynamoService {
         Ok(())
     }
 
-    pub async fn update_summary(&self, table_name: &str, job_id: &str, tracking_summary: &TrackingSummary) -> Result<()>{
+    async fn update_summary(&self, table_name: &str, job_id: &str, tracking_summary: &TrackingSummary) -> Result<()>{
         let attribute_value: aws_sdk_dynamodb::types::AttributeValue = to_attribute_value(&tracking_summary)?;
 
         self
@@ -48,7 +97,7 @@ impl DynamoService {
         Ok(())
     }
 
-    pub async fn update_metadata(&self, table_name: &str, job_id: &str, metadata: &VideoMetadata) -> Result<()>{
+    async fn update_metadata(&self, table_name: &str, job_id: &str, metadata: &VideoMetadata) -> Result<()>{
         let attribute_value: aws_sdk_dynamodb::types::AttributeValue = to_attribute_value(&metadata)?;
 
         self
@@ -64,7 +113,7 @@ impl DynamoService {
         Ok(())
     }
 
-    pub async fn update_job_status(&self, table_name: &str, job_id: &str, status: JobStatus) -> Result<()>{
+    async fn update_job_status(&self, table_name: &str, job_id: &str, status: JobStatus) -> Result<()>{
         let attribute_value: aws_sdk_dynamodb::types::AttributeValue = to_attribute_value(&status)?;
 
         self
@@ -80,7 +129,186 @@ impl DynamoService {
         Ok(())
     }
 
-    pub async fn get_entry_single(&self, table_name: &str, job_id: &str) -> Result<RekognitionJobTableEntry> {
+    async fn update_job_status_if(&self, table_name: &str, job_id: &str, status: JobStatus, expected: JobStatus) -> Result<bool>{
+        self.set_job_status_if(table_name, job_id, status, "job_status = :condition", to_attribute_value(&expected)?).await
+    }
+
+    async fn update_zones(&self, table_name: &str, job_id: &str, zones: &[Zone]) -> Result<()>{
+        self.set_attribute(table_name, job_id, "zones", zones).await
+    }
+
+    async fn update_zone_summaries(&self, table_name: &str, job_id: &str, zone_summaries: &[ZoneSummary]) -> Result<()>{
+        self.set_attribute(table_name, job_id, "zone_summaries", zone_summaries).await
+    }
+
+    async fn update_tripwires(&self, table_name: &str, job_id: &str, tripwires: &[Tripwire]) -> Result<()>{
+        self.set_attribute(table_name, job_id, "tripwires", tripwires).await
+    }
+
+    async fn update_tripwire_summaries(&self, table_name: &str, job_id: &str, tripwire_summaries: &[TripwireSummary]) -> Result<()>{
+        self.set_attribute(table_name, job_id, "tripwire_summaries", tripwire_summaries).await
+    }
+
+    async fn update_heatmap_config(&self, table_name: &str, job_id: &str, heatmap_config: &HeatmapConfig) -> Result<()>{
+        self.set_attribute(table_name, job_id, "heatmap_config", heatmap_config).await
+    }
+
+    async fn update_post_processing(&self, table_name: &str, job_id: &str, post_processing: &TrackingPostProcessing) -> Result<()>{
+        self.set_attribute(table_name, job_id, "post_processing", post_processing).await
+    }
+
+    async fn update_columnar_formats(&self, table_name: &str, job_id: &str, columnar_formats: &[ColumnarFormat]) -> Result<()>{
+        self.set_attribute(table_name, job_id, "columnar_formats", columnar_formats).await
+    }
+
+    async fn update_analysis_type(&self, table_name: &str, job_id: &str, analysis_type: AnalysisType) -> Result<()>{
+        self.set_attribute(table_name, job_id, "analysis_type", &analysis_type).await
+    }
+
+    async fn update_analysis_summary(&self, table_name: &str, job_id: &str, analysis_summary: &AnalysisSummary) -> Result<()>{
+        self.set_attribute(table_name, job_id, "analysis_summary", analysis_summary).await
+    }
+
+    async fn update_children(&self, table_name: &str, job_id: &str, children: &BTreeMap<String, ChildJob>) -> Result<()>{
+        self.set_attribute(table_name, job_id, "children", children).await
+    }
+
+    // only the child's key of the `children` map is set, so children finishing at the same time don't overwrite each other
+    async fn update_child_job(&self, table_name: &str, job_id: &str, child_job_id: &str, child: &ChildJob) -> Result<RekognitionJobTableEntry>{
+        let attribute_value: AttributeValue = to_attribute_value(child)?;
+
+        let output = self
+            .client.clone()
+            .update_item()
+            .table_name(table_name)
+            .key("job_id", AttributeValue::S(job_id.to_owned()))
+            .update_expression("set children.#child = :child add children_version :one")
+            .expression_attribute_names("#child", child_job_id)
+            .expression_attribute_values(":child", attribute_value)
+            .expression_attribute_values(":one", AttributeValue::N("1".to_owned()))
+            .return_values(ReturnValue::AllNew)
+            .send()
+            .await?;
+
+        let Some(item) = output.attributes else {
+            bail!("Job does not exist for id: {}!", job_id)
+        };
+        let entry: RekognitionJobTableEntry = from_item(item)?;
+        Ok(entry)
+    }
+
+    async fn update_parent_job_status(&self, table_name: &str, job_id: &str, status: JobStatus, children_version: u64) -> Result<bool>{
+        self.set_job_status_if(table_name, job_id, status, "children_version = :condition", AttributeValue::N(children_version.to_string())).await
+    }
+
+    async fn update_parent_job_id(&self, table_name: &str, job_id: &str, parent_job_id: &str) -> Result<()>{
+        self.set_attribute(table_name, job_id, "parent_job_id", parent_job_id).await
+    }
+
+    async fn update_timeout_reason(&self, table_name: &str, job_id: &str, timeout_reason: &str) -> Result<()>{
+        self.set_attribute(table_name, job_id, "timeout_reason", timeout_reason).await
+    }
+
+    async fn update_user_id(&self, table_name: &str, job_id: &str, user_id: &str) -> Result<()>{
+        self.set_attribute(table_name, job_id, "user_id", user_id).await
+    }
+
+    async fn claim_retry(&self, table_name: &str, job_id: &str, previous_attempt: &JobAttempt) -> Result<bool>{
+        let job_status: AttributeValue = to_attribute_value(JobStatus::InProgress)?;
+        let previous_job_status: AttributeValue = to_attribute_value(&previous_attempt.job_status)?;
+
+        // entries without `rekognition_job_id` use the job id
+        let result = self
+            .client.clone()
+            .update_item()
+            .table_name(table_name)
+            .key("job_id", AttributeValue::S(job_id.to_owned()))
+            .update_expression("set job_status = :job_status")
+            .expression_attribute_values(":job_status", job_status)
+            .condition_expression("job_status = :previous_job_status and (rekognition_job_id = :previous_rekognition_job_id or (attribute_not_exists(rekognition_job_id) and job_id = :previous_rekognition_job_id))")
+            .expression_attribute_values(":previous_job_status", previous_job_status)
+            .expression_attribute_values(":previous_rekognition_job_id", AttributeValue::S(previous_attempt.rekognition_job_id.to_owned()))
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(err) if matches!(err.as_service_error(), Some(UpdateItemError::ConditionalCheckFailedException(_))) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn restart_job(&self, table_name: &str, job_id: &str, rekognition_job_id: &str, previous_attempt: &JobAttempt) -> Result<bool>{
+        let attempts: AttributeValue = to_attribute_value(vec![previous_attempt])?;
+        let job_status: AttributeValue = to_attribute_value(JobStatus::InProgress)?;
+
+        // entries without `rekognition_job_id` use the job id
+        let result = self
+            .client.clone()
+            .update_item()
+            .table_name(table_name)
+            .key("job_id", AttributeValue::S(job_id.to_owned()))
+            .update_expression("set rekognition_job_id = :rekognition_job_id, attempt_started_at = :attempt_started_at, attempts = list_append(if_not_exists(attempts, :empty), :attempts) remove timeout_reason")
+            .expression_attribute_values(":rekognition_job_id", AttributeValue::S(rekognition_job_id.to_owned()))
+            .expression_attribute_values(":attempt_started_at", AttributeValue::N(current_timestamp().to_string()))
+            .expression_attribute_values(":empty", AttributeValue::L(vec![]))
+            .expression_attribute_values(":attempts", attempts)
+            .condition_expression("job_status = :job_status and (rekognition_job_id = :previous_rekognition_job_id or (attribute_not_exists(rekognition_job_id) and job_id = :previous_rekognition_job_id))")
+            .expression_attribute_values(":job_status", job_status)
+            .expression_attribute_values(":previous_rekognition_job_id", AttributeValue::S(previous_attempt.rekognition_job_id.to_owned()))
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(err) if matches!(err.as_service_error(), Some(UpdateItemError::ConditionalCheckFailedException(_))) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn update_job_details(&self, table_name: &str, job_id: &str, details: &JobDetails) -> Result<RekognitionJobTableEntry>{
+        let mut builder = self
+            .client.clone()
+            .update_item()
+            .table_name(table_name)
+            .key("job_id", AttributeValue::S(job_id.to_owned()))
+            // update_item creates missing items
+            .condition_expression("attribute_exists(job_id)")
+            .return_values(ReturnValue::AllNew);
+
+        let mut set_expressions: Vec<String> = vec![];
+        let fields: [(&str, Option<AttributeValue>); 4] = [
+            ("title", details.title.as_ref().map(to_attribute_value).transpose()?),
+            ("description", details.description.as_ref().map(to_attribute_value).transpose()?),
+            ("tags", details.tags.as_ref().map(to_attribute_value).transpose()?),
+            ("location", details.location.as_ref().map(to_attribute_value).transpose()?),
+        ];
+        for (name, value) in fields {
+            let Some(value) = value else {
+                continue;
+            };
+            set_expressions.push(format!("#{} = :{}", name, name));
+            builder = builder
+                .expression_attribute_names(format!("#{}", name), name)
+                .expression_attribute_values(format!(":{}", name), value);
+        }
+        if set_expressions.is_empty() {
+            bail!("No job details to update")
+        }
+
+        let output = builder
+            .update_expression(format!("set {}", set_expressions.join(", ")))
+            .send()
+            .await?;
+
+        let Some(item) = output.attributes else {
+            bail!("Job does not exist for id: {}!", job_id)
+        };
+        let entry: RekognitionJobTableEntry = from_item(item)?;
+        Ok(entry)
+    }
+
+    async fn get_entry_single(&self, table_name: &str, job_id: &str) -> Result<RekognitionJobTableEntry> {
         let results = self
             .client.clone()
             .query()
@@ -102,17 +330,76 @@ impl DynamoService {
         Ok(entry)
     }
 
-    pub async fn query_entries(&self, table_name: &str, user_id: &str, last_evaluated_key: Option<LastEvaluatedKey>) -> Result<(Vec<RekognitionJobTableEntry>, Option<LastEvaluatedKey>)> {
+    async fn scan_in_progress_entries(&self, table_name: &str, started_before: u64) -> Result<Vec<RekognitionJobTableEntry>> {
+        let mut entries: Vec<RekognitionJobTableEntry> = vec![];
+        let mut exclusive_start_key: Option<HashMap<String, AttributeValue>> = None;
+        loop {
+            let results = self.client.clone()
+                .scan()
+                .table_name(table_name)
+                // retried jobs: the start of the current attempt
+                .filter_expression("job_status = :status AND (attempt_started_at < :timestamp OR (attribute_not_exists(attempt_started_at) AND request_timestamp < :timestamp))")
+                .expression_attribute_values(":status", to_attribute_value(JobStatus::InProgress)?)
+                .expression_attribute_values(":timestamp", AttributeValue::N(started_before.to_string()))
+                .set_exclusive_start_key(exclusive_start_key)
+                .send()
+                .await?;
+
+            if let Some(items) = results.items {
+                let mut page: Vec<RekognitionJobTableEntry> = from_items(items)?;
+                entries.append(&mut page);
+            }
+            exclusive_start_key = results.last_evaluated_key;
+            if exclusive_start_key.is_none() {
+                break;
+            }
+        }
+        Ok(entries)
+    }
+
+    async fn query_entries(&self, table_name: &str, user_id: &str, query: &JobQuery, last_evaluated_key: Option<LastEvaluatedKey>) -> Result<(Vec<RekognitionJobTableEntry>, Option<LastEvaluatedKey>)> {
+        let filter = &query.filter;
+
+        // request timestamp range on the sort key of the index
+        let key_condition = match (filter.requested_from, filter.requested_to) {
+            (Some(_), Some(_)) => "#name = :value AND request_timestamp BETWEEN :requested_from AND :requested_to",
+            (Some(_), None) => "#name = :value AND request_timestamp >= :requested_from",
+            (None, Some(_)) => "#name = :value AND request_timestamp <= :requested_to",
+            (None, None) => "#name = :value",
+        };
+
         let mut builder = self.client.clone()
             .query()
-            // .limit(1)
-            .scan_index_forward(false)
+            .set_limit(query.limit)
+            .scan_index_forward(query.sort_order == SortOrder::Asc)
             .table_name(table_name)
             .index_name("gsi-userid")
-            .key_condition_expression("#name = :value")
+            .key_condition_expression(key_condition)
             .expression_attribute_names("#name", "user_id")
             .expression_attribute_values(":value", AttributeValue::S(user_id.to_owned()));
+        if let Some(requested_from) = filter.requested_from {
+            builder = builder.expression_attribute_values(":requested_from", AttributeValue::N(requested_from.to_string()));
+        }
+        if let Some(requested_to) = filter.requested_to {
+            builder = builder.expression_attribute_values(":requested_to", AttributeValue::N(requested_to.to_string()));
+        }
 
+        // filters apply after the page is read: a page can have fewer jobs (or none) while more are available
+        // children are listed with their multi-analysis job
+        let mut filter_expressions: Vec<&str> = vec!["attribute_not_exists(parent_job_id)"];
+        if let Some(tag) = &filter.tag {
+            filter_expressions.push("contains(tags, :tag)");
+            builder = builder.expression_attribute_values(":tag", AttributeValue::S(tag.to_owned()));
+        }
+        if let Some(job_status) = &filter.job_status {
+            filter_expressions.push("job_status = :job_status");
+            builder = builder.expression_attribute_values(":job_status", to_attribute_value(job_status)?);
+        }
+        if let Some(min_detections) = filter.min_detections {
+            filter_expressions.push("tracking_summary.total_detection_count >= :min_detections");
+            builder = builder.expression_attribute_values(":min_detections", AttributeValue::N(min_detections.to_string()));
+        }
+        builder = builder.filter_expression(filter_expressions.join(" AND "));
 
             if let Some(last_evaluated_key) = last_evaluated_key {
             let mut exclusive_key: HashMap<String, AttributeValue> = HashMap::new();
@@ -139,7 +426,7 @@ impl DynamoService {
         Ok((entries, last_evaluated_key))
     }
 
-    pub async fn delete_entry(&self, table_name: &str, job_id: &str) -> Result<()> {
+    async fn delete_entry(&self, table_name: &str, job_id: &str) -> Result<()> {
         self.client.clone()
             .delete_item()
             .table_name(table_name)
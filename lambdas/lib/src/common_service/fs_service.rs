@@ -0,0 +1,272 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Context, Result};
+use axum::body::Bytes;
+
+use crate::analytics::heatmap::HeatmapConfig;
+use crate::analytics::tripwires::{Tripwire, TripwireSummary};
+use crate::analytics::zones::{Zone, ZoneSummary};
+use crate::exports::columnar::ColumnarFormat;
+use crate::common_structs::{AnalysisSummary, AnalysisType, ChildJob, JobAttempt, JobDetails, JobQuery, JobStatus, LastEvaluatedKey, RekognitionJobTableEntry, TrackingPostProcessing, TrackingSummary, VideoMetadata};
+use super::memory_service::InMemoryJobRepository;
+use super::traits::{JobRepository, ObjectStore};
+
+
+// Filesystem backends for running the stack offline (local development).
+
+// Objects are saved as `{root}/{bucket_name}/{key}`, with the content type in a `.content-type` sidecar file.
+// Presigned URLs point to `{base_url}/{bucket_name}/{key}` and are expected to be served by the local server.
+#[derive(Debug, Clone)]
+pub struct FsObjectStore {
+    root: PathBuf,
+    base_url: String,
+}
+
+impl FsObjectStore {
+    pub fn new(root: &Path, base_url: &str) -> Self {
+        Self {
+            root: root.to_owned(),
+            base_url: base_url.trim_end_matches('/').to_owned(),
+        }
+    }
+
+    fn object_path(&self, bucket_name: &str, key: &str) -> Result<PathBuf> {
+        let relative = Path::new(bucket_name).join(key);
+        if relative.components().any(|component| !matches!(component, Component::Normal(_))) {
+            bail!("Invalid object key: {}/{}", bucket_name, key)
+        }
+        Ok(self.root.join(relative))
+    }
+
+    fn content_type_path(path: &Path) -> PathBuf {
+        let mut path = path.as_os_str().to_owned();
+        path.push(".content-type");
+        PathBuf::from(path)
+    }
+
+    fn object_url(&self, bucket_name: &str, key: &str) -> String {
+        format!("{}/{}/{}", self.base_url, bucket_name, key)
+    }
+}
+
+impl ObjectStore for FsObjectStore {
+    async fn put_object(&self, bucket_name: &str, key: &str, bytes: Bytes, content_type: &str) -> Result<()> {
+        let path = self.object_path(bucket_name, key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, bytes)?;
+        fs::write(Self::content_type_path(&path), content_type)?;
+        Ok(())
+    }
+
+    async fn get_object(&self, bucket_name: &str, key: &str) -> Result<(Vec<u8>, String)> {
+        let path = self.object_path(bucket_name, key)?;
+        let bytes = fs::read(&path).with_context(|| format!("Object does not exist for key: {}", key))?;
+        let content_type = fs::read_to_string(Self::content_type_path(&path)).unwrap_or("application/octet-stream".to_owned());
+        Ok((bytes, content_type))
+    }
+
+    async fn delete_object(&self, bucket_name: &str, key: &str) -> Result<()> {
+        // same as S3: only the exact key is deleted, and a missing key is not an error
+        let path = self.object_path(bucket_name, key)?;
+        if path.is_file() {
+            fs::remove_file(&path)?;
+            let _ = fs::remove_file(Self::content_type_path(&path));
+        }
+        Ok(())
+    }
+
+    async fn put_object_presigned(&self, bucket_name: &str, key: &str, _content_type: &str) -> Result<String> {
+        self.object_path(bucket_name, key)?;
+        Ok(self.object_url(bucket_name, key))
+    }
+
+    async fn get_object_presigned(&self, bucket_name: &str, key: &str) -> Result<String> {
+        self.object_path(bucket_name, key)?;
+        Ok(self.object_url(bucket_name, key))
+    }
+}
+
+
+// Job table kept in memory and written to a single JSON file ({ table_name: [entry] }) after every change.
+#[derive(Debug, Clone)]
+pub struct JsonFileJobRepository {
+    path: PathBuf,
+    memory: InMemoryJobRepository,
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl JsonFileJobRepository {
+    pub fn new(path: &Path) -> Result<Self> {
+        let memory = InMemoryJobRepository::new();
+        if path.exists() {
+            let json = fs::read_to_string(path)?;
+            let tables: HashMap<String, Vec<RekognitionJobTableEntry>> = serde_json::from_str(&json).with_context(|| format!("Invalid job table file: {}", path.display()))?;
+            for (table_name, entries) in tables {
+                for entry in entries {
+                    memory.put_entry(&table_name, entry);
+                }
+            }
+        }
+
+        Ok(Self {
+            path: path.to_owned(),
+            memory,
+            write_lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    fn persist(&self) -> Result<()> {
+        let _guard = self.write_lock.lock().unwrap();
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(&self.memory.entries())?;
+        fs::write(&self.path, json)?;
+        Ok(())
+    }
+}
+
+impl JobRepository for JsonFileJobRepository {
+    async fn register_entry(&self, table_name: &str, user_id: &str, job_id: &str, s3_folder_name: &str, file_name: &str) -> Result<()> {
+        self.memory.register_entry(table_name, user_id, job_id, s3_folder_name, file_name).await?;
+        self.persist()
+    }
+
+    async fn update_summary(&self, table_name: &str, job_id: &str, tracking_summary: &TrackingSummary) -> Result<()> {
+        self.memory.update_summary(table_name, job_id, tracking_summary).await?;
+        self.persist()
+    }
+
+    async fn update_metadata(&self, table_name: &str, job_id: &str, metadata: &VideoMetadata) -> Result<()> {
+        self.memory.update_metadata(table_name, job_id, metadata).await?;
+        self.persist()
+    }
+
+    async fn update_job_status(&self, table_name: &str, job_id: &str, status: JobStatus) -> Result<()> {
+        self.memory.update_job_status(table_name, job_id, status).await?;
+        self.persist()
+    }
+
+    async fn update_job_status_if(&self, table_name: &str, job_id: &str, status: JobStatus, expected: JobStatus) -> Result<bool> {
+        let updated = self.memory.update_job_status_if(table_name, job_id, status, expected).await?;
+        self.persist()?;
+        Ok(updated)
+    }
+
+    async fn update_zones(&self, table_name: &str, job_id: &str, zones: &[Zone]) -> Result<()> {
+        self.memory.update_zones(table_name, job_id, zones).await?;
+        self.persist()
+    }
+
+    async fn update_zone_summaries(&self, table_name: &str, job_id: &str, zone_summaries: &[ZoneSummary]) -> Result<()> {
+        self.memory.update_zone_summaries(table_name, job_id, zone_summaries).await?;
+        self.persist()
+    }
+
+    async fn update_tripwires(&self, table_name: &str, job_id: &str, tripwires: &[Tripwire]) -> Result<()> {
+        self.memory.update_tripwires(table_name, job_id, tripwires).await?;
+        self.persist()
+    }
+
+    async fn update_tripwire_summaries(&self, table_name: &str, job_id: &str, tripwire_summaries: &[TripwireSummary]) -> Result<()> {
+        self.memory.update_tripwire_summaries(table_name, job_id, tripwire_summaries).await?;
+        self.persist()
+    }
+
+    async fn update_heatmap_config(&self, table_name: &str, job_id: &str, heatmap_config: &HeatmapConfig) -> Result<()> {
+        self.memory.update_heatmap_config(table_name, job_id, heatmap_config).await?;
+        self.persist()
+    }
+
+    async fn update_post_processing(&self, table_name: &str, job_id: &str, post_processing: &TrackingPostProcessing) -> Result<()> {
+        self.memory.update_post_processing(table_name, job_id, post_processing).await?;
+        self.persist()
+    }
+
+    async fn update_columnar_formats(&self, table_name: &str, job_id: &str, columnar_formats: &[ColumnarFormat]) -> Result<()> {
+        self.memory.update_columnar_formats(table_name, job_id, columnar_formats).await?;
+        self.persist()
+    }
+
+    async fn update_analysis_type(&self, table_name: &str, job_id: &str, analysis_type: AnalysisType) -> Result<()> {
+        self.memory.update_analysis_type(table_name, job_id, analysis_type).await?;
+        self.persist()
+    }
+
+    async fn update_analysis_summary(&self, table_name: &str, job_id: &str, analysis_summary: &AnalysisSummary) -> Result<()> {
+        self.memory.update_analysis_summary(table_name, job_id, analysis_summary).await?;
+        self.persist()
+    }
+
+    async fn update_children(&self, table_name: &str, job_id: &str, children: &BTreeMap<String, ChildJob>) -> Result<()> {
+        self.memory.update_children(table_name, job_id, children).await?;
+        self.persist()
+    }
+
+    async fn update_child_job(&self, table_name: &str, job_id: &str, child_job_id: &str, child: &ChildJob) -> Result<RekognitionJobTableEntry> {
+        let entry = self.memory.update_child_job(table_name, job_id, child_job_id, child).await?;
+        self.persist()?;
+        Ok(entry)
+    }
+
+    async fn update_parent_job_status(&self, table_name: &str, job_id: &str, status: JobStatus, children_version: u64) -> Result<bool> {
+        let updated = self.memory.update_parent_job_status(table_name, job_id, status, children_version).await?;
+        self.persist()?;
+        Ok(updated)
+    }
+
+    async fn update_parent_job_id(&self, table_name: &str, job_id: &str, parent_job_id: &str) -> Result<()> {
+        self.memory.update_parent_job_id(table_name, job_id, parent_job_id).await?;
+        self.persist()
+    }
+
+    async fn update_timeout_reason(&self, table_name: &str, job_id: &str, timeout_reason: &str) -> Result<()> {
+        self.memory.update_timeout_reason(table_name, job_id, timeout_reason).await?;
+        self.persist()
+    }
+
+    async fn update_user_id(&self, table_name: &str, job_id: &str, user_id: &str) -> Result<()> {
+        self.memory.update_user_id(table_name, job_id, user_id).await?;
+        self.persist()
+    }
+
+    async fn claim_retry(&self, table_name: &str, job_id: &str, previous_attempt: &JobAttempt) -> Result<bool> {
+        let claimed = self.memory.claim_retry(table_name, job_id, previous_attempt).await?;
+        self.persist()?;
+        Ok(claimed)
+    }
+
+    async fn restart_job(&self, table_name: &str, job_id: &str, rekognition_job_id: &str, previous_attempt: &JobAttempt) -> Result<bool> {
+        let restarted = self.memory.restart_job(table_name, job_id, rekognition_job_id, previous_attempt).await?;
+        self.persist()?;
+        Ok(restarted)
+    }
+
+    async fn update_job_details(&self, table_name: &str, job_id: &str, details: &JobDetails) -> Result<RekognitionJobTableEntry> {
+        let entry = self.memory.update_job_details(table_name, job_id, details).await?;
+        self.persist()?;
+        Ok(entry)
+    }
+
+    async fn get_entry_single(&self, table_name: &str, job_id: &str) -> Result<RekognitionJobTableEntry> {
+        self.memory.get_entry_single(table_name, job_id).await
+    }
+
+    async fn scan_in_progress_entries(&self, table_name: &str, started_before: u64) -> Result<Vec<RekognitionJobTableEntry>> {
+        self.memory.scan_in_progress_entries(table_name, started_before).await
+    }
+
+    async fn query_entries(&self, table_name: &str, user_id: &str, query: &JobQuery, last_evaluated_key: Option<LastEvaluatedKey>) -> Result<(Vec<RekognitionJobTableEntry>, Option<LastEvaluatedKey>)> {
+        self.memory.query_entries(table_name, user_id, query, last_evaluated_key).await
+    }
+
+    async fn delete_entry(&self, table_name: &str, job_id: &str) -> Result<()> {
+        self.memory.delete_entry(table_name, job_id).await?;
+        self.persist()
+    }
+}
@@ -0,0 +1,439 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, bail, Context, Result};
+use aws_sdk_rekognition::types::PersonDetection;
+use axum::body::Bytes;
+use uuid::Uuid;
+
+use crate::analytics::faces::FaceDetection;
+use crate::analytics::heatmap::HeatmapConfig;
+use crate::analytics::labels::LabelDetection;
+use crate::analytics::segments::Segment;
+use crate::analytics::tripwires::{Tripwire, TripwireSummary};
+use crate::analytics::zones::{Zone, ZoneSummary};
+use crate::exports::columnar::ColumnarFormat;
+use crate::common_structs::{current_timestamp, AnalysisSummary, AnalysisType, ChildJob, JobAttempt, JobDetails, JobQuery, JobStatus, LastEvaluatedKey, RekognitionJobTableEntry, SortOrder, TrackingPostProcessing, TrackingSummary, VideoMetadata};
+use super::traits::{JobRepository, ObjectStore, VideoAnalyzer};
+
+
+// In-memory backends for running handlers without AWS (tests, local development).
+// Clones share the same underlying storage.
+
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryObjectStore {
+    // "{bucket_name}/{key}": (bytes, content_type)
+    objects: Arc<Mutex<HashMap<String, (Bytes, String)>>>,
+}
+
+impl InMemoryObjectStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains_object(&self, bucket_name: &str, key: &str) -> bool {
+        let objects = self.objects.lock().unwrap();
+        objects.contains_key(&Self::object_path(bucket_name, key))
+    }
+
+    fn object_path(bucket_name: &str, key: &str) -> String {
+        format!("{}/{}", bucket_name, key)
+    }
+}
+
+impl ObjectStore for InMemoryObjectStore {
+    async fn put_object(&self, bucket_name: &str, key: &str, bytes: Bytes, content_type: &str) -> Result<()> {
+        let mut objects = self.objects.lock().unwrap();
+        objects.insert(Self::object_path(bucket_name, key), (bytes, content_type.to_owned()));
+        Ok(())
+    }
+
+    async fn get_object(&self, bucket_name: &str, key: &str) -> Result<(Vec<u8>, String)> {
+        let objects = self.objects.lock().unwrap();
+        let (bytes, content_type) = objects
+            .get(&Self::object_path(bucket_name, key))
+            .with_context(|| format!("Object does not exist for key: {}", key))?;
+        Ok((bytes.to_vec(), content_type.to_owned()))
+    }
+
+    async fn delete_object(&self, bucket_name: &str, key: &str) -> Result<()> {
+        // same as S3: deleting a key that does not exist is not an error
+        let mut objects = self.objects.lock().unwrap();
+        objects.remove(&Self::object_path(bucket_name, key));
+        Ok(())
+    }
+
+    async fn put_object_presigned(&self, bucket_name: &str, key: &str, _content_type: &str) -> Result<String> {
+        Ok(format!("memory://{}", Self::object_path(bucket_name, key)))
+    }
+
+    async fn get_object_presigned(&self, bucket_name: &str, key: &str) -> Result<String> {
+        Ok(format!("memory://{}", Self::object_path(bucket_name, key)))
+    }
+}
+
+
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryJobRepository {
+    // table_name: { job_id: entry }
+    tables: Arc<Mutex<HashMap<String, HashMap<String, RekognitionJobTableEntry>>>>,
+    // whether the updates of parent job ids fail
+    failing_parent_job_id_updates: bool,
+}
+
+impl InMemoryJobRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_failing_parent_job_id_updates(mut self) -> Self {
+        self.failing_parent_job_id_updates = true;
+        self
+    }
+
+    pub fn put_entry(&self, table_name: &str, entry: RekognitionJobTableEntry) {
+        let mut tables = self.tables.lock().unwrap();
+        tables.entry(table_name.to_owned()).or_default().insert(entry.job_id.clone(), entry);
+    }
+
+    // all entries, grouped by table name
+    pub fn entries(&self) -> HashMap<String, Vec<RekognitionJobTableEntry>> {
+        let tables = self.tables.lock().unwrap();
+        tables
+            .iter()
+            .map(|(table_name, table)| (table_name.to_owned(), table.values().cloned().collect()))
+            .collect()
+    }
+
+    // also for setting fields without a repository method in tests (ex: request_timestamp)
+    pub fn update_entry(&self, table_name: &str, job_id: &str, update: impl FnOnce(&mut RekognitionJobTableEntry)) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+        let entry = tables
+            .get_mut(table_name)
+            .and_then(|table| table.get_mut(job_id))
+            .with_context(|| format!("Job does not exist for id: {}!", job_id))?;
+        update(entry);
+        Ok(())
+    }
+
+    // conditional update, returns false without updating if the condition does not hold
+    // or if the job does not exist (a Dynamo condition fails on a missing item)
+    fn update_entry_if(&self, table_name: &str, job_id: &str, condition: impl FnOnce(&RekognitionJobTableEntry) -> bool, update: impl FnOnce(&mut RekognitionJobTableEntry)) -> Result<bool> {
+        let mut tables = self.tables.lock().unwrap();
+        let Some(entry) = tables.get_mut(table_name).and_then(|table| table.get_mut(job_id)) else {
+            return Ok(false);
+        };
+        if !condition(entry) {
+            return Ok(false);
+        }
+        update(entry);
+        Ok(true)
+    }
+}
+
+impl JobRepository for InMemoryJobRepository {
+    async fn register_entry(&self, table_name: &str, user_id: &str, job_id: &str, s3_folder_name: &str, file_name: &str) -> Result<()> {
+        let entry = RekognitionJobTableEntry::new(job_id, user_id, s3_folder_name, file_name);
+        self.put_entry(table_name, entry);
+        Ok(())
+    }
+
+    async fn update_summary(&self, table_name: &str, job_id: &str, tracking_summary: &TrackingSummary) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.tracking_summary = Some(tracking_summary.to_owned()))
+    }
+
+    async fn update_metadata(&self, table_name: &str, job_id: &str, metadata: &VideoMetadata) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.video_metadata = Some(metadata.to_owned()))
+    }
+
+    async fn update_job_status(&self, table_name: &str, job_id: &str, status: JobStatus) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.job_status = status)
+    }
+
+    async fn update_job_status_if(&self, table_name: &str, job_id: &str, status: JobStatus, expected: JobStatus) -> Result<bool> {
+        self.update_entry_if(table_name, job_id, |entry| entry.job_status == expected, |entry| entry.job_status = status)
+    }
+
+    async fn update_zones(&self, table_name: &str, job_id: &str, zones: &[Zone]) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.zones = Some(zones.to_vec()))
+    }
+
+    async fn update_zone_summaries(&self, table_name: &str, job_id: &str, zone_summaries: &[ZoneSummary]) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.zone_summaries = Some(zone_summaries.to_vec()))
+    }
+
+    async fn update_tripwires(&self, table_name: &str, job_id: &str, tripwires: &[Tripwire]) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.tripwires = Some(tripwires.to_vec()))
+    }
+
+    async fn update_tripwire_summaries(&self, table_name: &str, job_id: &str, tripwire_summaries: &[TripwireSummary]) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.tripwire_summaries = Some(tripwire_summaries.to_vec()))
+    }
+
+    async fn update_heatmap_config(&self, table_name: &str, job_id: &str, heatmap_config: &HeatmapConfig) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.heatmap_config = Some(heatmap_config.clone()))
+    }
+
+    async fn update_post_processing(&self, table_name: &str, job_id: &str, post_processing: &TrackingPostProcessing) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.post_processing = Some(post_processing.clone()))
+    }
+
+    async fn update_columnar_formats(&self, table_name: &str, job_id: &str, columnar_formats: &[ColumnarFormat]) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.columnar_formats = Some(columnar_formats.to_vec()))
+    }
+
+    async fn update_analysis_type(&self, table_name: &str, job_id: &str, analysis_type: AnalysisType) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.analysis_type = Some(analysis_type))
+    }
+
+    async fn update_analysis_summary(&self, table_name: &str, job_id: &str, analysis_summary: &AnalysisSummary) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.analysis_summary = Some(analysis_summary.clone()))
+    }
+
+    async fn update_children(&self, table_name: &str, job_id: &str, children: &BTreeMap<String, ChildJob>) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.children = Some(children.clone()))
+    }
+
+    async fn update_child_job(&self, table_name: &str, job_id: &str, child_job_id: &str, child: &ChildJob) -> Result<RekognitionJobTableEntry> {
+        let mut updated: Option<RekognitionJobTableEntry> = None;
+        self.update_entry(table_name, job_id, |entry| {
+            entry.children.get_or_insert_with(BTreeMap::new).insert(child_job_id.to_owned(), child.clone());
+            entry.children_version = Some(entry.children_version.unwrap_or_default() + 1);
+            updated = Some(entry.clone());
+        })?;
+        updated.with_context(|| format!("Job does not exist for id: {}!", job_id))
+    }
+
+    async fn update_parent_job_status(&self, table_name: &str, job_id: &str, status: JobStatus, children_version: u64) -> Result<bool> {
+        self.update_entry_if(table_name, job_id, |entry| entry.children_version.unwrap_or_default() == children_version, |entry| entry.job_status = status)
+    }
+
+    async fn update_parent_job_id(&self, table_name: &str, job_id: &str, parent_job_id: &str) -> Result<()> {
+        if self.failing_parent_job_id_updates {
+            bail!("Parent job id of {} failed to update", job_id)
+        }
+        self.update_entry(table_name, job_id, |entry| entry.parent_job_id = Some(parent_job_id.to_owned()))
+    }
+
+    async fn update_timeout_reason(&self, table_name: &str, job_id: &str, timeout_reason: &str) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.timeout_reason = Some(timeout_reason.to_owned()))
+    }
+
+    async fn update_user_id(&self, table_name: &str, job_id: &str, user_id: &str) -> Result<()> {
+        self.update_entry(table_name, job_id, |entry| entry.user_id = user_id.to_owned())
+    }
+
+    async fn claim_retry(&self, table_name: &str, job_id: &str, previous_attempt: &JobAttempt) -> Result<bool> {
+        let is_previous_attempt = |entry: &RekognitionJobTableEntry| entry.job_status == previous_attempt.job_status && entry.rekognition_job_id() == previous_attempt.rekognition_job_id;
+        self.update_entry_if(table_name, job_id, is_previous_attempt, |entry| entry.job_status = JobStatus::InProgress)
+    }
+
+    async fn restart_job(&self, table_name: &str, job_id: &str, rekognition_job_id: &str, previous_attempt: &JobAttempt) -> Result<bool> {
+        let is_claimed = |entry: &RekognitionJobTableEntry| entry.job_status == JobStatus::InProgress && entry.rekognition_job_id() == previous_attempt.rekognition_job_id;
+        self.update_entry_if(table_name, job_id, is_claimed, |entry| {
+            entry.attempts.get_or_insert_with(Vec::new).push(previous_attempt.clone());
+            entry.rekognition_job_id = Some(rekognition_job_id.to_owned());
+            entry.attempt_started_at = Some(current_timestamp());
+            entry.timeout_reason = None;
+        })
+    }
+
+    async fn update_job_details(&self, table_name: &str, job_id: &str, details: &JobDetails) -> Result<RekognitionJobTableEntry> {
+        if details.is_empty() {
+            bail!("No job details to update")
+        }
+        self.update_entry(table_name, job_id, |entry| {
+            if let Some(title) = &details.title {
+                entry.title = Some(title.to_owned());
+            }
+            if let Some(description) = &details.description {
+                entry.description = Some(description.to_owned());
+            }
+            if let Some(tags) = &details.tags {
+                entry.tags = Some(tags.to_owned());
+            }
+            if let Some(location) = &details.location {
+                entry.location = Some(location.to_owned());
+            }
+        })?;
+        self.get_entry_single(table_name, job_id).await
+    }
+
+    async fn get_entry_single(&self, table_name: &str, job_id: &str) -> Result<RekognitionJobTableEntry> {
+        let tables = self.tables.lock().unwrap();
+        tables
+            .get(table_name)
+            .and_then(|table| table.get(job_id))
+            .cloned()
+            .ok_or_else(|| anyhow!("Job does not exist for id: {}!", job_id))
+    }
+
+    async fn scan_in_progress_entries(&self, table_name: &str, started_before: u64) -> Result<Vec<RekognitionJobTableEntry>> {
+        let tables = self.tables.lock().unwrap();
+        Ok(tables
+            .get(table_name)
+            .map(|table| table.values().filter(|entry| entry.job_status == JobStatus::InProgress && entry.attempt_started_at() < started_before).cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn query_entries(&self, table_name: &str, user_id: &str, query: &JobQuery, last_evaluated_key: Option<LastEvaluatedKey>) -> Result<(Vec<RekognitionJobTableEntry>, Option<LastEvaluatedKey>)> {
+        let tables = self.tables.lock().unwrap();
+        let mut entries: Vec<RekognitionJobTableEntry> = tables
+            .get(table_name)
+            .map(|table| table.values().filter(|entry| entry.user_id == user_id).cloned().collect())
+            .unwrap_or_default();
+
+        // same order as the `gsi-userid` query, newest first by default
+        entries.sort_by(|a, b| (a.request_timestamp, &a.job_id).cmp(&(b.request_timestamp, &b.job_id)));
+        if query.sort_order == SortOrder::Desc {
+            entries.reverse();
+        }
+
+        if let Some(last_evaluated_key) = last_evaluated_key {
+            let start = (last_evaluated_key.request_timestamp, last_evaluated_key.job_id);
+            entries.retain(|entry| match query.sort_order {
+                SortOrder::Asc => (entry.request_timestamp, entry.job_id.clone()) > start,
+                SortOrder::Desc => (entry.request_timestamp, entry.job_id.clone()) < start,
+            });
+        }
+        entries.retain(|entry| query.filter.requested_from.is_none_or(|from| entry.request_timestamp >= from) && query.filter.requested_to.is_none_or(|to| entry.request_timestamp <= to));
+
+        // like Dynamo, the limit applies before the other filters
+        let mut next_key: Option<LastEvaluatedKey> = None;
+        if let Some(limit) = query.limit.map(|limit| limit.max(0) as usize) {
+            if entries.len() > limit {
+                entries.truncate(limit);
+                next_key = entries.last().map(|entry| LastEvaluatedKey::new(&entry.job_id, &entry.user_id, &entry.request_timestamp));
+            }
+        }
+        entries.retain(|entry| query.filter.matches(entry));
+
+        Ok((entries, next_key))
+    }
+
+    async fn delete_entry(&self, table_name: &str, job_id: &str) -> Result<()> {
+        let mut tables = self.tables.lock().unwrap();
+        if let Some(table) = tables.get_mut(table_name) {
+            table.remove(job_id);
+        }
+        Ok(())
+    }
+}
+
+
+// Every started job is "finished" right away (unless its status is set), and returns the results (and metadata) given at creation.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryVideoAnalyzer {
+    // job_id: analysis type
+    jobs: Arc<Mutex<HashMap<String, AnalysisType>>>,
+    // job_id: status, succeeded if not set
+    statuses: Arc<Mutex<HashMap<String, JobStatus>>>,
+    detections: Vec<PersonDetection>,
+    labels: Vec<LabelDetection>,
+    faces: Vec<FaceDetection>,
+    segments: Vec<Segment>,
+    metadata: Option<VideoMetadata>,
+    // analysis types whose jobs fail to start
+    failing_starts: Vec<AnalysisType>,
+}
+
+impl InMemoryVideoAnalyzer {
+    pub fn new(detections: Vec<PersonDetection>, metadata: Option<VideoMetadata>) -> Self {
+        Self {
+            detections,
+            metadata,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_labels(mut self, labels: Vec<LabelDetection>) -> Self {
+        self.labels = labels;
+        self
+    }
+
+    pub fn with_faces(mut self, faces: Vec<FaceDetection>) -> Self {
+        self.faces = faces;
+        self
+    }
+
+    pub fn with_segments(mut self, segments: Vec<Segment>) -> Self {
+        self.segments = segments;
+        self
+    }
+
+    pub fn with_failing_starts(mut self, failing_starts: Vec<AnalysisType>) -> Self {
+        self.failing_starts = failing_starts;
+        self
+    }
+
+    pub fn set_job_status(&self, job_id: &str, status: JobStatus) {
+        let mut statuses = self.statuses.lock().unwrap();
+        statuses.insert(job_id.to_owned(), status);
+    }
+
+    pub fn job_ids(&self) -> Vec<String> {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.keys().cloned().collect()
+    }
+
+    fn start_job(&self, analysis_type: AnalysisType) -> Result<String> {
+        if self.failing_starts.contains(&analysis_type) {
+            bail!("{:?} job failed to start", analysis_type)
+        }
+        let job_id = Uuid::new_v4().to_string();
+        let mut jobs = self.jobs.lock().unwrap();
+        jobs.insert(job_id.clone(), analysis_type);
+        Ok(job_id)
+    }
+
+    fn results<T: Clone>(&self, job_id: &str, analysis_type: AnalysisType, results: &[T]) -> Result<(Vec<T>, Option<VideoMetadata>)> {
+        let jobs = self.jobs.lock().unwrap();
+        match jobs.get(job_id) {
+            Some(job_type) if *job_type == analysis_type => Ok((results.to_vec(), self.metadata.clone())),
+            _ => Err(anyhow!("{:?} job does not exist for id: {}!", analysis_type, job_id)),
+        }
+    }
+}
+
+impl VideoAnalyzer for InMemoryVideoAnalyzer {
+    async fn start_tracking(&self, _s3_bucket_name: &str, _s3_key_name: &str, _role_arn: &str, _topic_arn: &str, _job_tag: Option<&str>) -> Result<String> {
+        self.start_job(AnalysisType::PersonTracking)
+    }
+
+    async fn get_persons_detection_results(&self, job_id: &str) -> Result<(Vec<PersonDetection>, Option<VideoMetadata>)> {
+        self.results(job_id, AnalysisType::PersonTracking, &self.detections)
+    }
+
+    async fn start_label_detection(&self, _s3_bucket_name: &str, _s3_key_name: &str, _role_arn: &str, _topic_arn: &str, _job_tag: Option<&str>) -> Result<String> {
+        self.start_job(AnalysisType::LabelDetection)
+    }
+
+    async fn get_label_detection_results(&self, job_id: &str) -> Result<(Vec<LabelDetection>, Option<VideoMetadata>)> {
+        self.results(job_id, AnalysisType::LabelDetection, &self.labels)
+    }
+
+    async fn start_face_detection(&self, _s3_bucket_name: &str, _s3_key_name: &str, _role_arn: &str, _topic_arn: &str, _job_tag: Option<&str>) -> Result<String> {
+        self.start_job(AnalysisType::FaceDetection)
+    }
+
+    async fn get_face_detection_results(&self, job_id: &str) -> Result<(Vec<FaceDetection>, Option<VideoMetadata>)> {
+        self.results(job_id, AnalysisType::FaceDetection, &self.faces)
+    }
+
+    async fn start_segment_detection(&self, _s3_bucket_name: &str, _s3_key_name: &str, _role_arn: &str, _topic_arn: &str, _job_tag: Option<&str>) -> Result<String> {
+        self.start_job(AnalysisType::SegmentDetection)
+    }
+
+    async fn get_segment_detection_results(&self, job_id: &str) -> Result<(Vec<Segment>, Option<VideoMetadata>)> {
+        self.results(job_id, AnalysisType::SegmentDetection, &self.segments)
+    }
+
+    async fn get_job_status(&self, job_id: &str, analysis_type: AnalysisType) -> Result<JobStatus> {
+        let jobs = self.jobs.lock().unwrap();
+        if jobs.get(job_id) != Some(&analysis_type) {
+            bail!("{:?} job does not exist for id: {}!", analysis_type, job_id)
+        }
+        let statuses = self.statuses.lock().unwrap();
+        Ok(statuses.get(job_id).cloned().unwrap_or(JobStatus::Succeeded))
+    }
+}
@@ -1,14 +1,22 @@
+use anyhow::Result;
 use aws_config::SdkConfig;
 
+use crate::common_structs::{ChildJob, JobStatus};
+
+pub mod traits;
 pub mod rekognition_service;
 pub mod s3_service;
 pub mod dynamo_service;
+pub mod memory_service;
+pub mod fs_service;
+
+pub use traits::{JobRepository, ObjectStore, VideoAnalyzer};
 
 #[derive(Debug, Clone)]
-pub struct CommonService {
-    pub s3: s3_service::S3Service,
-    pub dynamo: dynamo_service::DynamoService,
-    pub rekognition: rekognition_service::RekognitionService
+pub struct CommonService<S = s3_service::S3Service, D = dynamo_service::DynamoService, R = rekognition_service::RekognitionService> {
+    pub s3: S,
+    pub dynamo: D,
+    pub rekognition: R
 }
 
 impl CommonService {
@@ -23,4 +31,36 @@ impl CommonService {
             rekognition: rekognition_service::RekognitionService::new(&rekognition_client)
         }
     }
-}
\ No newline at end of file
+}
+
+impl<S: ObjectStore, D: JobRepository, R: VideoAnalyzer> CommonService<S, D, R> {
+    pub fn with_services(s3: S, dynamo: D, rekognition: R) -> Self {
+        Self { s3, dynamo, rekognition }
+    }
+
+    // child of a multi-analysis job: record its status in the parent, which finishes with its last child
+    // children finishing at the same time: only the status computed from the latest children is written
+    pub async fn update_parent_job(&self, table_name: &str, job_id: &str, status: JobStatus) -> Result<()> {
+        let entry = self.dynamo.get_entry_single(table_name, job_id).await?;
+        let Some(parent_job_id) = entry.parent_job_id else {
+            return Ok(());
+        };
+
+        let child = ChildJob { analysis_type: entry.analysis_type.unwrap_or_default(), job_status: status };
+        let parent = self.dynamo.update_child_job(table_name, &parent_job_id, job_id, &child).await?;
+        let children = parent.children.unwrap_or_default();
+        let parent_status = JobStatus::from_children(children.values().map(|child| &child.job_status));
+        self.dynamo.update_parent_job_status(table_name, &parent_job_id, parent_status, parent.children_version.unwrap_or_default()).await?;
+        Ok(())
+    }
+
+    // final status of an in progress job (and of its multi-analysis job)
+    // returns false if the job is no longer in progress: finished, cancelled or timed out in the meantime, nothing is written
+    pub async fn finish_job(&self, table_name: &str, job_id: &str, status: JobStatus) -> Result<bool> {
+        if !self.dynamo.update_job_status_if(table_name, job_id, status.clone(), JobStatus::InProgress).await? {
+            return Ok(false);
+        }
+        self.update_parent_job(table_name, job_id, status).await?;
+        Ok(true)
+    }
+}
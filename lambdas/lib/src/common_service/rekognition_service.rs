@@ -1,8 +1,11 @@
-
 use anyhow::{Context, Result};
-use aws_sdk_rekognition::types::{NotificationChannel, PersonDetection, S3Object, Video};
+use aws_sdk_rekognition::types::{BoundingBox, FaceAttributes, LabelDetectionSortBy, NotificationChannel, PersonDetection, S3Object, SegmentType, Video, VideoJobStatus};
 
-use crate::common_structs::VideoMetadata;
+use crate::analytics::faces::FaceDetection;
+use crate::analytics::labels::LabelDetection;
+use crate::analytics::segments::{Segment, SegmentKind};
+use crate::common_structs::{AnalysisType, JobStatus, PersonBoundingBox, VideoMetadata};
+use super::traits::VideoAnalyzer;
 
 #[derive(Debug, Clone)]
 pub struct RekognitionService {
@@ -16,32 +19,61 @@ impl RekognitionService {
         }
     }
 
-    pub async fn start_tracking(
-        &self,
-        s3_bucket_name: &str,
-        s3_key_name: &str,
-        role_arn: &str,
-        topic_arn: &str
-    ) -> Result<String> {
-
+    fn video(s3_bucket_name: &str, s3_key_name: &str) -> Video {
         let s3_object = S3Object::builder()
             .bucket(s3_bucket_name)
             .name(s3_key_name)
             .build();
 
-        let video = Video::builder()
-            .s3_object(s3_object.clone())
-            .build();
+        Video::builder()
+            .s3_object(s3_object)
+            .build()
+    }
 
+    fn notification_channel(role_arn: &str, topic_arn: &str) -> Result<NotificationChannel> {
         let notification_channel = NotificationChannel::builder()
             .role_arn(role_arn)
             .sns_topic_arn(topic_arn)
             .build()?;
+        Ok(notification_channel)
+    }
+
+    fn job_status(status: Option<VideoJobStatus>) -> JobStatus {
+        match status {
+            Some(VideoJobStatus::Succeeded) => JobStatus::Succeeded,
+            Some(VideoJobStatus::Failed) => JobStatus::Failed,
+            Some(VideoJobStatus::InProgress) => JobStatus::InProgress,
+            Some(status) => JobStatus::Unknown(status.as_str().to_owned()),
+            None => JobStatus::Unknown("".to_owned()),
+        }
+    }
+
+    fn bounding_box(bounding_box: &Option<BoundingBox>) -> PersonBoundingBox {
+        let bounding_box = bounding_box.clone().unwrap_or_else(|| BoundingBox::builder().build());
+        PersonBoundingBox {
+            width: bounding_box.width.unwrap_or(0.0),
+            height: bounding_box.height.unwrap_or(0.0),
+            left: bounding_box.left.unwrap_or(0.0),
+            top: bounding_box.top.unwrap_or(0.0),
+        }
+    }
+}
+
+impl VideoAnalyzer for RekognitionService {
+    async fn start_tracking(
+        &self,
+        s3_bucket_name: &str,
+        s3_key_name: &str,
+        role_arn: &str,
+        topic_arn: &str,
+        job_tag: Option<&str>
+    ) -> Result<String> {
 
         let response = self.client.clone()
             .start_person_tracking()
-            .video(video)
-            .notification_channel(notification_channel)
+            .video(Self::video(s3_bucket_name, s3_key_name))
+            .notification_channel(Self::notification_channel(role_arn, topic_arn)?)
+            .set_job_tag(job_tag.map(str::to_owned))
             .send()
             .await?;
 
@@ -54,7 +86,7 @@ impl RekognitionService {
     }
 
 
-    pub async fn get_persons_detection_results(
+    async fn get_persons_detection_results(
         &self,
         job_id: &str
     ) -> Result<(Vec<PersonDetection>, Option<VideoMetadata>)> {
@@ -93,4 +125,196 @@ impl RekognitionService {
         Ok((persons_detection, metadata))
     }
 
-}
\ No newline at end of file
+
+    async fn start_label_detection(&self, s3_bucket_name: &str, s3_key_name: &str, role_arn: &str, topic_arn: &str, job_tag: Option<&str>) -> Result<String> {
+        let response = self.client.clone()
+            .start_label_detection()
+            .video(Self::video(s3_bucket_name, s3_key_name))
+            .notification_channel(Self::notification_channel(role_arn, topic_arn)?)
+            .set_job_tag(job_tag.map(str::to_owned))
+            .send()
+            .await?;
+
+        let job_id = response.job_id.context("Job Id not available")?;
+        println!("Start label detection job id: {}", job_id);
+        Ok(job_id)
+    }
+
+    async fn get_label_detection_results(&self, job_id: &str) -> Result<(Vec<LabelDetection>, Option<VideoMetadata>)> {
+        let builder = self.client.clone()
+            .get_label_detection()
+            .job_id(job_id)
+            .sort_by(LabelDetectionSortBy::Timestamp);
+
+        let mut labels: Vec<LabelDetection> = vec![];
+        let mut metadata: Option<VideoMetadata> = None;
+        let mut next_token: Option<String> = None;
+        loop {
+            let response = builder.clone().set_next_token(next_token).send().await?;
+            if metadata.is_none() {
+                metadata = response.video_metadata.map(VideoMetadata::new);
+            }
+
+            for detection in response.labels.unwrap_or_default() {
+                let Some(label) = detection.label else {
+                    continue;
+                };
+                labels.push(LabelDetection {
+                    timestamp: detection.timestamp,
+                    name: label.name.unwrap_or_default(),
+                    confidence: label.confidence.unwrap_or(0.0),
+                    bounding_boxes: label
+                        .instances
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|instance| Self::bounding_box(&instance.bounding_box))
+                        .collect(),
+                });
+            }
+
+            next_token = response.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+
+        println!("labels: {}", labels.len());
+        Ok((labels, metadata))
+    }
+
+
+    async fn start_face_detection(&self, s3_bucket_name: &str, s3_key_name: &str, role_arn: &str, topic_arn: &str, job_tag: Option<&str>) -> Result<String> {
+        // all attributes: age range, gender and emotions
+        let response = self.client.clone()
+            .start_face_detection()
+            .video(Self::video(s3_bucket_name, s3_key_name))
+            .notification_channel(Self::notification_channel(role_arn, topic_arn)?)
+            .set_job_tag(job_tag.map(str::to_owned))
+            .face_attributes(FaceAttributes::All)
+            .send()
+            .await?;
+
+        let job_id = response.job_id.context("Job Id not available")?;
+        println!("Start face detection job id: {}", job_id);
+        Ok(job_id)
+    }
+
+    async fn get_face_detection_results(&self, job_id: &str) -> Result<(Vec<FaceDetection>, Option<VideoMetadata>)> {
+        let builder = self.client.clone()
+            .get_face_detection()
+            .job_id(job_id);
+
+        let mut faces: Vec<FaceDetection> = vec![];
+        let mut metadata: Option<VideoMetadata> = None;
+        let mut next_token: Option<String> = None;
+        loop {
+            let response = builder.clone().set_next_token(next_token).send().await?;
+            if metadata.is_none() {
+                metadata = response.video_metadata.map(VideoMetadata::new);
+            }
+
+            for detection in response.faces.unwrap_or_default() {
+                let Some(face) = detection.face else {
+                    continue;
+                };
+                let emotion = face
+                    .emotions
+                    .unwrap_or_default()
+                    .into_iter()
+                    .max_by(|a, b| a.confidence.unwrap_or(0.0).total_cmp(&b.confidence.unwrap_or(0.0)))
+                    .and_then(|emotion| emotion.r#type)
+                    .map(|emotion| emotion.as_str().to_owned());
+                faces.push(FaceDetection {
+                    timestamp: detection.timestamp,
+                    bounding_box: Self::bounding_box(&face.bounding_box),
+                    age_low: face.age_range.as_ref().and_then(|age_range| age_range.low),
+                    age_high: face.age_range.as_ref().and_then(|age_range| age_range.high),
+                    gender: face.gender.and_then(|gender| gender.value).map(|gender| gender.as_str().to_owned()),
+                    emotion,
+                    confidence: face.confidence.unwrap_or(0.0),
+                });
+            }
+
+            next_token = response.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+
+        println!("faces: {}", faces.len());
+        Ok((faces, metadata))
+    }
+
+
+    async fn start_segment_detection(&self, s3_bucket_name: &str, s3_key_name: &str, role_arn: &str, topic_arn: &str, job_tag: Option<&str>) -> Result<String> {
+        let response = self.client.clone()
+            .start_segment_detection()
+            .video(Self::video(s3_bucket_name, s3_key_name))
+            .notification_channel(Self::notification_channel(role_arn, topic_arn)?)
+            .set_job_tag(job_tag.map(str::to_owned))
+            .segment_types(SegmentType::Shot)
+            .segment_types(SegmentType::TechnicalCue)
+            .send()
+            .await?;
+
+        let job_id = response.job_id.context("Job Id not available")?;
+        println!("Start segment detection job id: {}", job_id);
+        Ok(job_id)
+    }
+
+    async fn get_segment_detection_results(&self, job_id: &str) -> Result<(Vec<Segment>, Option<VideoMetadata>)> {
+        let builder = self.client.clone()
+            .get_segment_detection()
+            .job_id(job_id);
+
+        let mut segments: Vec<Segment> = vec![];
+        let mut metadata: Option<VideoMetadata> = None;
+        let mut next_token: Option<String> = None;
+        loop {
+            let response = builder.clone().set_next_token(next_token).send().await?;
+            // one per video stream
+            if metadata.is_none() {
+                metadata = response.video_metadata.unwrap_or_default().into_iter().next().map(VideoMetadata::new);
+            }
+
+            for segment in response.segments.unwrap_or_default() {
+                let (kind, confidence) = match (&segment.shot_segment, &segment.technical_cue_segment) {
+                    (Some(shot), _) => (SegmentKind::Shot, shot.confidence),
+                    (None, Some(technical_cue)) => (SegmentKind::TechnicalCue, technical_cue.confidence),
+                    (None, None) => continue,
+                };
+                segments.push(Segment {
+                    kind,
+                    start_ms: segment.start_timestamp_millis,
+                    end_ms: segment.end_timestamp_millis,
+                    shot_index: segment.shot_segment.as_ref().and_then(|shot| shot.index),
+                    technical_cue: segment
+                        .technical_cue_segment
+                        .as_ref()
+                        .and_then(|technical_cue| technical_cue.r#type.as_ref())
+                        .map(|technical_cue| technical_cue.as_str().to_owned()),
+                    confidence: confidence.unwrap_or(0.0),
+                });
+            }
+
+            next_token = response.next_token;
+            if next_token.is_none() {
+                break;
+            }
+        }
+
+        println!("segments: {}", segments.len());
+        Ok((segments, metadata))
+    }
+
+    // a single result is enough for the status
+    async fn get_job_status(&self, job_id: &str, analysis_type: AnalysisType) -> Result<JobStatus> {
+        let status = match analysis_type {
+            AnalysisType::PersonTracking => self.client.clone().get_person_tracking().job_id(job_id).max_results(1).send().await?.job_status,
+            AnalysisType::LabelDetection => self.client.clone().get_label_detection().job_id(job_id).max_results(1).send().await?.job_status,
+            AnalysisType::FaceDetection => self.client.clone().get_face_detection().job_id(job_id).max_results(1).send().await?.job_status,
+            AnalysisType::SegmentDetection => self.client.clone().get_segment_detection().job_id(job_id).max_results(1).send().await?.job_status,
+        };
+        Ok(Self::job_status(status))
+    }
+}
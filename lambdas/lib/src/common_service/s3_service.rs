@@ -7,6 +7,7 @@ use aws_sdk_s3::{presigning::PresigningConfig, primitives::ByteStream};
 use axum::body::Bytes;
 
 use crate::constants::{PRESIGNED_VALID_DURATION_UPLOAD, PRESIGNED_VALID_DURATION_VIEW};
+use super::traits::ObjectStore;
 
 
 #[derive(Debug, Clone)]
@@ -20,8 +21,10 @@ impl S3Service {
             client: client.to_owned()
         }
     }
+}
 
-    pub async fn put_object(
+impl ObjectStore for S3Service {
+    async fn put_object(
         &self,
         bucket_name: &str,
         key: &str,
@@ -43,7 +46,7 @@ impl S3Service {
         Ok(())
     }
 
-    pub async fn delete_object(
+    async fn delete_object(
         &self,
         bucket_name: &str,
         key: &str,
@@ -59,29 +62,29 @@ impl S3Service {
         Ok(())
     }
 
-    // pub async fn get_object(
-    //     &self,
-    //     bucket_name: &str,
-    //     key: &str
-    // ) -> Result<(Vec<u8>, String)> {
+    async fn get_object(
+        &self,
+        bucket_name: &str,
+        key: &str
+    ) -> Result<(Vec<u8>, String)> {
 
-    //     let response = self.client.clone()
-    //         .get_object()
-    //         .bucket(bucket_name)
-    //         .key(key)
-    //         .send()
-    //         .await?;
+        let response = self.client.clone()
+            .get_object()
+            .bucket(bucket_name)
+            .key(key)
+            .send()
+            .await?;
 
-    //     let content_type = &response.content_type.context("content type not available")?;
-    //     println!("{}", content_type);
+        // objects stored without a content type
+        let content_type = response.content_type.unwrap_or("application/octet-stream".to_owned());
 
-    //     let byte_stream = response.body;
-    //     let bytes = byte_stream.collect().await?.to_vec();
+        let byte_stream = response.body;
+        let bytes = byte_stream.collect().await?.to_vec();
 
-    //     Ok((bytes, content_type.to_owned()))
-    // }
+        Ok((bytes, content_type))
+    }
 
-    pub async fn put_object_presigned(
+    async fn put_object_presigned(
         &self,
         bucket_name: &str,
         key: &str,
@@ -101,7 +104,7 @@ impl S3Service {
         Ok(presigned_request.uri().to_owned())
     }
 
-    pub async fn get_object_presigned(
+    async fn get_object_presigned(
         &self,
         bucket_name: &str,
         key: &str
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+
+use anyhow::Result;
+use aws_sdk_rekognition::types::PersonDetection;
+use axum::body::Bytes;
+
+use crate::analytics::faces::FaceDetection;
+use crate::analytics::heatmap::HeatmapConfig;
+use crate::analytics::labels::LabelDetection;
+use crate::analytics::segments::Segment;
+use crate::analytics::tripwires::{Tripwire, TripwireSummary};
+use crate::analytics::zones::{Zone, ZoneSummary};
+use crate::exports::columnar::ColumnarFormat;
+use crate::common_structs::{AnalysisSummary, AnalysisType, ChildJob, JobAttempt, JobDetails, JobQuery, JobStatus, LastEvaluatedKey, RekognitionJobTableEntry, TrackingPostProcessing, TrackingSummary, VideoMetadata};
+
+
+// storage for videos and analysis results (S3)
+pub trait ObjectStore: Clone + Send + Sync + 'static {
+    fn put_object(&self, bucket_name: &str, key: &str, bytes: Bytes, content_type: &str) -> impl Future<Output = Result<()>> + Send;
+
+    // (bytes, content_type)
+    fn get_object(&self, bucket_name: &str, key: &str) -> impl Future<Output = Result<(Vec<u8>, String)>> + Send;
+
+    fn delete_object(&self, bucket_name: &str, key: &str) -> impl Future<Output = Result<()>> + Send;
+
+    fn put_object_presigned(&self, bucket_name: &str, key: &str, content_type: &str) -> impl Future<Output = Result<String>> + Send;
+
+    fn get_object_presigned(&self, bucket_name: &str, key: &str) -> impl Future<Output = Result<String>> + Send;
+}
+
+
+// table for analysis jobs (Dynamo)
+pub trait JobRepository: Clone + Send + Sync + 'static {
+    fn register_entry(&self, table_name: &str, user_id: &str, job_id: &str, s3_folder_name: &str, file_name: &str) -> impl Future<Output = Result<()>> + Send;
+
+    fn update_summary(&self, table_name: &str, job_id: &str, tracking_summary: &TrackingSummary) -> impl Future<Output = Result<()>> + Send;
+
+    fn update_metadata(&self, table_name: &str, job_id: &str, metadata: &VideoMetadata) -> impl Future<Output = Result<()>> + Send;
+
+    fn update_job_status(&self, table_name: &str, job_id: &str, status: JobStatus) -> impl Future<Output = Result<()>> + Send;
+
+    // only if the job is still in the `expected` status, returns false otherwise (updated by another writer in the meantime)
+    fn update_job_status_if(&self, table_name: &str, job_id: &str, status: JobStatus, expected: JobStatus) -> impl Future<Output = Result<bool>> + Send;
+
+    fn update_zones(&self, table_name: &str, job_id: &str, zones: &[Zone]) -> impl Future<Output = Result<()>> + Send;
+
+    fn update_zone_summaries(&self, table_name: &str, job_id: &str, zone_summaries: &[ZoneSummary]) -> impl Future<Output = Result<()>> + Send;
+
+    fn update_tripwires(&self, table_name: &str, job_id: &str, tripwires: &[Tripwire]) -> impl Future<Output = Result<()>> + Send;
+
+    fn update_tripwire_summaries(&self, table_name: &str, job_id: &str, tripwire_summaries: &[TripwireSummary]) -> impl Future<Output = Result<()>> + Send;
+
+    fn update_heatmap_config(&self, table_name: &str, job_id: &str, heatmap_config: &HeatmapConfig) -> impl Future<Output = Result<()>> + Send;
+
+    fn update_post_processing(&self, table_name: &str, job_id: &str, post_processing: &TrackingPostProcessing) -> impl Future<Output = Result<()>> + Send;
+
+    fn update_columnar_formats(&self, table_name: &str, job_id: &str, columnar_formats: &[ColumnarFormat]) -> impl Future<Output = Result<()>> + Send;
+
+    fn update_analysis_type(&self, table_name: &str, job_id: &str, analysis_type: AnalysisType) -> impl Future<Output = Result<()>> + Send;
+
+    fn update_analysis_summary(&self, table_name: &str, job_id: &str, analysis_summary: &AnalysisSummary) -> impl Future<Output = Result<()>> + Send;
+
+    fn update_children(&self, table_name: &str, job_id: &str, children: &BTreeMap<String, ChildJob>) -> impl Future<Output = Result<()>> + Send;
+
+    // set a single child of a multi-analysis job and increment `children_version`, returns the updated parent entry
+    fn update_child_job(&self, table_name: &str, job_id: &str, child_job_id: &str, child: &ChildJob) -> impl Future<Output = Result<RekognitionJobTableEntry>> + Send;
+
+    // status of a multi-analysis job from its children, only if no other child was updated since `children_version`
+    // returns false otherwise: the later child update writes the status instead
+    fn update_parent_job_status(&self, table_name: &str, job_id: &str, status: JobStatus, children_version: u64) -> impl Future<Output = Result<bool>> + Send;
+
+    fn update_parent_job_id(&self, table_name: &str, job_id: &str, parent_job_id: &str) -> impl Future<Output = Result<()>> + Send;
+
+    fn update_timeout_reason(&self, table_name: &str, job_id: &str, timeout_reason: &str) -> impl Future<Output = Result<()>> + Send;
+
+    // owner of the job, to move the jobs of a previous user id
+    fn update_user_id(&self, table_name: &str, job_id: &str, user_id: &str) -> impl Future<Output = Result<()>> + Send;
+
+    // a retried job is in progress again before its new Rekognition job is started
+    // only if the job is still at the previous attempt (same Rekognition job and status), returns false otherwise (retried concurrently)
+    fn claim_retry(&self, table_name: &str, job_id: &str, previous_attempt: &JobAttempt) -> impl Future<Output = Result<bool>> + Send;
+
+    // new Rekognition job of a claimed retry: the previous one goes to `attempts`
+    // only if the job is still in progress at the previous Rekognition job, returns false otherwise (cancelled in the meantime)
+    fn restart_job(&self, table_name: &str, job_id: &str, rekognition_job_id: &str, previous_attempt: &JobAttempt) -> impl Future<Output = Result<bool>> + Send;
+
+    // only the set fields, fails if the job does not exist, returns the updated entry
+    fn update_job_details(&self, table_name: &str, job_id: &str, details: &JobDetails) -> impl Future<Output = Result<RekognitionJobTableEntry>> + Send;
+
+    fn get_entry_single(&self, table_name: &str, job_id: &str) -> impl Future<Output = Result<RekognitionJobTableEntry>> + Send;
+
+    // in progress jobs whose current attempt started before the timestamp (seconds), to check jobs with a lost completion message
+    fn scan_in_progress_entries(&self, table_name: &str, started_before: u64) -> impl Future<Output = Result<Vec<RekognitionJobTableEntry>>> + Send;
+
+    // a page of the jobs of a user, filters apply after the page is read (`query.limit` jobs)
+    fn query_entries(&self, table_name: &str, user_id: &str, query: &JobQuery, last_evaluated_key: Option<LastEvaluatedKey>) -> impl Future<Output = Result<(Vec<RekognitionJobTableEntry>, Option<LastEvaluatedKey>)>> + Send;
+
+    fn delete_entry(&self, table_name: &str, job_id: &str) -> impl Future<Output = Result<()>> + Send;
+}
+
+
+// analysis of a stored video (Rekognition): person tracking, label, face and segment detection
+// start_* return the job id, the completion (with the job tag, if any) is notified to the SNS topic
+pub trait VideoAnalyzer: Clone + Send + Sync + 'static {
+    fn start_tracking(&self, s3_bucket_name: &str, s3_key_name: &str, role_arn: &str, topic_arn: &str, job_tag: Option<&str>) -> impl Future<Output = Result<String>> + Send;
+
+    fn get_persons_detection_results(&self, job_id: &str) -> impl Future<Output = Result<(Vec<PersonDetection>, Option<VideoMetadata>)>> + Send;
+
+    fn start_label_detection(&self, s3_bucket_name: &str, s3_key_name: &str, role_arn: &str, topic_arn: &str, job_tag: Option<&str>) -> impl Future<Output = Result<String>> + Send;
+
+    fn get_label_detection_results(&self, job_id: &str) -> impl Future<Output = Result<(Vec<LabelDetection>, Option<VideoMetadata>)>> + Send;
+
+    fn start_face_detection(&self, s3_bucket_name: &str, s3_key_name: &str, role_arn: &str, topic_arn: &str, job_tag: Option<&str>) -> impl Future<Output = Result<String>> + Send;
+
+    fn get_face_detection_results(&self, job_id: &str) -> impl Future<Output = Result<(Vec<FaceDetection>, Option<VideoMetadata>)>> + Send;
+
+    fn start_segment_detection(&self, s3_bucket_name: &str, s3_key_name: &str, role_arn: &str, topic_arn: &str, job_tag: Option<&str>) -> impl Future<Output = Result<String>> + Send;
+
+    fn get_segment_detection_results(&self, job_id: &str) -> impl Future<Output = Result<(Vec<Segment>, Option<VideoMetadata>)>> + Send;
+
+    // current status of a job, without the completion message
+    fn get_job_status(&self, job_id: &str, analysis_type: AnalysisType) -> impl Future<Output = Result<JobStatus>> + Send;
+}
@@ -1,8 +1,19 @@
-use std::{collections::HashMap, time::{SystemTime, UNIX_EPOCH}};
+use std::{collections::{BTreeMap, HashMap}, time::{SystemTime, UNIX_EPOCH}};
 
+use anyhow::{bail, Result};
 use aws_sdk_rekognition::types::PersonDetection;
 use serde::{Deserialize, Serialize};
 
+use crate::analytics::PersonSample;
+use crate::analytics::faces::FaceSummary;
+use crate::analytics::heatmap::HeatmapConfig;
+use crate::analytics::labels::LabelSummary;
+use crate::analytics::segments::SegmentSummary;
+use crate::analytics::tripwires::{Tripwire, TripwireSummary};
+use crate::analytics::zones::{Zone, ZoneSummary};
+use crate::constants::{FACES_JSON_KEY, LABELS_JSON_KEY, RESULTS_JSON_KEY, SEGMENTS_JSON_KEY};
+use crate::exports::columnar::ColumnarFormat;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all(deserialize = "PascalCase"))]
 pub struct RekognitionSNSMessage {
@@ -10,7 +21,17 @@ pub struct RekognitionSNSMessage {
     pub status: JobStatus,
     #[serde(rename(deserialize = "API"))]
     pub api: String,
-    pub video: VideoObject
+    pub video: VideoObject,
+    // set on retried jobs: the job id of the entry
+    #[serde(default)]
+    pub job_tag: Option<String>
+}
+
+impl RekognitionSNSMessage {
+    // job entry of the message, the Rekognition job id differs from it after a retry
+    pub fn entry_job_id(&self) -> &str {
+        self.job_tag.as_deref().unwrap_or(&self.job_id)
+    }
 }
 
 
@@ -27,10 +48,154 @@ pub enum JobStatus {
     Failed,
     InProgress,
     Succeeded,
+    // multi-analysis jobs: some of the children failed
+    PartiallySucceeded,
+    // no completion before the job timeout
+    TimedOut,
+    // cancelled by the user, the completion is ignored
+    Cancelled,
     #[serde(untagged)]
     Unknown(String),
 }
 
+impl JobStatus {
+    // status of a multi-analysis job: in progress until every child has finished (or timed out, or was cancelled)
+    pub fn from_children<'a>(statuses: impl IntoIterator<Item = &'a JobStatus>) -> Self {
+        let statuses: Vec<&JobStatus> = statuses.into_iter().collect();
+        if statuses.iter().any(|status| !matches!(status, Self::Succeeded | Self::Failed | Self::TimedOut | Self::Cancelled)) {
+            Self::InProgress
+        } else if let Some(status) = statuses.first().filter(|first| statuses.iter().all(|status| status == *first)) {
+            (*status).clone()
+        } else if statuses.iter().all(|status| **status != Self::Succeeded) {
+            Self::Failed
+        } else {
+            Self::PartiallySucceeded
+        }
+    }
+
+    // finished without results, can be started again
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Failed | Self::TimedOut | Self::Cancelled)
+    }
+}
+
+// previous Rekognition job of a retried job
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct JobAttempt {
+    pub rekognition_job_id: String,
+    pub job_status: JobStatus,
+    // timestamp in seconds
+    pub request_timestamp: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_reason: Option<String>,
+}
+
+impl JobAttempt {
+    // the current attempt of the entry
+    pub fn new(entry: &RekognitionJobTableEntry) -> Self {
+        Self {
+            rekognition_job_id: entry.rekognition_job_id().to_owned(),
+            job_status: entry.job_status.clone(),
+            request_timestamp: entry.attempt_started_at(),
+            timeout_reason: entry.timeout_reason.clone(),
+        }
+    }
+}
+
+// child of a multi-analysis job, keyed by its Rekognition job id in the parent entry
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct ChildJob {
+    pub analysis_type: AnalysisType,
+    pub job_status: JobStatus,
+}
+
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisType {
+    #[default]
+    PersonTracking,
+    LabelDetection,
+    FaceDetection,
+    SegmentDetection,
+}
+
+impl AnalysisType {
+    // `API` of the Rekognition completion message
+    pub fn api(&self) -> &'static str {
+        match self {
+            Self::PersonTracking => "StartPersonTracking",
+            Self::LabelDetection => "StartLabelDetection",
+            Self::FaceDetection => "StartFaceDetection",
+            Self::SegmentDetection => "StartSegmentDetection",
+        }
+    }
+
+    pub fn from_api(api: &str) -> Option<Self> {
+        [Self::PersonTracking, Self::LabelDetection, Self::FaceDetection, Self::SegmentDetection]
+            .into_iter()
+            .find(|analysis_type| analysis_type.api() == api)
+    }
+
+    // results file within the job folder
+    pub fn results_key(&self) -> &'static str {
+        match self {
+            Self::PersonTracking => RESULTS_JSON_KEY,
+            Self::LabelDetection => LABELS_JSON_KEY,
+            Self::FaceDetection => FACES_JSON_KEY,
+            Self::SegmentDetection => SEGMENTS_JSON_KEY,
+        }
+    }
+}
+
+// summary of label, face and segment detection jobs (person tracking jobs have a `TrackingSummary`)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisSummary {
+    Labels(Vec<LabelSummary>),
+    Faces(FaceSummary),
+    Segments(SegmentSummary),
+}
+
+// results of the finished children of a multi-analysis job, merged per analysis type
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct CombinedSummary {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracking_summary: Option<TrackingSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<LabelSummary>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub faces: Option<FaceSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segments: Option<SegmentSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub video_metadata: Option<VideoMetadata>,
+}
+
+impl CombinedSummary {
+    pub fn new(children: &[RekognitionJobTableEntry]) -> Self {
+        let mut summary = Self::default();
+        for child in children.iter().filter(|child| child.job_status == JobStatus::Succeeded) {
+            if child.tracking_summary.is_some() {
+                summary.tracking_summary = child.tracking_summary.clone();
+            }
+            match child.analysis_summary.clone() {
+                Some(AnalysisSummary::Labels(labels)) => summary.labels = Some(labels),
+                Some(AnalysisSummary::Faces(faces)) => summary.faces = Some(faces),
+                Some(AnalysisSummary::Segments(segments)) => summary.segments = Some(segments),
+                None => {},
+            }
+            if summary.video_metadata.is_none() {
+                summary.video_metadata = child.video_metadata.clone();
+            }
+        }
+        summary
+    }
+}
+
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
@@ -66,7 +231,14 @@ pub struct TrackingSummary {
 }
 
 impl  TrackingSummary {
-    pub fn new(mut person_detections: Vec<PersonDetection>) -> Self {
+    pub fn new(person_detections: Vec<PersonDetection>) -> Self {
+        Self::new_in_range(person_detections, i64::MIN, i64::MAX)
+    }
+
+    // Summary of the persons tracked within [start_ms, end_ms].
+    // A person counts if their track (first to last detection) overlaps the range,
+    // and the tracking time is clipped to the range.
+    pub fn new_in_range(mut person_detections: Vec<PersonDetection>, start_ms: i64, end_ms: i64) -> Self {
          // index: (first_detect_timestamp, last_detect_timestamp)
         let mut results_map: HashMap<i64, (i64, i64)> = HashMap::new();
         person_detections.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
@@ -86,8 +258,28 @@ impl  TrackingSummary {
             }
         }
 
-        let total_detection_count = results_map.len();
-        let tracking_time_vector: Vec<f64> = results_map.values().map(|(first, last)| ((last - first) as f64)/1000.0).collect();
+        Self::from_tracks(results_map.into_values().collect(), start_ms, end_ms)
+    }
+
+    // same as new_in_range, for results already saved as `persons.json`
+    pub fn from_samples_in_range(samples: &[PersonSample], start_ms: i64, end_ms: i64) -> Self {
+        let tracks: Vec<(i64, i64)> = PersonSample::group_by_person(samples)
+            .values()
+            .filter_map(|person_samples| Some((person_samples.first()?.timestamp, person_samples.last()?.timestamp)))
+            .collect();
+        Self::from_tracks(tracks, start_ms, end_ms)
+    }
+
+    // tracks: (first_detect_timestamp, last_detect_timestamp)
+    fn from_tracks(tracks: Vec<(i64, i64)>, start_ms: i64, end_ms: i64) -> Self {
+        let tracks_in_range: Vec<(i64, i64)> = tracks
+            .into_iter()
+            .filter(|(first, last)| *first <= end_ms && *last >= start_ms)
+            .map(|(first, last)| (first.max(start_ms), last.min(end_ms)))
+            .collect();
+
+        let total_detection_count = tracks_in_range.len();
+        let tracking_time_vector: Vec<f64> = tracks_in_range.iter().map(|(first, last)| ((last - first) as f64)/1000.0).collect();
         let sum: f64 = tracking_time_vector.iter().sum();
         let mut average_tracking_time: f64 = 0.0;
         if total_detection_count > 0 {
@@ -99,11 +291,65 @@ impl  TrackingSummary {
 }
 
 
+// version of the `persons.json` format
+// 1: array of TrackingResult without timestamp_ms
+// 2: TrackingResults
+pub const TRACKING_RESULTS_SCHEMA_VERSION: u32 = 2;
+
+// saved as `persons.json`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct TrackingResults {
+    pub schema_version: u32,
+    // sorted by frame
+    pub results: Vec<TrackingResult>
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedTrackingResults {
+    Versioned(TrackingResults),
+    Legacy(Vec<LegacyTrackingResult>),
+}
+
+#[derive(Deserialize)]
+struct LegacyTrackingResult {
+    frame: i64,
+    persons: Vec<PersonDetectionResult>
+}
+
+impl TrackingResults {
+    pub fn new(results: Vec<TrackingResult>) -> Self {
+        Self { schema_version: TRACKING_RESULTS_SCHEMA_VERSION, results }
+    }
+
+    // `persons.json` of any schema version, frame_rate is used for the timestamps of version 1
+    pub fn from_slice(bytes: &[u8], frame_rate: f32) -> Result<Self> {
+        let results = match serde_json::from_slice(bytes)? {
+            SavedTrackingResults::Versioned(results) => results.results,
+            SavedTrackingResults::Legacy(results) => results
+                .into_iter()
+                .map(|result| TrackingResult {
+                    frame: result.frame,
+                    timestamp_ms: TrackingResult::frame_to_timestamp(result.frame, frame_rate),
+                    persons: result.persons,
+                })
+                .collect(),
+        };
+        let mut results = Self::new(results);
+        results.results.sort_by_key(|result| result.frame);
+        Ok(results)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 pub struct TrackingResult {
     // frame count
     pub frame: i64,
+    // original Rekognition timestamp (earliest one if several land on the same frame),
+    // or the frame time for frames with interpolated boxes only
+    pub timestamp_ms: i64,
     pub persons: Vec<PersonDetectionResult>
 }
 
@@ -111,7 +357,10 @@ pub struct TrackingResult {
 #[serde(rename_all = "snake_case")]
 pub struct PersonDetectionResult {
     pub index: i64,
-    pub bounding_box: PersonBoundingBox
+    pub bounding_box: PersonBoundingBox,
+    // synthesized by post processing between two detections, omitted for detected boxes
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interpolated: bool
 }
 
 impl PersonDetectionResult {
@@ -128,7 +377,8 @@ impl PersonDetectionResult {
         return Some(
             Self  {
                 index: person.index,
-                bounding_box: PersonBoundingBox { width, height, left, top }
+                bounding_box: PersonBoundingBox { width, height, left, top },
+                interpolated: false
             }
         )
     }
@@ -144,43 +394,143 @@ pub struct PersonBoundingBox {
     pub top: f32,
 }
 
+impl PersonBoundingBox {
+    // t = 0: self, t = 1: other
+    pub fn lerp(&self, other: &PersonBoundingBox, t: f32) -> Self {
+        Self {
+            width: self.width + (other.width - self.width) * t,
+            height: self.height + (other.height - self.height) * t,
+            left: self.left + (other.left - self.left) * t,
+            top: self.top + (other.top - self.top) * t,
+        }
+    }
+}
+
 
 impl TrackingResult {
 
-    pub fn new_vec(person_detections: Vec<PersonDetection>, frame_rate: f32) -> Vec<Self> {
-        // frame duration in milliseconds
-        let frame_duration = 1000.0/frame_rate;
+    // Sorted by frame. Detections with timestamps landing on the same frame are merged,
+    // keeping the earliest detection if a person appears more than once.
+    pub fn new_vec(mut person_detections: Vec<PersonDetection>, frame_rate: f32) -> Vec<Self> {
+        person_detections.sort_by_key(|detection| detection.timestamp);
 
-        // timestamp: [PersonDetectionResult]
-        let mut results_map: HashMap<i64, Vec<PersonDetectionResult>> = HashMap::new();
+        // frame: TrackingResult
+        let mut results_map: BTreeMap<i64, TrackingResult> = BTreeMap::new();
         for detection in person_detections {
             let Some(person) = PersonDetectionResult::new(&detection) else {
                 continue;
             };
-            let timestamp = detection.timestamp;
-            if results_map.contains_key(&timestamp) {
-                results_map.entry(timestamp).and_modify(|detail_array| detail_array.push(person));
+            let frame = Self::timestamp_to_frame(detection.timestamp, frame_rate);
+            let result = results_map.entry(frame).or_insert_with(|| TrackingResult {
+                frame,
+                timestamp_ms: detection.timestamp,
+                persons: vec![]
+            });
+            if result.persons.iter().all(|existing| existing.index != person.index) {
+                result.persons.push(person);
+            }
+        }
 
-            } else {
-                results_map.insert(timestamp, vec![person]);
+        return results_map.into_values().collect();
+    }
+
+    // Rekognition frame rates are f32 (29.97 becomes 29.9699993...), rounded to 3 decimals before the conversion
+    fn exact_frame_rate(frame_rate: f32) -> f64 {
+        (frame_rate as f64 * 1000.0).round() / 1000.0
+    }
+
+    pub fn timestamp_to_frame(timestamp: i64, frame_rate: f32) -> i64 {
+        return (timestamp as f64 * Self::exact_frame_rate(frame_rate) / 1000.0).round() as i64
+    }
+
+    pub fn frame_to_timestamp(frame: i64, frame_rate: f32) -> i64 {
+        return (frame as f64 * 1000.0 / Self::exact_frame_rate(frame_rate)).round() as i64
+    }
+
+    // Optional stage after new_vec: smooth each person's boxes, then fill short gaps between detections.
+    // Returns results sorted by frame.
+    pub fn post_process(results: Vec<TrackingResult>, frame_rate: f32, options: &TrackingPostProcessing) -> Vec<Self> {
+        // frame: timestamp_ms of the detected frames
+        let mut timestamps: BTreeMap<i64, i64> = BTreeMap::new();
+        // index: [(frame, bounding_box)]
+        let mut tracks: BTreeMap<i64, Vec<(i64, PersonBoundingBox)>> = BTreeMap::new();
+        for result in results {
+            let timestamp = timestamps.entry(result.frame).or_insert(result.timestamp_ms);
+            *timestamp = (*timestamp).min(result.timestamp_ms);
+            for person in result.persons {
+                tracks.entry(person.index).or_default().push((result.frame, person.bounding_box));
+            }
+        }
+
+        let max_gap_frames = options.max_interpolation_gap_ms.map(|gap| (gap as f64 * Self::exact_frame_rate(frame_rate) / 1000.0).floor() as i64);
+        let mut frames: BTreeMap<i64, Vec<PersonDetectionResult>> = BTreeMap::new();
+        for (index, mut track) in tracks {
+            track.sort_by_key(|(frame, _)| *frame);
+            if let Some(factor) = options.smoothing_factor {
+                for i in 1..track.len() {
+                    let previous = track[i - 1].1.clone();
+                    track[i].1 = track[i].1.lerp(&previous, 1.0 - factor);
+                }
+            }
+
+            for (i, (frame, bounding_box)) in track.iter().enumerate() {
+                frames.entry(*frame).or_default().push(PersonDetectionResult { index, bounding_box: bounding_box.clone(), interpolated: false });
+
+                let (Some(max_gap_frames), Some((next_frame, next_box))) = (max_gap_frames, track.get(i + 1)) else {
+                    continue;
+                };
+                let gap = next_frame - frame;
+                if gap <= 1 || gap > max_gap_frames {
+                    continue;
+                }
+                for missing_frame in (frame + 1)..*next_frame {
+                    let t = (missing_frame - frame) as f32 / gap as f32;
+                    frames.entry(missing_frame).or_default().push(PersonDetectionResult { index, bounding_box: bounding_box.lerp(next_box, t), interpolated: true });
+                }
             }
         }
 
-        let results: Vec<TrackingResult> = results_map
+        frames
             .into_iter()
-            .map(|result| TrackingResult {
-                frame: Self::timestamp_to_frame(result.0, frame_duration),
-                persons: result.1
+            .map(|(frame, persons)| TrackingResult {
+                frame,
+                timestamp_ms: timestamps.get(&frame).copied().unwrap_or_else(|| Self::frame_to_timestamp(frame, frame_rate)),
+                persons
             })
-            .collect();
-
-        return results;
+            .collect()
     }
+}
+
+
+// Post processing of the tracking results, configured per job.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct TrackingPostProcessing {
+    // fill gaps up to this duration (millisecond) between two detections of a person with interpolated boxes
+    #[serde(default)]
+    pub max_interpolation_gap_ms: Option<i64>,
+    // exponential smoothing of the boxes, (0, 1]: weight of the new detection (1: no smoothing)
+    #[serde(default)]
+    pub smoothing_factor: Option<f32>,
+}
 
+impl TrackingPostProcessing {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(gap) = self.max_interpolation_gap_ms {
+            if !(0..=10000).contains(&gap) {
+                bail!("max_interpolation_gap_ms should be between 0 and 10000.")
+            }
+        }
+        if let Some(factor) = self.smoothing_factor {
+            if !(factor > 0.0 && factor <= 1.0) {
+                bail!("smoothing_factor should be greater than 0 and up to 1.")
+            }
+        }
+        Ok(())
+    }
 
-    fn timestamp_to_frame(timestamp: i64, frame_duration: f32) -> i64 {
-        let frame_float = timestamp as f32 / frame_duration;
-        return frame_float.round() as i64
+    pub fn is_enabled(&self) -> bool {
+        self.max_interpolation_gap_ms.is_some() || self.smoothing_factor.is_some()
     }
 }
 
@@ -200,14 +550,190 @@ pub struct RekognitionJobTableEntry {
     pub tracking_summary:Option<TrackingSummary>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub video_metadata:Option<VideoMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zones:Option<Vec<Zone>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zone_summaries:Option<Vec<ZoneSummary>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tripwires:Option<Vec<Tripwire>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tripwire_summaries:Option<Vec<TripwireSummary>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heatmap_config:Option<HeatmapConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_processing:Option<TrackingPostProcessing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub columnar_formats:Option<Vec<ColumnarFormat>>,
+    // not set: person tracking
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analysis_type:Option<AnalysisType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub analysis_summary:Option<AnalysisSummary>,
+    // multi-analysis job: child job id: child
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children:Option<BTreeMap<String, ChildJob>>,
+    // multi-analysis job: incremented with each child update, the status is only written for the latest one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children_version:Option<u64>,
+    // child of a multi-analysis job
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_job_id:Option<String>,
+    // why the job was marked `TimedOut`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_reason:Option<String>,
+    // Rekognition job of the current attempt of a retried job (the job id otherwise)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rekognition_job_id:Option<String>,
+    // previous attempts of a retried job, oldest first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attempts:Option<Vec<JobAttempt>>,
+    // timestamp in seconds of the current attempt of a retried job (`request_timestamp` otherwise, which stays the same)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attempt_started_at:Option<u64>,
+    // user editable details, see `JobDetails`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title:Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description:Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags:Option<Vec<String>>,
+    // location or camera name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location:Option<String>,
+}
+
+pub const MAX_TITLE_LENGTH: usize = 200;
+pub const MAX_LOCATION_LENGTH: usize = 200;
+pub const MAX_DESCRIPTION_LENGTH: usize = 2000;
+pub const MAX_TAG_COUNT: usize = 20;
+pub const MAX_TAG_LENGTH: usize = 50;
+
+// user editable details of a job, fields not set are left unchanged
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct JobDetails {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+impl JobDetails {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.description.is_none() && self.tags.is_none() && self.location.is_none()
+    }
+
+    // trimmed text, tags without duplicates
+    pub fn normalized(&self) -> Self {
+        let mut tags: Option<Vec<String>> = None;
+        if let Some(input_tags) = &self.tags {
+            let mut normalized_tags: Vec<String> = vec![];
+            for tag in input_tags.iter().map(|tag| tag.trim().to_owned()) {
+                if !normalized_tags.contains(&tag) {
+                    normalized_tags.push(tag);
+                }
+            }
+            tags = Some(normalized_tags);
+        }
+        Self {
+            title: self.title.as_ref().map(|title| title.trim().to_owned()),
+            description: self.description.as_ref().map(|description| description.trim().to_owned()),
+            tags,
+            location: self.location.as_ref().map(|location| location.trim().to_owned()),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.is_empty() {
+            bail!("Set at least one of title, description, tags and location")
+        }
+        // an empty title would be shown instead of the file name
+        if self.title.as_ref().is_some_and(|title| title.is_empty() || title.chars().count() > MAX_TITLE_LENGTH) {
+            bail!("Title should be between 1 and {} characters", MAX_TITLE_LENGTH)
+        }
+        if self.description.as_ref().is_some_and(|description| description.chars().count() > MAX_DESCRIPTION_LENGTH) {
+            bail!("Description should be at most {} characters", MAX_DESCRIPTION_LENGTH)
+        }
+        if self.location.as_ref().is_some_and(|location| location.is_empty() || location.chars().count() > MAX_LOCATION_LENGTH) {
+            bail!("Location should be between 1 and {} characters", MAX_LOCATION_LENGTH)
+        }
+        if let Some(tags) = &self.tags {
+            if tags.len() > MAX_TAG_COUNT {
+                bail!("At most {} tags are allowed", MAX_TAG_COUNT)
+            }
+            if tags.iter().any(|tag| tag.is_empty() || tag.chars().count() > MAX_TAG_LENGTH) {
+                bail!("Tags should be between 1 and {} characters", MAX_TAG_LENGTH)
+            }
+        }
+        Ok(())
+    }
+}
+
+// filters of the job list (`query_entries`)
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct JobFilter {
+    // jobs with this tag
+    pub tag: Option<String>,
+    pub job_status: Option<JobStatus>,
+    // request timestamp range (seconds), both inclusive
+    pub requested_from: Option<u64>,
+    pub requested_to: Option<u64>,
+    // person tracking jobs with at least this many detections
+    pub min_detections: Option<usize>,
+}
+
+impl JobFilter {
+    pub fn matches(&self, entry: &RekognitionJobTableEntry) -> bool {
+        let tag_matches = match &self.tag {
+            Some(tag) => entry.tags.as_ref().is_some_and(|tags| tags.contains(tag)),
+            None => true,
+        };
+        let status_matches = self.job_status.as_ref().is_none_or(|job_status| entry.job_status == *job_status);
+        let requested_matches = self.requested_from.is_none_or(|from| entry.request_timestamp >= from) && self.requested_to.is_none_or(|to| entry.request_timestamp <= to);
+        let detections_match = self.min_detections.is_none_or(|min_detections| {
+            entry.tracking_summary.as_ref().is_some_and(|summary| summary.total_detection_count >= min_detections)
+        });
+        // children are listed with their multi-analysis job
+        entry.parent_job_id.is_none() && tag_matches && status_matches && requested_matches && detections_match
+    }
+}
+
+// by request timestamp
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    // newest first
+    #[default]
+    Desc,
+}
+
+// a page of the job list
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct JobQuery {
+    pub filter: JobFilter,
+    // jobs read for the page, before the filters apply (default: all, up to 1MB)
+    pub limit: Option<i32>,
+    pub sort_order: SortOrder,
+}
+
+// current timestamp in seconds
+pub fn current_timestamp() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(timestamp) => timestamp.as_secs(),
+        Err(_) => 0,
+    }
 }
 
 impl RekognitionJobTableEntry {
     pub fn new(job_id: &str, user_id: &str, s3_folder_name: &str, file_name: &str) -> Self{
-        let timestamp : u64 = match SystemTime::now().duration_since(UNIX_EPOCH) {
-            Ok(timestamp) => timestamp.as_secs(),
-            Err(_) => 0,
-        };
+        let timestamp : u64 = current_timestamp();
 
         Self {
             job_id: job_id.to_owned(),
@@ -217,9 +743,38 @@ impl RekognitionJobTableEntry {
             request_timestamp: timestamp,
             job_status: JobStatus::InProgress,
             tracking_summary: None,
-            video_metadata: None
+            video_metadata: None,
+            zones: None,
+            zone_summaries: None,
+            tripwires: None,
+            tripwire_summaries: None,
+            heatmap_config: None,
+            post_processing: None,
+            columnar_formats: None,
+            analysis_type: None,
+            analysis_summary: None,
+            children: None,
+            children_version: None,
+            parent_job_id: None,
+            timeout_reason: None,
+            rekognition_job_id: None,
+            attempts: None,
+            attempt_started_at: None,
+            title: None,
+            description: None,
+            tags: None,
+            location: None
         }
     }
+
+    pub fn rekognition_job_id(&self) -> &str {
+        self.rekognition_job_id.as_deref().unwrap_or(&self.job_id)
+    }
+
+    // timestamp in seconds of the current attempt
+    pub fn attempt_started_at(&self) -> u64 {
+        self.attempt_started_at.unwrap_or(self.request_timestamp)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -238,4 +793,332 @@ impl LastEvaluatedKey {
             request_timestamp: request_timestamp.to_owned()
         }
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_rekognition::types::{BoundingBox, PersonDetail};
+
+    use super::*;
+
+    fn detection(timestamp: i64, index: i64) -> PersonDetection {
+        PersonDetection::builder()
+            .timestamp(timestamp)
+            .person(PersonDetail::builder().index(index).build())
+            .build()
+    }
+
+    fn detections() -> Vec<PersonDetection> {
+        vec![
+            // person 0: 0 to 4000
+            detection(0, 0),
+            detection(4000, 0),
+            // person 1: 3000 to 5000
+            detection(3000, 1),
+            detection(5000, 1),
+            // person 2: 8000 to 9000
+            detection(8000, 2),
+            detection(9000, 2),
+        ]
+    }
+
+    #[test]
+    fn summary_for_whole_video() {
+        let summary = TrackingSummary::new(detections());
+        assert_eq!(summary.total_detection_count, 3);
+        // (4 + 2 + 1) / 3
+        assert_eq!(summary.average_tracking_time, 7.0 / 3.0);
+    }
+
+    #[test]
+    fn summary_clips_tracks_to_range() {
+        let summary = TrackingSummary::new_in_range(detections(), 2000, 4500);
+        // person 0: 2000 to 4000, person 1: 3000 to 4500, person 2 not in range
+        assert_eq!(summary.total_detection_count, 2);
+        assert_eq!(summary.average_tracking_time, 1.75);
+
+        // tracks touching the range boundary count with no tracking time
+        let summary = TrackingSummary::new_in_range(detections(), 5000, 8000);
+        assert_eq!(summary.total_detection_count, 2);
+        assert_eq!(summary.average_tracking_time, 0.0);
+
+        let summary = TrackingSummary::new_in_range(detections(), 6000, 7000);
+        assert_eq!(summary.total_detection_count, 0);
+        assert_eq!(summary.average_tracking_time, 0.0);
+    }
+
+    fn detection_with_box(timestamp: i64, index: i64) -> PersonDetection {
+        let bounding_box = BoundingBox::builder().width(0.1).height(0.2).left(0.3).top(0.4).build();
+        PersonDetection::builder()
+            .timestamp(timestamp)
+            .person(PersonDetail::builder().index(index).bounding_box(bounding_box).build())
+            .build()
+    }
+
+    // (frame, timestamp_ms, [index])
+    fn golden(results: &[TrackingResult]) -> Vec<(i64, i64, Vec<i64>)> {
+        results
+            .iter()
+            .map(|result| (result.frame, result.timestamp_ms, result.persons.iter().map(|person| person.index).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn new_vec_at_29_97_fps() {
+        let detections = vec![
+            detection_with_box(200, 0),
+            detection_with_box(0, 0),
+            detection_with_box(33, 0),
+            detection_with_box(67, 1),
+            detection_with_box(100, 0),
+            detection_with_box(133, 1),
+            detection_with_box(167, 0),
+        ];
+
+        let results = TrackingResult::new_vec(detections, 29.97);
+        assert_eq!(golden(&results), vec![
+            (0, 0, vec![0]),
+            (1, 33, vec![0]),
+            (2, 67, vec![1]),
+            (3, 100, vec![0]),
+            (4, 133, vec![1]),
+            (5, 167, vec![0]),
+            (6, 200, vec![0]),
+        ]);
+    }
+
+    #[test]
+    fn new_vec_for_long_videos() {
+        // 2 to 10 hours at 29.97 fps, where f32 conversions are off by one frame
+        let detections = vec![
+            detection_with_box(36_000_017, 0),
+            detection_with_box(7_200_050, 0),
+            detection_with_box(14_400_017, 1),
+        ];
+
+        let results = TrackingResult::new_vec(detections, 29.97);
+        assert_eq!(golden(&results), vec![
+            (215785, 7_200_050, vec![0]),
+            (431569, 14_400_017, vec![1]),
+            (1078921, 36_000_017, vec![0]),
+        ]);
+        assert_eq!(TrackingResult::frame_to_timestamp(1078921, 29.97), 36_000_033);
+    }
+
+    #[test]
+    fn new_vec_merges_frame_collisions() {
+        // at 10 fps, 1000 and 1040 are both frame 10
+        let detections = vec![
+            detection_with_box(1040, 0),
+            detection_with_box(1040, 1),
+            detection_with_box(1000, 0),
+            detection_with_box(1000, 2),
+        ];
+
+        let results = TrackingResult::new_vec(detections, 10.0);
+        assert_eq!(golden(&results), vec![(10, 1000, vec![0, 2, 1])]);
+    }
+
+    #[test]
+    fn reads_all_schema_versions() {
+        let legacy = r#"[
+            {"frame": 30, "persons": [{"index": 0, "bounding_box": {"width": 0.1, "height": 0.2, "left": 0.3, "top": 0.4}}]},
+            {"frame": 0, "persons": []}
+        ]"#;
+        let results = TrackingResults::from_slice(legacy.as_bytes(), 29.97).unwrap();
+        assert_eq!(results.schema_version, TRACKING_RESULTS_SCHEMA_VERSION);
+        assert_eq!(golden(&results.results), vec![(0, 0, vec![]), (30, 1001, vec![0])]);
+
+        let current = serde_json::to_vec(&TrackingResults::new(vec![result(3, 0, 0.1)])).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&current).unwrap();
+        assert_eq!(json["schema_version"], 2);
+        let results = TrackingResults::from_slice(&current, 29.97).unwrap();
+        assert_eq!(golden(&results.results), vec![(3, 300, vec![0])]);
+    }
+
+    fn result(frame: i64, index: i64, left: f32) -> TrackingResult {
+        TrackingResult {
+            frame,
+            timestamp_ms: frame * 100,
+            persons: vec![PersonDetectionResult {
+                index,
+                bounding_box: PersonBoundingBox { width: 0.1, height: 0.2, left, top: 0.5 },
+                interpolated: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn post_process_interpolates_short_gaps() {
+        // at 10 fps: person 0 missing for 3 frames (300 ms) then 10 frames (1 s)
+        let results = vec![result(14, 0, 0.4), result(0, 0, 0.0), result(4, 0, 0.4)];
+        let options = TrackingPostProcessing { max_interpolation_gap_ms: Some(500), smoothing_factor: None };
+        let processed = TrackingResult::post_process(results, 10.0, &options);
+
+        let frames: Vec<i64> = processed.iter().map(|result| result.frame).collect();
+        assert_eq!(frames, vec![0, 1, 2, 3, 4, 14]);
+        let interpolated: Vec<bool> = processed.iter().map(|result| result.persons[0].interpolated).collect();
+        assert_eq!(interpolated, vec![false, true, true, true, false, false]);
+        assert!((processed[2].persons[0].bounding_box.left - 0.2).abs() < 1e-6);
+        let timestamps: Vec<i64> = processed.iter().map(|result| result.timestamp_ms).collect();
+        assert_eq!(timestamps, vec![0, 100, 200, 300, 400, 1400]);
+    }
+
+    #[test]
+    fn post_process_gap_uses_exact_frame_rate() {
+        // at 29.97 fps, 100 s are exactly 2997 frames
+        let results = vec![result(0, 0, 0.0), result(2997, 0, 0.4)];
+        let options = TrackingPostProcessing { max_interpolation_gap_ms: Some(100_000), smoothing_factor: None };
+        let processed = TrackingResult::post_process(results, 29.97, &options);
+        assert_eq!(processed.len(), 2998);
+    }
+
+    #[test]
+    fn post_process_smooths_boxes() {
+        let results = vec![result(0, 0, 0.0), result(1, 0, 0.4), result(2, 0, 0.4)];
+        let options = TrackingPostProcessing { max_interpolation_gap_ms: None, smoothing_factor: Some(0.5) };
+        let processed = TrackingResult::post_process(results, 10.0, &options);
+
+        let lefts: Vec<f32> = processed.iter().map(|result| result.persons[0].bounding_box.left).collect();
+        assert_eq!(lefts, vec![0.0, 0.2, 0.3]);
+        assert!(processed.iter().all(|result| !result.persons[0].interpolated));
+    }
+
+    #[test]
+    fn interpolated_flag_is_omitted_for_detected_boxes() {
+        let json = serde_json::to_value(result(0, 0, 0.0)).unwrap();
+        assert!(json["persons"][0].get("interpolated").is_none());
+
+        let parsed: TrackingResult = serde_json::from_value(json).unwrap();
+        assert!(!parsed.persons[0].interpolated);
+    }
+
+    #[test]
+    fn summary_from_samples_matches_detections() {
+        let samples: Vec<PersonSample> = detections()
+            .iter()
+            .map(|detection| PersonSample {
+                timestamp: detection.timestamp,
+                index: detection.person.as_ref().unwrap().index,
+                bounding_box: PersonBoundingBox { width: 0.1, height: 0.1, left: 0.0, top: 0.0 },
+            })
+            .collect();
+
+        for (start_ms, end_ms) in [(i64::MIN, i64::MAX), (2000, 4500), (5000, 8000), (6000, 7000)] {
+            let from_detections = TrackingSummary::new_in_range(detections(), start_ms, end_ms);
+            let from_samples = TrackingSummary::from_samples_in_range(&samples, start_ms, end_ms);
+            assert_eq!(from_detections.total_detection_count, from_samples.total_detection_count);
+            assert_eq!(from_detections.average_tracking_time, from_samples.average_tracking_time);
+        }
+    }
+
+    #[test]
+    fn analysis_type_from_sns_api() {
+        assert_eq!(AnalysisType::from_api("StartPersonTracking"), Some(AnalysisType::PersonTracking));
+        assert_eq!(AnalysisType::from_api("StartSegmentDetection"), Some(AnalysisType::SegmentDetection));
+        assert_eq!(AnalysisType::from_api("StartCelebrityRecognition"), None);
+        assert_eq!(serde_json::to_value(AnalysisType::LabelDetection).unwrap(), "label_detection");
+    }
+
+    #[test]
+    fn multi_analysis_status_from_children() {
+        use JobStatus::*;
+        assert_eq!(JobStatus::from_children(&[Succeeded, InProgress]), InProgress);
+        assert_eq!(JobStatus::from_children(&[Failed, InProgress]), InProgress);
+        assert_eq!(JobStatus::from_children(&[Succeeded, Succeeded]), Succeeded);
+        assert_eq!(JobStatus::from_children(&[Failed, Failed]), Failed);
+        assert_eq!(JobStatus::from_children(&[Succeeded, Failed]), PartiallySucceeded);
+        assert_eq!(serde_json::to_value(PartiallySucceeded).unwrap(), "PARTIALLYSUCCEEDED");
+
+        assert_eq!(JobStatus::from_children(&[TimedOut, InProgress]), InProgress);
+        assert_eq!(JobStatus::from_children(&[TimedOut, TimedOut]), TimedOut);
+        assert_eq!(JobStatus::from_children(&[TimedOut, Failed]), Failed);
+        assert_eq!(JobStatus::from_children(&[TimedOut, Succeeded]), PartiallySucceeded);
+        assert_eq!(serde_json::to_value(TimedOut).unwrap(), "TIMEDOUT");
+        assert_eq!(serde_json::from_value::<JobStatus>("TIMEDOUT".into()).unwrap(), TimedOut);
+
+        assert_eq!(JobStatus::from_children(&[Cancelled, Cancelled]), Cancelled);
+        assert_eq!(JobStatus::from_children(&[Cancelled, TimedOut]), Failed);
+        assert_eq!(JobStatus::from_children(&[Cancelled, Succeeded]), PartiallySucceeded);
+        assert_eq!(serde_json::to_value(Cancelled).unwrap(), "CANCELLED");
+    }
+
+    #[test]
+    fn job_details_validation() {
+        let details = JobDetails { title: Some("  Entrance  ".to_owned()), tags: Some(vec!["lobby ".to_owned(), "lobby".to_owned(), "morning".to_owned()]), ..Default::default() }.normalized();
+        assert_eq!(details.title.as_deref(), Some("Entrance"));
+        assert_eq!(details.tags.as_ref().unwrap(), &vec!["lobby".to_owned(), "morning".to_owned()]);
+        assert!(details.validate().is_ok());
+
+        assert!(JobDetails::default().validate().is_err());
+        assert!(JobDetails { title: Some("a".repeat(MAX_TITLE_LENGTH + 1)), ..Default::default() }.validate().is_err());
+        assert!(JobDetails { location: Some("a".repeat(MAX_LOCATION_LENGTH + 1)), ..Default::default() }.validate().is_err());
+        assert!(JobDetails { title: Some("  ".to_owned()), ..Default::default() }.normalized().validate().is_err());
+        assert!(JobDetails { location: Some(" ".to_owned()), ..Default::default() }.normalized().validate().is_err());
+        assert!(JobDetails { tags: Some(vec![" ".to_owned()]), ..Default::default() }.normalized().validate().is_err());
+        assert!(JobDetails { tags: Some((0..=MAX_TAG_COUNT).map(|i| i.to_string()).collect()), ..Default::default() }.validate().is_err());
+
+        let mut entry = RekognitionJobTableEntry::new("job-1", "user", "folder", "video.mp4");
+        let filter = JobFilter { tag: Some("lobby".to_owned()), ..Default::default() };
+        assert!(!filter.matches(&entry));
+        entry.tags = details.tags;
+        assert!(filter.matches(&entry));
+        assert!(JobFilter::default().matches(&entry));
+    }
+
+    #[test]
+    fn job_filter_matches_entries() {
+        let mut entry = RekognitionJobTableEntry::new("job-1", "user", "folder", "video.mp4");
+        entry.request_timestamp = 1000;
+        entry.job_status = JobStatus::Failed;
+
+        assert!(JobFilter { job_status: Some(JobStatus::Failed), requested_from: Some(1000), requested_to: Some(2000), ..Default::default() }.matches(&entry));
+        assert!(!JobFilter { job_status: Some(JobStatus::Succeeded), ..Default::default() }.matches(&entry));
+        assert!(!JobFilter { requested_from: Some(1001), ..Default::default() }.matches(&entry));
+        assert!(!JobFilter { requested_to: Some(999), ..Default::default() }.matches(&entry));
+
+        let filter = JobFilter { min_detections: Some(100), ..Default::default() };
+        assert!(!filter.matches(&entry));
+        entry.tracking_summary = Some(TrackingSummary { total_detection_count: 100, average_tracking_time: 1.0 });
+        assert!(filter.matches(&entry));
+
+        // children of multi-analysis jobs are never listed
+        entry.parent_job_id = Some("parent-1".to_owned());
+        assert!(!JobFilter::default().matches(&entry));
+    }
+
+    #[test]
+    fn message_of_retried_job() {
+        let message: RekognitionSNSMessage = serde_json::from_str(r#"{
+            "JobId": "rekognition-2",
+            "Status": "SUCCEEDED",
+            "API": "StartPersonTracking",
+            "JobTag": "job-1",
+            "Video": {"S3ObjectName": "folder-1/video.mp4", "S3Bucket": "bucket-1"}
+        }"#).unwrap();
+        assert_eq!(message.entry_job_id(), "job-1");
+
+        let message = RekognitionSNSMessage { job_tag: None, ..message };
+        assert_eq!(message.entry_job_id(), "rekognition-2");
+    }
+
+    #[test]
+    fn combined_summary_of_finished_children() {
+        let mut tracking = RekognitionJobTableEntry::new("job-1", "user", "folder", "video.mp4");
+        tracking.job_status = JobStatus::Succeeded;
+        tracking.tracking_summary = Some(TrackingSummary::new(detections()));
+        let mut segments = RekognitionJobTableEntry::new("job-2", "user", "folder", "video.mp4");
+        segments.job_status = JobStatus::Succeeded;
+        segments.analysis_summary = Some(AnalysisSummary::Segments(SegmentSummary::new(&[])));
+        let mut labels = RekognitionJobTableEntry::new("job-3", "user", "folder", "video.mp4");
+        labels.job_status = JobStatus::Failed;
+        labels.analysis_summary = Some(AnalysisSummary::Labels(vec![]));
+
+        let summary = CombinedSummary::new(&[tracking, segments, labels]);
+        assert_eq!(summary.tracking_summary.unwrap().total_detection_count, 3);
+        assert_eq!(summary.segments.unwrap().shot_count, 0);
+        assert!(summary.labels.is_none());
+        assert!(summary.faces.is_none());
+    }
+}
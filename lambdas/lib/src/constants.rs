@@ -1,6 +1,30 @@
 
 pub static RESULTS_JSON_KEY: &str = "persons.json";
+pub static RESULTS_CSV_KEY: &str = "persons.csv";
+pub static RESULTS_PARQUET_KEY: &str = "persons.parquet";
+pub static RESULTS_ARROW_KEY: &str = "persons.arrow";
+pub static PERSON_SUMMARIES_CSV_KEY: &str = "person_summaries.csv";
+// MOTChallenge ground truth (tracks) and detections
+pub static MOT_GT_TXT_KEY: &str = "gt.txt";
+pub static MOT_DET_TXT_KEY: &str = "det.txt";
+pub static COCO_JSON_KEY: &str = "coco.json";
+pub static RESULTS_MANIFEST_JSON_KEY: &str = "results_manifest.json";
+// WebVTT tracks to play with the video
+pub static DETECTIONS_VTT_KEY: &str = "detections.vtt";
+pub static CAPTIONS_VTT_KEY: &str = "captions.vtt";
+// results of label, face and segment detection jobs
+pub static LABELS_JSON_KEY: &str = "labels.json";
+pub static FACES_JSON_KEY: &str = "faces.json";
+pub static SEGMENTS_JSON_KEY: &str = "segments.json";
+pub static ZONES_JSON_KEY: &str = "zones.json";
+pub static TRIPWIRES_JSON_KEY: &str = "tripwires.json";
+pub static OCCUPANCY_JSON_KEY: &str = "occupancy.json";
+pub static HEATMAP_JSON_KEY: &str = "heatmap.json";
+pub static HEATMAP_PNG_KEY: &str = "heatmap.png";
+pub static TRAJECTORIES_JSON_KEY: &str = "trajectories.json";
 // presigned URL for view: valid for 5 min
 pub static PRESIGNED_VALID_DURATION_UPLOAD: u64 = 300;
 // presigned URL for view: valid for 1 hour
 pub static PRESIGNED_VALID_DURATION_VIEW: u64 = 3600;
+// jobs read per page of the job list
+pub static MAX_JOBS_PAGE_SIZE: i32 = 100;
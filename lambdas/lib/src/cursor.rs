@@ -0,0 +1,97 @@
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::common_structs::LastEvaluatedKey;
+
+type HmacSha256 = Hmac<Sha256>;
+
+// Opaque pagination cursor of the job list: `{payload}.{signature}`, both base64 (URL safe, no padding).
+// The payload is the last evaluated key (JSON), signed with HMAC-SHA256 over the payload and the user id,
+// so a cursor can not be crafted, modified, or used to page through the jobs of another user.
+
+fn cursor_mac(payload: &str, user_id: &str, secret: &[u8]) -> Result<HmacSha256> {
+    let mut mac = HmacSha256::new_from_slice(secret).map_err(|err| anyhow!("Invalid cursor secret: {}", err))?;
+    mac.update(payload.as_bytes());
+    mac.update(b".");
+    mac.update(user_id.as_bytes());
+    Ok(mac)
+}
+
+pub fn encode_cursor(key: &LastEvaluatedKey, secret: &[u8]) -> Result<String> {
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(key)?);
+    let signature = cursor_mac(&payload, &key.user_id, secret)?.finalize().into_bytes();
+    Ok(format!("{}.{}", payload, URL_SAFE_NO_PAD.encode(signature)))
+}
+
+// the last evaluated key of a cursor issued for the user
+pub fn decode_cursor(cursor: &str, user_id: &str, secret: &[u8]) -> Result<LastEvaluatedKey> {
+    let Some((payload, signature)) = cursor.split_once('.') else {
+        bail!("Malformed cursor")
+    };
+    let signature = URL_SAFE_NO_PAD.decode(signature).context("Malformed cursor signature")?;
+    cursor_mac(payload, user_id, secret)?
+        .verify_slice(&signature)
+        .map_err(|_| anyhow!("Cursor signature does not match"))?;
+
+    let payload = URL_SAFE_NO_PAD.decode(payload).context("Malformed cursor payload")?;
+    let key: LastEvaluatedKey = serde_json::from_slice(&payload).context("Malformed cursor payload")?;
+    if key.user_id != user_id {
+        bail!("Cursor of another user")
+    }
+    Ok(key)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8] = b"test-secret";
+
+    fn key() -> LastEvaluatedKey {
+        LastEvaluatedKey::new("job-1", "user-1", &1000)
+    }
+
+    #[test]
+    fn cursor_round_trip() {
+        let cursor = encode_cursor(&key(), SECRET).unwrap();
+        assert!(!cursor.contains("job-1"));
+        let decoded = decode_cursor(&cursor, "user-1", SECRET).unwrap();
+        assert_eq!((decoded.job_id.as_str(), decoded.user_id.as_str(), decoded.request_timestamp), ("job-1", "user-1", 1000));
+    }
+
+    #[test]
+    fn cursor_rejects_tampering() {
+        let cursor = encode_cursor(&key(), SECRET).unwrap();
+        let (_, signature) = cursor.split_once('.').unwrap();
+
+        // another key with the original signature
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&LastEvaluatedKey::new("job-2", "user-1", &1000)).unwrap());
+        assert!(decode_cursor(&format!("{}.{}", payload, signature), "user-1", SECRET).is_err());
+
+        let mut tampered_signature = URL_SAFE_NO_PAD.decode(signature).unwrap();
+        tampered_signature[0] ^= 1;
+        let (payload, _) = cursor.split_once('.').unwrap();
+        assert!(decode_cursor(&format!("{}.{}", payload, URL_SAFE_NO_PAD.encode(tampered_signature)), "user-1", SECRET).is_err());
+
+        assert!(decode_cursor(&cursor, "user-1", b"another-secret").is_err());
+        for malformed in ["", "abc", "abc.def", "!!!.???"] {
+            assert!(decode_cursor(malformed, "user-1", SECRET).is_err());
+        }
+    }
+
+    #[test]
+    fn cursor_rejects_other_users() {
+        let cursor = encode_cursor(&key(), SECRET).unwrap();
+        assert!(decode_cursor(&cursor, "user-2", SECRET).is_err());
+
+        // signed for user-2, but holding a key of user-1
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&key()).unwrap());
+        let signature = cursor_mac(&payload, "user-2", SECRET).unwrap().finalize().into_bytes();
+        let cursor = format!("{}.{}", payload, URL_SAFE_NO_PAD.encode(signature));
+        assert!(decode_cursor(&cursor, "user-2", SECRET).is_err());
+    }
+}
@@ -2,4 +2,17 @@
 pub static S3_BUCKET_NAME_KEY: &str = "BUCKET_NAME";
 pub static TOPIC_ARN_KEY: &str = "TOPIC_ARN";
 pub static ROLE_ARN_KEY: &str = "ROLE_ARN";
-pub static TABLE_NAME_KEY: &str = "TABLE_NAME";
\ No newline at end of file
+pub static TABLE_NAME_KEY: &str = "TABLE_NAME";
+// reconcile lambda: minutes before an in progress job is checked against Rekognition
+pub static RECONCILE_AFTER_MINUTES_KEY: &str = "RECONCILE_AFTER_MINUTES";
+// reconcile lambda: minutes before an in progress job is marked as timed out
+pub static JOB_TIMEOUT_MINUTES_KEY: &str = "JOB_TIMEOUT_MINUTES";
+// api: key for signing the pagination cursors of the job list
+pub static CURSOR_SECRET_KEY: &str = "CURSOR_SECRET";
+// api lambda: Secrets Manager secret of the cursor key, read once at cold start (instead of setting CURSOR_SECRET)
+pub static CURSOR_SECRET_ARN_KEY: &str = "CURSOR_SECRET_ARN";
+// api: public keys (JWKS document) of the OIDC provider, for verifying tokens without fetching them
+pub static AUTH_JWKS_KEY: &str = "AUTH_JWKS";
+// api: expected `iss` and `aud` of the tokens
+pub static AUTH_ISSUER_KEY: &str = "AUTH_ISSUER";
+pub static AUTH_AUDIENCE_KEY: &str = "AUTH_AUDIENCE";
@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+
+use crate::common_structs::{TrackingResult, TrackingResults, TRACKING_RESULTS_SCHEMA_VERSION};
+
+
+// 1 minute of video per chunk
+pub const RESULTS_CHUNK_MILLIS: i64 = 60 * 1000;
+
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct ResultsChunkInfo {
+    pub index: usize,
+    // in millisecond, [start_ms, end_ms)
+    pub start_ms: i64,
+    pub end_ms: i64,
+    // file name within the job folder
+    pub key: String,
+    pub frame_count: usize,
+}
+
+// index of the chunk files, saved as `results_manifest.json`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct ResultsManifest {
+    // of the chunk files
+    pub schema_version: u32,
+    pub chunk_millis: i64,
+    // consecutive chunks covering the whole video, including the ones without any person
+    pub chunks: Vec<ResultsChunkInfo>,
+}
+
+impl ResultsManifest {
+    // manifest and tracking results (same schema as `persons.json`) of each chunk
+    // `duration`: video duration in millisecond
+    pub fn new(tracking_results: &[TrackingResult], duration: i64, chunk_millis: i64) -> (Self, Vec<TrackingResults>) {
+        let end = tracking_results.iter().map(|result| result.timestamp_ms + 1).max().unwrap_or(0).max(duration);
+        let chunk_count = (end + chunk_millis - 1) / chunk_millis;
+
+        let mut chunk_results: Vec<Vec<TrackingResult>> = vec![vec![]; chunk_count as usize];
+        for result in tracking_results {
+            let index = (result.timestamp_ms.max(0) / chunk_millis) as usize;
+            chunk_results[index].push(result.clone());
+        }
+
+        let chunks = chunk_results
+            .iter()
+            .enumerate()
+            .map(|(index, results)| ResultsChunkInfo {
+                index,
+                start_ms: index as i64 * chunk_millis,
+                end_ms: ((index as i64 + 1) * chunk_millis).min(end),
+                key: Self::chunk_key(index),
+                frame_count: results.len(),
+            })
+            .collect();
+
+        let manifest = Self { schema_version: TRACKING_RESULTS_SCHEMA_VERSION, chunk_millis, chunks };
+        (manifest, chunk_results.into_iter().map(TrackingResults::new).collect())
+    }
+
+    pub fn chunk_key(index: usize) -> String {
+        format!("results_chunks/{:05}.json", index)
+    }
+
+    pub fn chunk_at(&self, timestamp_ms: i64) -> Option<&ResultsChunkInfo> {
+        if timestamp_ms < 0 {
+            return None;
+        }
+        self.chunks.get((timestamp_ms / self.chunk_millis) as usize).filter(|chunk| timestamp_ms < chunk.end_ms)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(timestamp_ms: i64) -> TrackingResult {
+        TrackingResult { frame: timestamp_ms / 100, timestamp_ms, persons: vec![] }
+    }
+
+    #[test]
+    fn splits_results_in_time_windows() {
+        let results = vec![result(0), result(900), result(1000), result(3500)];
+        let (manifest, chunks) = ResultsManifest::new(&results, 4200, 1000);
+
+        assert_eq!(manifest.chunks.len(), 5);
+        let frame_counts: Vec<usize> = manifest.chunks.iter().map(|chunk| chunk.frame_count).collect();
+        assert_eq!(frame_counts, vec![2, 1, 0, 1, 0]);
+        assert_eq!((manifest.chunks[4].start_ms, manifest.chunks[4].end_ms), (4000, 4200));
+        assert_eq!(manifest.chunks[3].key, "results_chunks/00003.json");
+
+        assert_eq!(chunks.len(), 5);
+        let timestamps: Vec<i64> = chunks[0].results.iter().map(|result| result.timestamp_ms).collect();
+        assert_eq!(timestamps, vec![0, 900]);
+        assert!(chunks[2].results.is_empty());
+    }
+
+    #[test]
+    fn finds_chunk_at_timestamp() {
+        let (manifest, _) = ResultsManifest::new(&[result(0)], 2500, 1000);
+        assert_eq!(manifest.chunk_at(0).unwrap().index, 0);
+        assert_eq!(manifest.chunk_at(1999).unwrap().index, 1);
+        assert_eq!(manifest.chunk_at(2499).unwrap().index, 2);
+        assert!(manifest.chunk_at(2500).is_none());
+        assert!(manifest.chunk_at(3000).is_none());
+        assert!(manifest.chunk_at(-1).is_none());
+    }
+
+    #[test]
+    fn covers_results_past_the_duration() {
+        // no metadata
+        let (manifest, chunks) = ResultsManifest::new(&[result(1500)], 0, 1000);
+        assert_eq!(manifest.chunks.len(), 2);
+        assert_eq!(manifest.chunks[1].end_ms, 1501);
+        assert_eq!(chunks[1].results.len(), 1);
+    }
+}
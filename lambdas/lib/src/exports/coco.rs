@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common_structs::{PersonBoundingBox, PersonDetectionResult, TrackingResult, VideoMetadata};
+
+
+const PERSON_CATEGORY_ID: i64 = 1;
+
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct CocoCategory {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct CocoVideoInfo {
+    pub id: i64,
+    pub name: String,
+    pub width: i64,
+    pub height: i64,
+    pub fps: f32,
+}
+
+// one image per frame with at least one person
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct CocoImage {
+    pub id: i64,
+    pub video_id: i64,
+    // 0-based
+    pub frame_id: i64,
+    pub file_name: String,
+    pub width: i64,
+    pub height: i64,
+    // in millisecond, not part of COCO
+    pub timestamp_ms: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct CocoAnnotation {
+    pub id: i64,
+    pub image_id: i64,
+    pub video_id: i64,
+    pub category_id: i64,
+    // Rekognition person index
+    pub instance_id: i64,
+    // [left, top, width, height] in pixel
+    pub bbox: [f32; 4],
+    pub area: f32,
+    pub iscrowd: i64,
+}
+
+// COCO video-style annotations of a single video, saved as `coco.json`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct CocoVideo {
+    pub categories: Vec<CocoCategory>,
+    pub videos: Vec<CocoVideoInfo>,
+    pub images: Vec<CocoImage>,
+    pub annotations: Vec<CocoAnnotation>,
+}
+
+impl CocoVideo {
+    // ids are 1-based
+    pub fn new(tracking_results: &[TrackingResult], metadata: &VideoMetadata, video_name: &str) -> Self {
+        let (width, height) = (metadata.frame_width, metadata.frame_height);
+        let video_id = 1;
+
+        let mut images: Vec<CocoImage> = vec![];
+        let mut annotations: Vec<CocoAnnotation> = vec![];
+        for result in tracking_results {
+            let image_id = images.len() as i64 + 1;
+            images.push(CocoImage {
+                id: image_id,
+                video_id,
+                frame_id: result.frame,
+                file_name: format!("{}/{:06}.jpg", video_name, result.frame),
+                width,
+                height,
+                timestamp_ms: result.timestamp_ms,
+            });
+
+            for person in &result.persons {
+                let bounding_box = &person.bounding_box;
+                let bbox = [
+                    bounding_box.left * width as f32,
+                    bounding_box.top * height as f32,
+                    bounding_box.width * width as f32,
+                    bounding_box.height * height as f32,
+                ];
+                annotations.push(CocoAnnotation {
+                    id: annotations.len() as i64 + 1,
+                    image_id,
+                    video_id,
+                    category_id: PERSON_CATEGORY_ID,
+                    instance_id: person.index,
+                    bbox,
+                    area: bbox[2] * bbox[3],
+                    iscrowd: 0,
+                });
+            }
+        }
+
+        Self {
+            categories: vec![CocoCategory { id: PERSON_CATEGORY_ID, name: "person".to_owned() }],
+            videos: vec![CocoVideoInfo { id: video_id, name: video_name.to_owned(), width, height, fps: metadata.frame_rate }],
+            images,
+            annotations,
+        }
+    }
+
+    // back to normalized tracking results (sorted by frame), the interpolated flag is not kept
+    pub fn to_tracking_results(&self) -> Vec<TrackingResult> {
+        let mut results: BTreeMap<i64, TrackingResult> = BTreeMap::new();
+        let mut frames: BTreeMap<i64, (i64, i64, i64)> = BTreeMap::new();
+        for image in &self.images {
+            frames.insert(image.id, (image.frame_id, image.width, image.height));
+            results.insert(image.frame_id, TrackingResult { frame: image.frame_id, timestamp_ms: image.timestamp_ms, persons: vec![] });
+        }
+
+        for annotation in &self.annotations {
+            let Some((frame, width, height)) = frames.get(&annotation.image_id) else {
+                continue;
+            };
+            let (width, height) = (*width as f32, *height as f32);
+            let Some(result) = results.get_mut(frame) else {
+                continue;
+            };
+            result.persons.push(PersonDetectionResult {
+                index: annotation.instance_id,
+                bounding_box: PersonBoundingBox {
+                    left: annotation.bbox[0] / width,
+                    top: annotation.bbox[1] / height,
+                    width: annotation.bbox[2] / width,
+                    height: annotation.bbox[3] / height,
+                },
+                interpolated: false,
+            });
+        }
+        results.into_values().collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata() -> VideoMetadata {
+        VideoMetadata { duration: 2000, frame_rate: 30.0, frame_height: 720, frame_width: 1280 }
+    }
+
+    fn person(index: i64, left: f32) -> PersonDetectionResult {
+        PersonDetectionResult {
+            index,
+            bounding_box: PersonBoundingBox { width: 0.1, height: 0.25, left, top: 0.5 },
+            interpolated: false,
+        }
+    }
+
+    fn results() -> Vec<TrackingResult> {
+        vec![
+            TrackingResult { frame: 0, timestamp_ms: 0, persons: vec![person(0, 0.1), person(1, 0.7)] },
+            TrackingResult { frame: 45, timestamp_ms: 1500, persons: vec![person(0, 0.2)] },
+        ]
+    }
+
+    #[test]
+    fn writes_pixel_annotations() {
+        let coco = CocoVideo::new(&results(), &metadata(), "video");
+        assert_eq!(coco.videos[0].width, 1280);
+        assert_eq!(coco.images.len(), 2);
+        assert_eq!((coco.images[1].id, coco.images[1].frame_id), (2, 45));
+        assert_eq!(coco.images[1].file_name, "video/000045.jpg");
+
+        assert_eq!(coco.annotations.len(), 3);
+        let annotation = &coco.annotations[1];
+        assert_eq!((annotation.id, annotation.image_id, annotation.instance_id), (2, 1, 1));
+        assert_eq!(annotation.bbox, [896.0, 360.0, 128.0, 180.0]);
+        assert_eq!(annotation.area, 128.0 * 180.0);
+    }
+
+    #[test]
+    fn round_trips_tracking_results() {
+        let results = results();
+        let json = serde_json::to_string(&CocoVideo::new(&results, &metadata(), "video")).unwrap();
+        let coco: CocoVideo = serde_json::from_str(&json).unwrap();
+        let round_trip = coco.to_tracking_results();
+
+        assert_eq!(round_trip.len(), results.len());
+        for (expected, result) in results.iter().zip(&round_trip) {
+            assert_eq!((expected.frame, expected.timestamp_ms), (result.frame, result.timestamp_ms));
+            assert_eq!(expected.persons.len(), result.persons.len());
+            for (expected, person) in expected.persons.iter().zip(&result.persons) {
+                assert_eq!(expected.index, person.index);
+                assert!((expected.bounding_box.left - person.bounding_box.left).abs() < 1e-6);
+                assert!((expected.bounding_box.top - person.bounding_box.top).abs() < 1e-6);
+                assert!((expected.bounding_box.width - person.bounding_box.width).abs() < 1e-6);
+                assert!((expected.bounding_box.height - person.bounding_box.height).abs() < 1e-6);
+            }
+        }
+    }
+}
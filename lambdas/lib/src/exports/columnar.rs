@@ -0,0 +1,182 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use arrow_array::{ArrayRef, BooleanArray, Float32Array, Int64Array, RecordBatch};
+use arrow_ipc::writer::FileWriter;
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+
+use crate::common_structs::TrackingResult;
+
+
+// detections of one minute of video per row group (parquet) or record batch (arrow)
+const BATCH_MILLIS: i64 = 60 * 1000;
+
+
+// columnar results written next to `persons.json`, chosen per job
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnarFormat {
+    // `persons.parquet`
+    Parquet,
+    // `persons.arrow`, Arrow IPC file
+    Arrow,
+}
+
+
+// one row per person per frame, same columns as `persons.csv`
+pub fn detections_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("frame", DataType::Int64, false),
+        Field::new("timestamp_ms", DataType::Int64, false),
+        Field::new("index", DataType::Int64, false),
+        Field::new("left", DataType::Float32, false),
+        Field::new("top", DataType::Float32, false),
+        Field::new("width", DataType::Float32, false),
+        Field::new("height", DataType::Float32, false),
+        Field::new("interpolated", DataType::Boolean, false),
+    ]))
+}
+
+// tracking results sorted by timestamp
+fn record_batches(tracking_results: &[TrackingResult]) -> Result<Vec<RecordBatch>> {
+    let mut batches: Vec<RecordBatch> = vec![];
+    for chunk in tracking_results.chunk_by(|a, b| a.timestamp_ms / BATCH_MILLIS == b.timestamp_ms / BATCH_MILLIS) {
+        let persons = chunk.iter().flat_map(|result| result.persons.iter().map(move |person| (result, person)));
+        let (mut frame, mut timestamp_ms, mut index) = (vec![], vec![], vec![]);
+        let (mut left, mut top, mut width, mut height) = (vec![], vec![], vec![], vec![]);
+        let mut interpolated = vec![];
+        for (result, person) in persons {
+            frame.push(result.frame);
+            timestamp_ms.push(result.timestamp_ms);
+            index.push(person.index);
+            left.push(person.bounding_box.left);
+            top.push(person.bounding_box.top);
+            width.push(person.bounding_box.width);
+            height.push(person.bounding_box.height);
+            interpolated.push(person.interpolated);
+        }
+        if frame.is_empty() {
+            continue;
+        }
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from(frame)),
+            Arc::new(Int64Array::from(timestamp_ms)),
+            Arc::new(Int64Array::from(index)),
+            Arc::new(Float32Array::from(left)),
+            Arc::new(Float32Array::from(top)),
+            Arc::new(Float32Array::from(width)),
+            Arc::new(Float32Array::from(height)),
+            Arc::new(BooleanArray::from(interpolated)),
+        ];
+        batches.push(RecordBatch::try_new(detections_schema(), columns)?);
+    }
+    Ok(batches)
+}
+
+pub fn tracking_results_parquet(tracking_results: &[TrackingResult]) -> Result<Vec<u8>> {
+    let mut bytes: Vec<u8> = vec![];
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer = ArrowWriter::try_new(&mut bytes, detections_schema(), Some(properties))?;
+    for batch in record_batches(tracking_results)? {
+        writer.write(&batch)?;
+        // close the row group: one per minute
+        writer.flush()?;
+    }
+    writer.close()?;
+    Ok(bytes)
+}
+
+pub fn tracking_results_arrow(tracking_results: &[TrackingResult]) -> Result<Vec<u8>> {
+    let mut bytes: Vec<u8> = vec![];
+    let mut writer = FileWriter::try_new(&mut bytes, &detections_schema())?;
+    for batch in record_batches(tracking_results)? {
+        writer.write(&batch)?;
+    }
+    writer.finish()?;
+    drop(writer);
+    Ok(bytes)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float32Type, Int64Type};
+    use arrow_ipc::reader::FileReader;
+    use axum::body::Bytes;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    use crate::common_structs::{PersonBoundingBox, PersonDetectionResult};
+
+    use super::*;
+
+    fn person(index: i64, left: f32, interpolated: bool) -> PersonDetectionResult {
+        PersonDetectionResult {
+            index,
+            bounding_box: PersonBoundingBox { width: 0.1, height: 0.25, left, top: 0.5 },
+            interpolated,
+        }
+    }
+
+    // 3 detections in the first minute, 1 in the third one
+    fn results() -> Vec<TrackingResult> {
+        vec![
+            TrackingResult { frame: 0, timestamp_ms: 0, persons: vec![person(0, 0.1, false), person(1, 0.7, false)] },
+            TrackingResult { frame: 1, timestamp_ms: 33, persons: vec![person(0, 0.15, true)] },
+            TrackingResult { frame: 3600, timestamp_ms: 120000, persons: vec![person(0, 0.2, false)] },
+        ]
+    }
+
+    fn assert_batches(batches: &[RecordBatch]) {
+        let rows: Vec<usize> = batches.iter().map(RecordBatch::num_rows).collect();
+        assert_eq!(rows, vec![3, 1]);
+
+        let batch = &batches[0];
+        assert_eq!(batch.schema(), detections_schema());
+        assert_eq!(batch.column(1).as_primitive::<Int64Type>().values().to_vec(), vec![0, 0, 33]);
+        assert_eq!(batch.column(2).as_primitive::<Int64Type>().values().to_vec(), vec![0, 1, 0]);
+        assert_eq!(batch.column(3).as_primitive::<Float32Type>().values().to_vec(), vec![0.1, 0.7, 0.15]);
+        let interpolated: Vec<bool> = batch.column(7).as_boolean().iter().flatten().collect();
+        assert_eq!(interpolated, vec![false, false, true]);
+        assert_eq!(batches[1].column(0).as_primitive::<Int64Type>().value(0), 3600);
+    }
+
+    #[test]
+    fn writes_parquet_row_groups_per_minute() {
+        let bytes = tracking_results_parquet(&results()).unwrap();
+        let bytes = Bytes::from(bytes);
+        let builder = ParquetRecordBatchReaderBuilder::try_new(bytes.clone()).unwrap();
+        assert_eq!(builder.metadata().num_row_groups(), 2);
+
+        // read one row group at a time, the reader would merge them into a single batch
+        let batches: Vec<RecordBatch> = (0..2)
+            .flat_map(|row_group| {
+                let builder = ParquetRecordBatchReaderBuilder::try_new(bytes.clone()).unwrap();
+                builder.with_row_groups(vec![row_group]).build().unwrap().map(|batch| batch.unwrap())
+            })
+            .collect();
+        assert_batches(&batches);
+    }
+
+    #[test]
+    fn writes_arrow_batches_per_minute() {
+        let bytes = tracking_results_arrow(&results()).unwrap();
+        let reader = FileReader::try_new(Cursor::new(bytes), None).unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|batch| batch.unwrap()).collect();
+        assert_batches(&batches);
+    }
+
+    #[test]
+    fn writes_empty_files() {
+        assert!(tracking_results_parquet(&[]).is_ok());
+        let bytes = tracking_results_arrow(&[]).unwrap();
+        assert_eq!(FileReader::try_new(Cursor::new(bytes), None).unwrap().count(), 0);
+    }
+}
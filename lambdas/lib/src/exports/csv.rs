@@ -0,0 +1,79 @@
+use std::fmt::Write;
+
+use crate::analytics::PersonSample;
+use crate::common_structs::TrackingResult;
+
+
+// one row per person per frame, saved as `persons.csv`
+pub fn tracking_results_csv(tracking_results: &[TrackingResult]) -> String {
+    let mut csv = String::from("frame,timestamp_ms,index,left,top,width,height,interpolated\n");
+    for result in tracking_results {
+        for person in &result.persons {
+            let bounding_box = &person.bounding_box;
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{}",
+                result.frame, result.timestamp_ms, person.index, bounding_box.left, bounding_box.top, bounding_box.width, bounding_box.height, person.interpolated
+            );
+        }
+    }
+    csv
+}
+
+// one row per person, saved as `person_summaries.csv`
+// first/last seen are based on detected boxes only
+pub fn person_summaries_csv(tracking_results: &[TrackingResult]) -> String {
+    let samples = PersonSample::from_tracking_results(tracking_results);
+    let mut csv = String::from("index,first_seen_ms,last_seen_ms,duration_seconds\n");
+    for (index, person_samples) in PersonSample::group_by_person(&samples) {
+        let (Some(first), Some(last)) = (person_samples.first(), person_samples.last()) else {
+            continue;
+        };
+        let _ = writeln!(csv, "{},{},{},{}", index, first.timestamp, last.timestamp, (last.timestamp - first.timestamp) as f64 / 1000.0);
+    }
+    csv
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::common_structs::{PersonBoundingBox, PersonDetectionResult};
+
+    use super::*;
+
+    fn person(index: i64, left: f32, interpolated: bool) -> PersonDetectionResult {
+        PersonDetectionResult {
+            index,
+            bounding_box: PersonBoundingBox { width: 0.1, height: 0.25, left, top: 0.5 },
+            interpolated,
+        }
+    }
+
+    fn results() -> Vec<TrackingResult> {
+        vec![
+            TrackingResult { frame: 0, timestamp_ms: 0, persons: vec![person(0, 0.1, false), person(1, 0.7, false)] },
+            TrackingResult { frame: 1, timestamp_ms: 33, persons: vec![person(0, 0.15, true)] },
+            TrackingResult { frame: 45, timestamp_ms: 1500, persons: vec![person(0, 0.2, false)] },
+        ]
+    }
+
+    #[test]
+    fn writes_one_row_per_person_and_frame() {
+        assert_eq!(tracking_results_csv(&results()), "\
+frame,timestamp_ms,index,left,top,width,height,interpolated
+0,0,0,0.1,0.5,0.1,0.25,false
+0,0,1,0.7,0.5,0.1,0.25,false
+1,33,0,0.15,0.5,0.1,0.25,true
+45,1500,0,0.2,0.5,0.1,0.25,false
+");
+    }
+
+    #[test]
+    fn writes_one_row_per_person() {
+        assert_eq!(person_summaries_csv(&results()), "\
+index,first_seen_ms,last_seen_ms,duration_seconds
+0,0,1500,1.5
+1,0,0,0
+");
+    }
+}
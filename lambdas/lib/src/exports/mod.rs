@@ -0,0 +1,7 @@
+// Tracking results in formats for other tools (or split for lazy loading), saved next to `persons.json`.
+pub mod chunks;
+pub mod coco;
+pub mod columnar;
+pub mod csv;
+pub mod mot;
+pub mod webvtt;
@@ -0,0 +1,116 @@
+use std::fmt::Write;
+
+use crate::common_structs::{PersonBoundingBox, TrackingResult, VideoMetadata};
+
+
+// MOTChallenge text files: one box per line, pixel coordinates, 1-based frames and track ids.
+// `gt.txt`: frame,id,left,top,width,height,1,1,1 (considered, pedestrian, fully visible)
+// `det.txt`: frame,-1,left,top,width,height,1,-1,-1,-1 (detections without identity)
+pub fn mot_gt_txt(tracking_results: &[TrackingResult], metadata: &VideoMetadata) -> String {
+    let mut txt = String::new();
+    for result in tracking_results {
+        for person in &result.persons {
+            let _ = writeln!(txt, "{},{},{},1,1,1", result.frame + 1, person.index + 1, pixel_box(&person.bounding_box, metadata));
+        }
+    }
+    txt
+}
+
+// interpolated boxes are not detections, they are only in `gt.txt`
+pub fn mot_det_txt(tracking_results: &[TrackingResult], metadata: &VideoMetadata) -> String {
+    let mut txt = String::new();
+    for result in tracking_results {
+        for person in result.persons.iter().filter(|person| !person.interpolated) {
+            let _ = writeln!(txt, "{},-1,{},1,-1,-1,-1", result.frame + 1, pixel_box(&person.bounding_box, metadata));
+        }
+    }
+    txt
+}
+
+fn pixel_box(bounding_box: &PersonBoundingBox, metadata: &VideoMetadata) -> String {
+    let (width, height) = (metadata.frame_width as f64, metadata.frame_height as f64);
+    format!(
+        "{:.2},{:.2},{:.2},{:.2}",
+        bounding_box.left as f64 * width,
+        bounding_box.top as f64 * height,
+        bounding_box.width as f64 * width,
+        bounding_box.height as f64 * height
+    )
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::common_structs::PersonDetectionResult;
+
+    use super::*;
+
+    fn metadata() -> VideoMetadata {
+        VideoMetadata { duration: 2000, frame_rate: 30.0, frame_height: 720, frame_width: 1280 }
+    }
+
+    fn person(index: i64, left: f32, interpolated: bool) -> PersonDetectionResult {
+        PersonDetectionResult {
+            index,
+            bounding_box: PersonBoundingBox { width: 0.1, height: 0.25, left, top: 0.5 },
+            interpolated,
+        }
+    }
+
+    fn results() -> Vec<TrackingResult> {
+        vec![
+            TrackingResult { frame: 0, timestamp_ms: 0, persons: vec![person(0, 0.1, false), person(1, 0.7, false)] },
+            TrackingResult { frame: 1, timestamp_ms: 33, persons: vec![person(0, 0.15, true)] },
+            TrackingResult { frame: 45, timestamp_ms: 1500, persons: vec![person(0, 0.2, false)] },
+        ]
+    }
+
+    // reads `gt.txt` back into frames (0-based) of (index, normalized box), as an evaluation tool would
+    fn parse_gt(txt: &str, metadata: &VideoMetadata) -> BTreeMap<i64, Vec<(i64, PersonBoundingBox)>> {
+        let (width, height) = (metadata.frame_width as f32, metadata.frame_height as f32);
+        let mut frames: BTreeMap<i64, Vec<(i64, PersonBoundingBox)>> = BTreeMap::new();
+        for line in txt.lines() {
+            let values: Vec<f32> = line.split(',').map(|value| value.parse().unwrap()).collect();
+            assert_eq!(values.len(), 9);
+            frames.entry(values[0] as i64 - 1).or_default().push((
+                values[1] as i64 - 1,
+                PersonBoundingBox { left: values[2] / width, top: values[3] / height, width: values[4] / width, height: values[5] / height },
+            ));
+        }
+        frames
+    }
+
+    #[test]
+    fn writes_pixel_boxes_with_one_based_ids() {
+        let txt = mot_gt_txt(&results(), &metadata());
+        assert_eq!(txt.lines().next(), Some("1,1,128.00,360.00,128.00,180.00,1,1,1"));
+        assert_eq!(txt.lines().nth(2), Some("2,1,192.00,360.00,128.00,180.00,1,1,1"));
+
+        let txt = mot_det_txt(&results(), &metadata());
+        assert_eq!(txt, "\
+1,-1,128.00,360.00,128.00,180.00,1,-1,-1,-1
+1,-1,896.00,360.00,128.00,180.00,1,-1,-1,-1
+46,-1,256.00,360.00,128.00,180.00,1,-1,-1,-1
+");
+    }
+
+    #[test]
+    fn round_trips_ground_truth() {
+        let results = results();
+        let frames = parse_gt(&mot_gt_txt(&results, &metadata()), &metadata());
+        assert_eq!(frames.len(), results.len());
+        for (result, (frame, persons)) in results.iter().zip(frames) {
+            assert_eq!(result.frame, frame);
+            assert_eq!(result.persons.len(), persons.len());
+            for (expected, (index, bounding_box)) in result.persons.iter().zip(persons) {
+                assert_eq!(expected.index, index);
+                assert!((expected.bounding_box.left - bounding_box.left).abs() < 1e-4);
+                assert!((expected.bounding_box.top - bounding_box.top).abs() < 1e-4);
+                assert!((expected.bounding_box.width - bounding_box.width).abs() < 1e-4);
+                assert!((expected.bounding_box.height - bounding_box.height).abs() < 1e-4);
+            }
+        }
+    }
+}
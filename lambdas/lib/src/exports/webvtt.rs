@@ -0,0 +1,114 @@
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::analytics::MAX_GAP_MILLIS;
+use crate::common_structs::TrackingResult;
+
+
+// Each cue lasts until the next frame with results, up to MAX_GAP_MILLIS (persons are no longer shown once lost).
+fn cues(tracking_results: &[TrackingResult]) -> Vec<(i64, i64, &TrackingResult)> {
+    tracking_results
+        .iter()
+        .enumerate()
+        .filter_map(|(index, result)| {
+            let start = result.timestamp_ms;
+            let end = match tracking_results.get(index + 1) {
+                Some(next) => next.timestamp_ms.min(start + MAX_GAP_MILLIS),
+                None => start + MAX_GAP_MILLIS,
+            };
+            (end > start && !result.persons.is_empty()).then_some((start, end, result))
+        })
+        .collect()
+}
+
+// metadata track, saved as `detections.vtt`: each cue holds the JSON list of persons (index and bounding box)
+pub fn detections_vtt(tracking_results: &[TrackingResult]) -> Result<String> {
+    let mut vtt = String::from("WEBVTT\n");
+    for (start, end, result) in cues(tracking_results) {
+        let _ = write!(vtt, "\nframe-{}\n{} --> {}\n{}\n", result.frame, timestamp(start), timestamp(end), serde_json::to_string(&result.persons)?);
+    }
+    Ok(vtt)
+}
+
+// captions track, saved as `captions.vtt`: number of persons, consecutive cues with the same count are merged
+pub fn captions_vtt(tracking_results: &[TrackingResult]) -> String {
+    let mut captions: Vec<(i64, i64, usize)> = vec![];
+    for (start, end, result) in cues(tracking_results) {
+        let count = result.persons.len();
+        match captions.last_mut() {
+            Some(last) if last.1 == start && last.2 == count => last.1 = end,
+            _ => captions.push((start, end, count)),
+        }
+    }
+
+    let mut vtt = String::from("WEBVTT\n");
+    for (start, end, count) in captions {
+        let text = if count == 1 { "1 person in frame".to_owned() } else { format!("{} people in frame", count) };
+        let _ = write!(vtt, "\n{} --> {}\n{}\n", timestamp(start), timestamp(end), text);
+    }
+    vtt
+}
+
+// hh:mm:ss.ttt
+fn timestamp(millis: i64) -> String {
+    let millis = millis.max(0);
+    format!("{:02}:{:02}:{:02}.{:03}", millis / 3_600_000, millis / 60_000 % 60, millis / 1000 % 60, millis % 1000)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::common_structs::{PersonBoundingBox, PersonDetectionResult};
+
+    use super::*;
+
+    fn person(index: i64) -> PersonDetectionResult {
+        PersonDetectionResult {
+            index,
+            bounding_box: PersonBoundingBox { width: 0.5, height: 0.5, left: 0.25, top: 0.25 },
+            interpolated: false,
+        }
+    }
+
+    fn results() -> Vec<TrackingResult> {
+        vec![
+            TrackingResult { frame: 0, timestamp_ms: 0, persons: vec![person(0), person(1)] },
+            TrackingResult { frame: 6, timestamp_ms: 200, persons: vec![person(0), person(1)] },
+            TrackingResult { frame: 12, timestamp_ms: 400, persons: vec![person(0)] },
+            // lost for more than MAX_GAP_MILLIS
+            TrackingResult { frame: 108000, timestamp_ms: 3_600_000, persons: vec![person(0)] },
+        ]
+    }
+
+    #[test]
+    fn writes_detection_cues() {
+        let vtt = detections_vtt(&results()).unwrap();
+        assert!(vtt.starts_with("WEBVTT\n\nframe-0\n00:00:00.000 --> 00:00:00.200\n[{\"index\":0,"));
+        assert!(vtt.contains("\nframe-12\n00:00:00.400 --> 00:00:01.400\n[{\"index\":0,\"bounding_box\":{\"width\":0.5,\"height\":0.5,\"left\":0.25,\"top\":0.25}}]\n"));
+        assert!(vtt.ends_with("\nframe-108000\n01:00:00.000 --> 01:00:01.000\n[{\"index\":0,\"bounding_box\":{\"width\":0.5,\"height\":0.5,\"left\":0.25,\"top\":0.25}}]\n"));
+        assert_eq!(vtt.matches(" --> ").count(), 4);
+    }
+
+    #[test]
+    fn merges_caption_cues() {
+        assert_eq!(captions_vtt(&results()), "\
+WEBVTT
+
+00:00:00.000 --> 00:00:00.400
+2 people in frame
+
+00:00:00.400 --> 00:00:01.400
+1 person in frame
+
+01:00:00.000 --> 01:00:01.000
+1 person in frame
+");
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        assert_eq!(timestamp(0), "00:00:00.000");
+        assert_eq!(timestamp(3_723_004), "01:02:03.004");
+    }
+}
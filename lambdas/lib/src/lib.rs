@@ -3,3 +3,6 @@ pub mod common_structs;
 pub mod env_keys;
 pub mod common_service;
 pub mod constants;
+pub mod analytics;
+pub mod exports;
+pub mod cursor;
@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+use lib::common_service::{CommonService, JobRepository, ObjectStore, VideoAnalyzer};
+use lib::common_structs::RekognitionSNSMessage;
+use process_results_lambda::handlers::process_message;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+// attempts to find the job entry: `start_analysis` registers it after the analyzer returns the job id
+const ENTRY_LOOKUP_ATTEMPTS: u32 = 20;
+const ENTRY_LOOKUP_INTERVAL: Duration = Duration::from_millis(500);
+
+
+// In-process replacement for the SNS topic + process-results-lambda.
+pub async fn run_completion_worker<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: CommonService<S, D, R>,
+    table_name: String,
+    delay: Duration,
+    mut receiver: UnboundedReceiver<RekognitionSNSMessage>,
+) {
+    while let Some(message) = receiver.recv().await {
+        let service = service.clone();
+        let table_name = table_name.clone();
+
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+
+            let mut found = false;
+            for _ in 0..ENTRY_LOOKUP_ATTEMPTS {
+                if service.dynamo.get_entry_single(&table_name, message.entry_job_id()).await.is_ok() {
+                    found = true;
+                    break;
+                }
+                tokio::time::sleep(ENTRY_LOOKUP_INTERVAL).await;
+            }
+            // never registered (e.g. `start_analysis` failed after starting the job)
+            if !found {
+                println!("Job entry not found, skipping the completion of job {}", message.job_id);
+                return;
+            }
+
+            match process_message(&service, &table_name, &message).await {
+                Ok(_) => println!("Job completed: {}", message.job_id),
+                Err(err) => println!("Error processing job {}: {}", message.job_id, err),
+            }
+        });
+    }
+}
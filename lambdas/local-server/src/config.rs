@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+// address to listen on
+pub static LOCAL_ADDRESS_KEY: &str = "LOCAL_ADDRESS";
+// base URL the frontend uses to reach this server (presigned URLs are built from it)
+pub static LOCAL_URL_KEY: &str = "LOCAL_URL";
+// folder for uploaded videos, results and the job table
+pub static LOCAL_DATA_DIR_KEY: &str = "LOCAL_DATA_DIR";
+// GetPersonTracking response (json file) or a folder of paginated responses
+pub static LOCAL_FIXTURE_PATH_KEY: &str = "LOCAL_FIXTURE_PATH";
+// seconds to wait before completing a started job
+pub static LOCAL_COMPLETION_DELAY_KEY: &str = "LOCAL_COMPLETION_DELAY";
+
+
+#[derive(Debug, Clone)]
+pub struct LocalConfig {
+    pub address: String,
+    pub url: String,
+    pub data_dir: PathBuf,
+    pub fixture_path: PathBuf,
+    pub completion_delay: Duration,
+}
+
+impl LocalConfig {
+    pub fn from_env() -> Self {
+        let address = std::env::var(LOCAL_ADDRESS_KEY).unwrap_or("127.0.0.1:8080".to_owned());
+        let url = std::env::var(LOCAL_URL_KEY).unwrap_or(format!("http://{}", address));
+        let data_dir = std::env::var(LOCAL_DATA_DIR_KEY).unwrap_or("local-data".to_owned());
+        let fixture_path = std::env::var(LOCAL_FIXTURE_PATH_KEY).unwrap_or(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/person_tracking.json").to_owned());
+        let completion_delay = std::env::var(LOCAL_COMPLETION_DELAY_KEY).ok().and_then(|delay| delay.parse::<u64>().ok()).unwrap_or(3);
+
+        Self {
+            address,
+            url: url.trim_end_matches('/').to_owned(),
+            data_dir: PathBuf::from(data_dir),
+            fixture_path: PathBuf::from(fixture_path),
+            completion_delay: Duration::from_secs(completion_delay),
+        }
+    }
+}
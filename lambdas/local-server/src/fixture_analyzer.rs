@@ -0,0 +1,252 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Context, Result};
+use aws_sdk_rekognition::types::{BoundingBox, PersonDetail, PersonDetection};
+use lib::analytics::faces::FaceDetection;
+use lib::analytics::labels::LabelDetection;
+use lib::analytics::segments::Segment;
+use lib::common_service::VideoAnalyzer;
+use lib::common_structs::{AnalysisType, JobStatus, RekognitionSNSMessage, VideoMetadata, VideoObject};
+use serde::Deserialize;
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+
+
+// GetPersonTracking response as recorded with `aws rekognition get-person-tracking --job-id <job_id>`
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct PersonTrackingFixture {
+    pub video_metadata: Option<VideoMetadataFixture>,
+    pub persons: Vec<PersonDetectionFixture>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct VideoMetadataFixture {
+    pub duration_millis: Option<i64>,
+    pub frame_rate: Option<f32>,
+    pub frame_height: Option<i64>,
+    pub frame_width: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct PersonDetectionFixture {
+    pub timestamp: i64,
+    pub person: Option<PersonDetailFixture>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct PersonDetailFixture {
+    pub index: i64,
+    pub bounding_box: Option<BoundingBoxFixture>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct BoundingBoxFixture {
+    pub width: f32,
+    pub height: f32,
+    pub left: f32,
+    pub top: f32,
+}
+
+impl VideoMetadataFixture {
+    fn to_metadata(&self) -> VideoMetadata {
+        let mut builder = aws_sdk_rekognition::types::VideoMetadata::builder();
+        if let Some(duration_millis) = self.duration_millis {
+            builder = builder.duration_millis(duration_millis);
+        }
+        if let Some(frame_rate) = self.frame_rate {
+            builder = builder.frame_rate(frame_rate);
+        }
+        if let Some(frame_height) = self.frame_height {
+            builder = builder.frame_height(frame_height);
+        }
+        if let Some(frame_width) = self.frame_width {
+            builder = builder.frame_width(frame_width);
+        }
+        VideoMetadata::new(builder.build())
+    }
+}
+
+impl PersonDetectionFixture {
+    fn to_detection(&self) -> PersonDetection {
+        let mut builder = PersonDetection::builder().timestamp(self.timestamp);
+        if let Some(person) = &self.person {
+            let mut person_builder = PersonDetail::builder().index(person.index);
+            if let Some(bounding_box) = &person.bounding_box {
+                person_builder = person_builder.bounding_box(
+                    BoundingBox::builder()
+                        .width(bounding_box.width)
+                        .height(bounding_box.height)
+                        .left(bounding_box.left)
+                        .top(bounding_box.top)
+                        .build()
+                );
+            }
+            builder = builder.person(person_builder.build());
+        }
+        builder.build()
+    }
+}
+
+
+// Replays a recorded GetPersonTracking response for every started job,
+// and sends the completion message in-process instead of through SNS.
+#[derive(Debug, Clone)]
+pub struct FixtureVideoAnalyzer {
+    detections: Arc<Vec<PersonDetection>>,
+    metadata: Option<VideoMetadata>,
+    jobs: Arc<Mutex<HashSet<String>>>,
+    completion_sender: UnboundedSender<RekognitionSNSMessage>,
+}
+
+impl FixtureVideoAnalyzer {
+    // `path`: a response json file, or a folder of paginated responses (read in file name order)
+    pub fn new(path: &Path, completion_sender: UnboundedSender<RekognitionSNSMessage>) -> Result<Self> {
+        let pages = Self::load_pages(path)?;
+
+        let metadata = pages.iter().find_map(|page| page.video_metadata.as_ref()).map(|metadata| metadata.to_metadata());
+        let detections: Vec<PersonDetection> = pages
+            .iter()
+            .flat_map(|page| page.persons.iter().map(|person| person.to_detection()))
+            .collect();
+        println!("Loaded fixture: {} person detections from {}", detections.len(), path.display());
+
+        Ok(Self {
+            detections: Arc::new(detections),
+            metadata,
+            jobs: Arc::new(Mutex::new(HashSet::new())),
+            completion_sender,
+        })
+    }
+
+    fn load_pages(path: &Path) -> Result<Vec<PersonTrackingFixture>> {
+        let files: Vec<PathBuf> = if path.is_dir() {
+            let mut files: Vec<PathBuf> = fs::read_dir(path)?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+                .collect();
+            files.sort();
+            files
+        } else {
+            vec![path.to_owned()]
+        };
+        if files.is_empty() {
+            bail!("No fixture available in: {}", path.display())
+        }
+
+        files
+            .iter()
+            .map(|file| {
+                let json = fs::read_to_string(file).with_context(|| format!("Error reading fixture: {}", file.display()))?;
+                let page: PersonTrackingFixture = serde_json::from_str(&json).with_context(|| format!("Invalid fixture: {}", file.display()))?;
+                Ok(page)
+            })
+            .collect()
+    }
+}
+
+impl VideoAnalyzer for FixtureVideoAnalyzer {
+    async fn start_tracking(&self, s3_bucket_name: &str, s3_key_name: &str, _role_arn: &str, _topic_arn: &str, job_tag: Option<&str>) -> Result<String> {
+        let job_id = Uuid::new_v4().to_string();
+        self.jobs.lock().unwrap().insert(job_id.clone());
+
+        let message = RekognitionSNSMessage {
+            job_id: job_id.clone(),
+            status: JobStatus::Succeeded,
+            api: "StartPersonTracking".to_owned(),
+            video: VideoObject {
+                s3_object_name: s3_key_name.to_owned(),
+                s3_bucket: s3_bucket_name.to_owned(),
+            },
+            job_tag: job_tag.map(str::to_owned),
+        };
+        self.completion_sender.send(message)?;
+
+        println!("Start job id: {}", job_id);
+        Ok(job_id)
+    }
+
+    async fn get_persons_detection_results(&self, job_id: &str) -> Result<(Vec<PersonDetection>, Option<VideoMetadata>)> {
+        if !self.jobs.lock().unwrap().contains(job_id) {
+            bail!("Tracking job does not exist for id: {}!", job_id)
+        }
+        Ok((self.detections.as_ref().clone(), self.metadata.clone()))
+    }
+
+    // only person tracking responses are recorded
+    async fn start_label_detection(&self, _s3_bucket_name: &str, _s3_key_name: &str, _role_arn: &str, _topic_arn: &str, _job_tag: Option<&str>) -> Result<String> {
+        bail!("Label detection is not available with the fixture analyzer.")
+    }
+
+    async fn get_label_detection_results(&self, job_id: &str) -> Result<(Vec<LabelDetection>, Option<VideoMetadata>)> {
+        bail!("Label detection job does not exist for id: {}!", job_id)
+    }
+
+    async fn start_face_detection(&self, _s3_bucket_name: &str, _s3_key_name: &str, _role_arn: &str, _topic_arn: &str, _job_tag: Option<&str>) -> Result<String> {
+        bail!("Face detection is not available with the fixture analyzer.")
+    }
+
+    async fn get_face_detection_results(&self, job_id: &str) -> Result<(Vec<FaceDetection>, Option<VideoMetadata>)> {
+        bail!("Face detection job does not exist for id: {}!", job_id)
+    }
+
+    async fn start_segment_detection(&self, _s3_bucket_name: &str, _s3_key_name: &str, _role_arn: &str, _topic_arn: &str, _job_tag: Option<&str>) -> Result<String> {
+        bail!("Segment detection is not available with the fixture analyzer.")
+    }
+
+    async fn get_segment_detection_results(&self, job_id: &str) -> Result<(Vec<Segment>, Option<VideoMetadata>)> {
+        bail!("Segment detection job does not exist for id: {}!", job_id)
+    }
+
+    // jobs finish when started
+    async fn get_job_status(&self, job_id: &str, analysis_type: AnalysisType) -> Result<JobStatus> {
+        if analysis_type != AnalysisType::PersonTracking || !self.jobs.lock().unwrap().contains(job_id) {
+            bail!("{:?} job does not exist for id: {}!", analysis_type, job_id)
+        }
+        Ok(JobStatus::Succeeded)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    fn fixture_path() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/person_tracking.json")
+    }
+
+    #[tokio::test]
+    async fn replays_fixture_for_started_jobs() {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let analyzer = FixtureVideoAnalyzer::new(&fixture_path(), sender).unwrap();
+
+        let job_id = analyzer.start_tracking("bucket", "folder/video.mp4", "role", "topic", None).await.unwrap();
+
+        let message = receiver.recv().await.unwrap();
+        assert_eq!(message.job_id, job_id);
+        assert_eq!(message.status, JobStatus::Succeeded);
+        assert_eq!(message.video.s3_object_name, "folder/video.mp4");
+
+        let (detections, metadata) = analyzer.get_persons_detection_results(&job_id).await.unwrap();
+        assert_eq!(detections.len(), 43);
+        assert_eq!(detections[0].person.as_ref().unwrap().index, 0);
+        assert_eq!(metadata.unwrap().frame_rate, 29.97);
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_job() {
+        let (sender, _receiver) = mpsc::unbounded_channel();
+        let analyzer = FixtureVideoAnalyzer::new(&fixture_path(), sender).unwrap();
+        assert!(analyzer.get_persons_detection_results("unknown").await.is_err());
+    }
+}
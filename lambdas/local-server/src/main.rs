@@ -0,0 +1,56 @@
+use anyhow::Result;
+use api_gateway_lambda::auth::JwtVerifier;
+use api_gateway_lambda::router::build_router;
+use completion::run_completion_worker;
+use config::LocalConfig;
+use fixture_analyzer::FixtureVideoAnalyzer;
+use lib::common_service::fs_service::{FsObjectStore, JsonFileJobRepository};
+use lib::common_service::CommonService;
+use lib::env_keys::{AUTH_AUDIENCE_KEY, AUTH_ISSUER_KEY, AUTH_JWKS_KEY, CURSOR_SECRET_KEY, ROLE_ARN_KEY, S3_BUCKET_NAME_KEY, TABLE_NAME_KEY, TOPIC_ARN_KEY};
+use objects::build_object_router;
+use tokens::build_token_router;
+use tokio::sync::mpsc;
+
+pub mod completion;
+pub mod config;
+pub mod fixture_analyzer;
+pub mod objects;
+pub mod tokens;
+
+
+// Runs the API Gateway router, with the results processing done in-process,
+// against the local filesystem and a recorded person tracking response.
+#[tokio::main]
+async fn main() -> Result<()> {
+    let config = LocalConfig::from_env();
+
+    // the handlers read these from the environment, same as on Lambda
+    // tokens of `/local/token` are signed with the key of the default JWKS
+    let defaults = [
+        (S3_BUCKET_NAME_KEY, "local-bucket"), (TABLE_NAME_KEY, "local-jobs"), (ROLE_ARN_KEY, "local-role"), (TOPIC_ARN_KEY, "local-topic"), (CURSOR_SECRET_KEY, "local-cursor-secret"),
+        (AUTH_JWKS_KEY, include_str!("../fixtures/local_auth_jwks.json")), (AUTH_ISSUER_KEY, "local-server"), (AUTH_AUDIENCE_KEY, "local-client")
+    ];
+    for (key, default_value) in defaults {
+        if std::env::var(key).is_err() {
+            std::env::set_var(key, default_value);
+        }
+    }
+    let table_name = std::env::var(TABLE_NAME_KEY)?;
+
+    let (completion_sender, completion_receiver) = mpsc::unbounded_channel();
+    let object_store = FsObjectStore::new(&config.data_dir.join("objects"), &format!("{}/objects", config.url));
+    let job_repository = JsonFileJobRepository::new(&config.data_dir.join("jobs.json"))?;
+    let analyzer = FixtureVideoAnalyzer::new(&config.fixture_path, completion_sender)?;
+    let common_service = CommonService::with_services(object_store.clone(), job_repository, analyzer);
+    let verifier = JwtVerifier::from_env()?;
+
+    tokio::spawn(run_completion_worker(common_service.clone(), table_name, config.completion_delay, completion_receiver));
+
+    let app = build_router(common_service, verifier).merge(build_object_router(object_store)).merge(build_token_router());
+
+    let listener = tokio::net::TcpListener::bind(&config.address).await?;
+    println!("Local server listening on {}", config.url);
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
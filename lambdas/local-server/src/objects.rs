@@ -0,0 +1,51 @@
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, Path, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use lib::common_service::fs_service::FsObjectStore;
+use lib::common_service::ObjectStore;
+
+
+// Stands in for the S3 presigned URLs returned by `FsObjectStore`.
+pub fn build_object_router(store: FsObjectStore) -> Router {
+    Router::new()
+        .route("/objects/:bucket_name/*key", get(get_object).put(put_object))
+        .with_state(store)
+        // videos are uploaded here directly
+        .layer(DefaultBodyLimit::disable())
+}
+
+
+async fn get_object(
+    State(store): State<FsObjectStore>,
+    Path((bucket_name, key)): Path<(String, String)>,
+) -> Response {
+    match store.get_object(&bucket_name, &key).await {
+        Ok((bytes, content_type)) => {
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, content_type.parse().unwrap_or("application/octet-stream".parse().unwrap()));
+            (headers, bytes).into_response()
+        },
+        Err(err) => (StatusCode::NOT_FOUND, err.to_string()).into_response(),
+    }
+}
+
+async fn put_object(
+    State(store): State<FsObjectStore>,
+    Path((bucket_name, key)): Path<(String, String)>,
+    headers: HeaderMap,
+    bytes: Bytes,
+) -> Response {
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/octet-stream");
+
+    match store.put_object(&bucket_name, &key, bytes, content_type).await {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, err.to_string()).into_response(),
+    }
+}
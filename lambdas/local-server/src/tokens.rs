@@ -0,0 +1,48 @@
+use api_gateway_lambda::auth::{Audience, Claims};
+use axum::extract::Query;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use lib::common_structs::current_timestamp;
+use lib::env_keys::{AUTH_AUDIENCE_KEY, AUTH_ISSUER_KEY};
+use serde::Deserialize;
+use serde_json::json;
+
+// key pair of `fixtures/local_auth_jwks.json`, the default keys of the local server
+const LOCAL_KEY_ID: &str = "local-key";
+const LOCAL_KEY: &[u8] = include_bytes!("../fixtures/local_auth_key.pem");
+// seconds
+const LOCAL_TOKEN_VALID_DURATION: u64 = 24 * 60 * 60;
+
+
+// Stands in for the OIDC provider: issues tokens for any user id, signed with the local key.
+pub fn build_token_router() -> Router {
+    Router::new()
+        .route("/local/token", get(get_token))
+}
+
+
+#[derive(Debug, Deserialize)]
+struct GetTokenQueryParams {
+    user_id: String,
+}
+
+async fn get_token(Query(params): Query<GetTokenQueryParams>) -> Response {
+    let (Ok(issuer), Ok(audience)) = (std::env::var(AUTH_ISSUER_KEY), std::env::var(AUTH_AUDIENCE_KEY)) else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Environment variables not defined.").into_response();
+    };
+
+    let claims = Claims { sub: params.user_id, iss: issuer, aud: Audience::Single(audience), exp: current_timestamp() + LOCAL_TOKEN_VALID_DURATION };
+    let mut header = Header::new(Algorithm::RS256);
+    header.kid = Some(LOCAL_KEY_ID.to_owned());
+
+    match EncodingKey::from_rsa_pem(LOCAL_KEY).and_then(|key| encode(&header, &claims, &key)) {
+        Ok(token) => Json(json!({
+            "token": token,
+            "expired_in": LOCAL_TOKEN_VALID_DURATION
+        })).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
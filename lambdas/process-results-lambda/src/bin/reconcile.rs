@@ -0,0 +1,53 @@
+use lambda_runtime::{run, service_fn, tracing, Error, LambdaEvent};
+use lib::common_service::CommonService;
+use lib::env_keys::{JOB_TIMEOUT_MINUTES_KEY, RECONCILE_AFTER_MINUTES_KEY, S3_BUCKET_NAME_KEY, TABLE_NAME_KEY};
+use process_results_lambda::reconciler::{reconcile_jobs, requested_before, ReconcileReport, DEFAULT_JOB_TIMEOUT_MINUTES, DEFAULT_RECONCILE_AFTER_MINUTES};
+use serde_json::Value;
+
+
+fn minutes_from_env(key: &str, default_minutes: u64) -> Result<u64, Error> {
+    match std::env::var(key) {
+        Ok(minutes) => Ok(minutes.parse()?),
+        Err(_) => Ok(default_minutes),
+    }
+}
+
+async fn reconcile(service: &CommonService, older_than_minutes: u64) -> Result<ReconcileReport, Error> {
+    let (table_name, bucket_name) = (std::env::var(TABLE_NAME_KEY)?, std::env::var(S3_BUCKET_NAME_KEY)?);
+    let timeout_minutes = minutes_from_env(JOB_TIMEOUT_MINUTES_KEY, DEFAULT_JOB_TIMEOUT_MINUTES)?;
+    let report = reconcile_jobs(service, &table_name, &bucket_name, requested_before(older_than_minutes), requested_before(timeout_minutes)).await?;
+    Ok(report)
+}
+
+
+// Scheduled Lambda (EventBridge rule, the event is not used), or `reconcile once [older_than_minutes]` from the command line.
+// Jobs still in progress after `RECONCILE_AFTER_MINUTES` (default: 30) are checked against Rekognition,
+// and marked as timed out after `JOB_TIMEOUT_MINUTES` (default: 180).
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing::init_default_subscriber();
+
+    let older_than_minutes = minutes_from_env(RECONCILE_AFTER_MINUTES_KEY, DEFAULT_RECONCILE_AFTER_MINUTES)?;
+
+    let config = aws_config::load_from_env().await;
+    let common_service = CommonService::new(&config);
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => {
+            run(service_fn(|_event: LambdaEvent<Value>| {
+                reconcile(&common_service, older_than_minutes)
+            })).await
+        },
+        Some("once") => {
+            let older_than_minutes = match args.get(1) {
+                Some(minutes) => minutes.parse()?,
+                None => older_than_minutes,
+            };
+            let report = reconcile(&common_service, older_than_minutes).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            Ok(())
+        },
+        Some(command) => Err(format!("Unknown command: {}, usage: reconcile [once [older_than_minutes]]", command).into()),
+    }
+}
@@ -0,0 +1,29 @@
+use lambda_runtime::Error;
+use lib::common_service::CommonService;
+use lib::env_keys::TABLE_NAME_KEY;
+use process_results_lambda::rekey::rekey_user_jobs;
+
+
+// One-off migration to the token user ids: `rekey_user_ids <mapping_file>` from the command line (needs `TABLE_NAME`).
+// Each line of the mapping file is `<previous_user_id>,<sub>`: the browser's random user id, and the `sub` claim of the user.
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(mapping_file) = args.first() else {
+        return Err("Usage: rekey_user_ids <mapping_file>".into());
+    };
+    let table_name = std::env::var(TABLE_NAME_KEY)?;
+    let mapping = std::fs::read_to_string(mapping_file)?;
+
+    let config = aws_config::load_from_env().await;
+    let common_service = CommonService::new(&config);
+
+    for line in mapping.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let Some((previous_user_id, user_id)) = line.split_once(',') else {
+            return Err(format!("Invalid mapping line: {}, expected <previous_user_id>,<sub>", line).into());
+        };
+        let job_ids = rekey_user_jobs(&common_service, &table_name, previous_user_id.trim(), user_id.trim()).await?;
+        println!("Moved {} jobs of {} to {}", job_ids.len(), previous_user_id.trim(), user_id.trim());
+    }
+    Ok(())
+}
@@ -0,0 +1,697 @@
+use anyhow::{anyhow, bail, Context, Result};
+use aws_sdk_rekognition::types::PersonDetection;
+use axum::body::Bytes;
+use lib::analytics::faces::FaceSummary;
+use lib::analytics::heatmap::Heatmap;
+use lib::analytics::labels::LabelSummary;
+use lib::analytics::occupancy::Occupancy;
+use lib::analytics::trajectories::Trajectory;
+use lib::analytics::tripwires::TripwireResult;
+use lib::analytics::zones::ZoneSummary;
+use lib::analytics::segments::SegmentSummary;
+use lib::analytics::PersonSample;
+use lib::exports::chunks::{ResultsManifest, RESULTS_CHUNK_MILLIS};
+use lib::exports::coco::CocoVideo;
+use lib::exports::columnar::{self, ColumnarFormat};
+use lib::exports::{csv, mot, webvtt};
+use lib::common_service::{CommonService, JobRepository, ObjectStore, VideoAnalyzer};
+use lib::common_structs::{AnalysisSummary, AnalysisType, JobStatus, RekognitionSNSMessage, TrackingPostProcessing, TrackingResult, TrackingResults, TrackingSummary, VideoMetadata};
+use lib::constants::{CAPTIONS_VTT_KEY, COCO_JSON_KEY, DETECTIONS_VTT_KEY, HEATMAP_JSON_KEY, HEATMAP_PNG_KEY, MOT_DET_TXT_KEY, MOT_GT_TXT_KEY, OCCUPANCY_JSON_KEY, PERSON_SUMMARIES_CSV_KEY, RESULTS_ARROW_KEY, RESULTS_CSV_KEY, RESULTS_JSON_KEY, RESULTS_MANIFEST_JSON_KEY, RESULTS_PARQUET_KEY, TRAJECTORIES_JSON_KEY, TRIPWIRES_JSON_KEY, ZONES_JSON_KEY};
+
+
+// parse the rekognition completion message delivered as the SNS message body
+pub fn parse_message(message: &str) -> Result<RekognitionSNSMessage> {
+    let message: RekognitionSNSMessage = serde_json::from_str(message).context("Invalid rekognition SNS message")?;
+    Ok(message)
+}
+
+// S3 folder for the job: video object is saved as `{s3_folder_name}/{filename}`
+pub fn s3_folder_name(message: &RekognitionSNSMessage) -> Result<String> {
+    let Some((folder, _)) = message.video.s3_object_name.rsplit_once('/') else {
+        bail!("S3 folder not available for object: {}", message.video.s3_object_name)
+    };
+    Ok(folder.to_owned())
+}
+
+// tracking results (frame based) and summary for the detections
+// post processing only applies to the tracking results, the summary is based on the detections
+pub fn build_results(person_detections: Vec<PersonDetection>, metadata: &Option<VideoMetadata>, post_processing: &Option<TrackingPostProcessing>) -> (Vec<TrackingResult>, TrackingSummary) {
+    let frame_rate = metadata.as_ref().map(|metadata| metadata.frame_rate).unwrap_or(30.0);
+    let mut tracking_results = TrackingResult::new_vec(person_detections.clone(), frame_rate);
+    if let Some(post_processing) = post_processing.as_ref().filter(|post_processing| post_processing.is_enabled()) {
+        tracking_results = TrackingResult::post_process(tracking_results, frame_rate, post_processing);
+    }
+    let tracking_summary = TrackingSummary::new(person_detections);
+    (tracking_results, tracking_summary)
+}
+
+
+pub async fn process_message<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(service: &CommonService<S, D, R>, table_name: &str, message: &RekognitionSNSMessage) -> Result<()> {
+    let job_id = message.entry_job_id();
+    println!("Processing job: {} (rekognition job: {}), status: {:?}, api: {}", job_id, message.job_id, message.status, message.api);
+
+    // late notifications of cancelled jobs, or of a previous attempt of a retried job
+    let entry = service.dynamo.get_entry_single(table_name, job_id).await?;
+    if entry.job_status == JobStatus::Cancelled || entry.rekognition_job_id() != message.job_id {
+        println!("Ignoring notification of job: {}, status: {:?}, current rekognition job: {}", job_id, entry.job_status, entry.rekognition_job_id());
+        return Ok(());
+    }
+
+    if message.status != JobStatus::Succeeded {
+        finish_job(service, table_name, job_id, JobStatus::Failed).await?;
+        return Ok(());
+    }
+
+    let result = match AnalysisType::from_api(&message.api) {
+        Some(AnalysisType::PersonTracking) => save_results(service, table_name, message).await,
+        Some(analysis_type) => save_analysis_results(service, table_name, message, analysis_type).await,
+        None => Err(anyhow!("Unsupported analysis API: {}", message.api)),
+    };
+    if let Err(err) = result {
+        finish_job(service, table_name, job_id, JobStatus::Failed).await?;
+        return Err(err);
+    }
+
+    finish_job(service, table_name, job_id, JobStatus::Succeeded).await?;
+    Ok(())
+}
+
+// the job may have been cancelled (or timed out) while the results were processed: its status is kept
+async fn finish_job<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(service: &CommonService<S, D, R>, table_name: &str, job_id: &str, status: JobStatus) -> Result<()> {
+    if !service.finish_job(table_name, job_id, status.clone()).await? {
+        println!("Job no longer in progress, ignoring status: {:?} of job: {}", status, job_id);
+    }
+    Ok(())
+}
+
+
+// label, face and segment detection: all results in a single file, and a summary in the job entry
+async fn save_analysis_results<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: &CommonService<S, D, R>,
+    table_name: &str,
+    message: &RekognitionSNSMessage,
+    analysis_type: AnalysisType
+) -> Result<()> {
+    let s3_folder_name = s3_folder_name(message)?;
+
+    let (bytes, analysis_summary, metadata) = match analysis_type {
+        AnalysisType::LabelDetection => {
+            let (labels, metadata) = service.rekognition.get_label_detection_results(&message.job_id).await?;
+            (serde_json::to_vec(&labels)?, AnalysisSummary::Labels(LabelSummary::new_vec(&labels)), metadata)
+        },
+        AnalysisType::FaceDetection => {
+            let (faces, metadata) = service.rekognition.get_face_detection_results(&message.job_id).await?;
+            (serde_json::to_vec(&faces)?, AnalysisSummary::Faces(FaceSummary::new(&faces)), metadata)
+        },
+        AnalysisType::SegmentDetection => {
+            let (segments, metadata) = service.rekognition.get_segment_detection_results(&message.job_id).await?;
+            (serde_json::to_vec(&segments)?, AnalysisSummary::Segments(SegmentSummary::new(&segments)), metadata)
+        },
+        AnalysisType::PersonTracking => bail!("Person tracking results are saved with save_results"),
+    };
+
+    let s3_key = format!("{}/{}", s3_folder_name, analysis_type.results_key());
+    service.s3.put_object(&message.video.s3_bucket, &s3_key, Bytes::from(bytes), "application/json").await?;
+
+    service.dynamo.update_analysis_summary(table_name, message.entry_job_id(), &analysis_summary).await?;
+    if let Some(metadata) = &metadata {
+        service.dynamo.update_metadata(table_name, message.entry_job_id(), metadata).await?;
+    }
+    Ok(())
+}
+
+
+async fn save_results<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(service: &CommonService<S, D, R>, table_name: &str, message: &RekognitionSNSMessage) -> Result<()> {
+    let s3_folder_name = s3_folder_name(message)?;
+
+    // zones, tripwires, heatmap, post processing and columnar formats configuration attached to the job (at start_analysis or later)
+    let entry = service.dynamo.get_entry_single(table_name, message.entry_job_id()).await.ok();
+    let zones = entry.as_ref().and_then(|entry| entry.zones.clone()).unwrap_or_default();
+    let tripwires = entry.as_ref().and_then(|entry| entry.tripwires.clone()).unwrap_or_default();
+    let heatmap_config = entry.as_ref().and_then(|entry| entry.heatmap_config.clone()).unwrap_or_default();
+    let post_processing = entry.as_ref().and_then(|entry| entry.post_processing.clone());
+    let columnar_formats = entry.and_then(|entry| entry.columnar_formats).unwrap_or_default();
+
+    let (person_detections, metadata) = service.rekognition.get_persons_detection_results(&message.job_id).await?;
+    let samples = PersonSample::from_detections(&person_detections);
+    let (tracking_results, tracking_summary) = build_results(person_detections, &metadata, &post_processing);
+
+    let s3_key = format!("{}/{}", s3_folder_name, RESULTS_CSV_KEY);
+    service.s3.put_object(&message.video.s3_bucket, &s3_key, Bytes::from(csv::tracking_results_csv(&tracking_results)), "text/csv").await?;
+    let s3_key = format!("{}/{}", s3_folder_name, PERSON_SUMMARIES_CSV_KEY);
+    service.s3.put_object(&message.video.s3_bucket, &s3_key, Bytes::from(csv::person_summaries_csv(&tracking_results)), "text/csv").await?;
+
+    let s3_key = format!("{}/{}", s3_folder_name, DETECTIONS_VTT_KEY);
+    service.s3.put_object(&message.video.s3_bucket, &s3_key, Bytes::from(webvtt::detections_vtt(&tracking_results)?), "text/vtt").await?;
+    let s3_key = format!("{}/{}", s3_folder_name, CAPTIONS_VTT_KEY);
+    service.s3.put_object(&message.video.s3_bucket, &s3_key, Bytes::from(webvtt::captions_vtt(&tracking_results)), "text/vtt").await?;
+
+    // pixel boxes need the frame size
+    if let Some(metadata) = &metadata {
+        let s3_key = format!("{}/{}", s3_folder_name, MOT_GT_TXT_KEY);
+        service.s3.put_object(&message.video.s3_bucket, &s3_key, Bytes::from(mot::mot_gt_txt(&tracking_results, metadata)), "text/plain").await?;
+        let s3_key = format!("{}/{}", s3_folder_name, MOT_DET_TXT_KEY);
+        service.s3.put_object(&message.video.s3_bucket, &s3_key, Bytes::from(mot::mot_det_txt(&tracking_results, metadata)), "text/plain").await?;
+
+        let coco = CocoVideo::new(&tracking_results, metadata, &s3_folder_name);
+        let s3_key = format!("{}/{}", s3_folder_name, COCO_JSON_KEY);
+        service.s3.put_object(&message.video.s3_bucket, &s3_key, Bytes::from(serde_json::to_vec(&coco)?), "application/json").await?;
+    }
+
+    for format in columnar_formats {
+        let (bytes, key, content_type) = match format {
+            ColumnarFormat::Parquet => (columnar::tracking_results_parquet(&tracking_results)?, RESULTS_PARQUET_KEY, "application/vnd.apache.parquet"),
+            ColumnarFormat::Arrow => (columnar::tracking_results_arrow(&tracking_results)?, RESULTS_ARROW_KEY, "application/vnd.apache.arrow.file"),
+        };
+        let s3_key = format!("{}/{}", s3_folder_name, key);
+        service.s3.put_object(&message.video.s3_bucket, &s3_key, Bytes::from(bytes), content_type).await?;
+    }
+
+    // time windows for lazy loading during playback, chunks first so the manifest never points to missing files
+    let duration = metadata.as_ref().map(|metadata| metadata.duration).unwrap_or(0);
+    let (manifest, chunks) = ResultsManifest::new(&tracking_results, duration, RESULTS_CHUNK_MILLIS);
+    for (chunk, chunk_results) in manifest.chunks.iter().zip(chunks) {
+        let bytes = Bytes::from(serde_json::to_vec(&chunk_results)?);
+        let s3_key = format!("{}/{}", s3_folder_name, chunk.key);
+        service.s3.put_object(&message.video.s3_bucket, &s3_key, bytes, "application/json").await?;
+    }
+    let bytes = Bytes::from(serde_json::to_vec(&manifest)?);
+    let s3_key = format!("{}/{}", s3_folder_name, RESULTS_MANIFEST_JSON_KEY);
+    service.s3.put_object(&message.video.s3_bucket, &s3_key, bytes, "application/json").await?;
+
+    let bytes = Bytes::from(serde_json::to_vec(&TrackingResults::new(tracking_results))?);
+    let s3_key = format!("{}/{}", s3_folder_name, RESULTS_JSON_KEY);
+    service.s3.put_object(&message.video.s3_bucket, &s3_key, bytes, "application/json").await?;
+
+    let occupancy = Occupancy::new(&samples, duration);
+    let bytes = Bytes::from(serde_json::to_vec(&occupancy)?);
+    let s3_key = format!("{}/{}", s3_folder_name, OCCUPANCY_JSON_KEY);
+    service.s3.put_object(&message.video.s3_bucket, &s3_key, bytes, "application/json").await?;
+
+    let trajectories = Trajectory::new_vec(&samples);
+    let bytes = Bytes::from(serde_json::to_vec(&trajectories)?);
+    let s3_key = format!("{}/{}", s3_folder_name, TRAJECTORIES_JSON_KEY);
+    service.s3.put_object(&message.video.s3_bucket, &s3_key, bytes, "application/json").await?;
+
+    service.dynamo.update_summary(table_name, message.entry_job_id(), &tracking_summary).await?;
+    if let Some(metadata) = &metadata {
+        service.dynamo.update_metadata(table_name, message.entry_job_id(), metadata).await?;
+    }
+
+    let heatmap = Heatmap::new(&heatmap_config, &samples);
+    let bytes = Bytes::from(serde_json::to_vec(&heatmap)?);
+    let s3_key = format!("{}/{}", s3_folder_name, HEATMAP_JSON_KEY);
+    service.s3.put_object(&message.video.s3_bucket, &s3_key, bytes, "application/json").await?;
+    let s3_key = format!("{}/{}", s3_folder_name, HEATMAP_PNG_KEY);
+    service.s3.put_object(&message.video.s3_bucket, &s3_key, Bytes::from(heatmap.to_png()?), "image/png").await?;
+
+    if !zones.is_empty() {
+        let (zone_summaries, zone_events) = ZoneSummary::new_vec(&zones, &samples);
+        let bytes = Bytes::from(serde_json::to_vec(&zone_events)?);
+        let s3_key = format!("{}/{}", s3_folder_name, ZONES_JSON_KEY);
+        service.s3.put_object(&message.video.s3_bucket, &s3_key, bytes, "application/json").await?;
+        service.dynamo.update_zone_summaries(table_name, message.entry_job_id(), &zone_summaries).await?;
+    }
+
+    if !tripwires.is_empty() {
+        let tripwire_results = TripwireResult::new_vec(&tripwires, &samples, duration);
+        let tripwire_summaries: Vec<_> = tripwire_results.iter().map(TripwireResult::summary).collect();
+        let bytes = Bytes::from(serde_json::to_vec(&tripwire_results)?);
+        let s3_key = format!("{}/{}", s3_folder_name, TRIPWIRES_JSON_KEY);
+        service.s3.put_object(&message.video.s3_bucket, &s3_key, bytes, "application/json").await?;
+        service.dynamo.update_tripwire_summaries(table_name, message.entry_job_id(), &tripwire_summaries).await?;
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_rekognition::types::{BoundingBox, PersonDetail};
+    use lib::analytics::labels::LabelDetection;
+    use lib::analytics::segments::{Segment, SegmentKind};
+    use lib::analytics::tripwires::Tripwire;
+    use lib::analytics::zones::Zone;
+    use lib::analytics::Point;
+    use lib::common_service::memory_service::{InMemoryJobRepository, InMemoryObjectStore, InMemoryVideoAnalyzer};
+    use lib::common_structs::{ChildJob, JobAttempt, RekognitionJobTableEntry};
+    use lib::constants::{LABELS_JSON_KEY, SEGMENTS_JSON_KEY};
+
+    use super::*;
+
+    const TABLE_NAME: &str = "test-table";
+    const BUCKET_NAME: &str = "test-bucket";
+
+    type TestService = CommonService<InMemoryObjectStore, InMemoryJobRepository, InMemoryVideoAnalyzer>;
+
+    fn detection(timestamp: i64, index: i64) -> PersonDetection {
+        let bounding_box = BoundingBox::builder()
+            .width(0.1)
+            .height(0.3)
+            .left(0.2)
+            .top(0.4)
+            .build();
+        let person = PersonDetail::builder()
+            .index(index)
+            .bounding_box(bounding_box)
+            .build();
+        PersonDetection::builder()
+            .timestamp(timestamp)
+            .person(person)
+            .build()
+    }
+
+    #[test]
+    fn parses_rekognition_message() {
+        let message = parse_message(r#"{
+            "JobId": "job-1",
+            "Status": "SUCCEEDED",
+            "API": "StartPersonTracking",
+            "Timestamp": 1725000000000,
+            "Video": {
+                "S3ObjectName": "folder-1/video.mp4",
+                "S3Bucket": "bucket-1"
+            }
+        }"#).unwrap();
+
+        assert_eq!(message.job_id, "job-1");
+        assert_eq!(message.status, JobStatus::Succeeded);
+        assert_eq!(message.api, "StartPersonTracking");
+        assert_eq!(message.video.s3_bucket, "bucket-1");
+        assert_eq!(s3_folder_name(&message).unwrap(), "folder-1");
+    }
+
+    #[test]
+    fn parses_failed_message() {
+        let message = parse_message(r#"{
+            "JobId": "job-1",
+            "Status": "FAILED",
+            "API": "StartPersonTracking",
+            "Video": {
+                "S3ObjectName": "folder-1/video.mp4",
+                "S3Bucket": "bucket-1"
+            }
+        }"#).unwrap();
+        assert_eq!(message.status, JobStatus::Failed);
+    }
+
+    #[test]
+    fn rejects_invalid_message() {
+        assert!(parse_message("{}").is_err());
+        assert!(parse_message("not json").is_err());
+    }
+
+    #[test]
+    fn rejects_object_without_folder() {
+        let message = parse_message(r#"{
+            "JobId": "job-1",
+            "Status": "SUCCEEDED",
+            "API": "StartPersonTracking",
+            "Video": {
+                "S3ObjectName": "video.mp4",
+                "S3Bucket": "bucket-1"
+            }
+        }"#).unwrap();
+        assert!(s3_folder_name(&message).is_err());
+    }
+
+    #[test]
+    fn builds_results_and_summary() {
+        let detections = vec![
+            detection(0, 0),
+            detection(1000, 0),
+            detection(1000, 1),
+            detection(3000, 1),
+        ];
+        let metadata = Some(VideoMetadata { duration: 3000, frame_rate: 10.0, frame_height: 720, frame_width: 1280 });
+
+        let (results, summary) = build_results(detections, &metadata, &None);
+
+        let frames: Vec<i64> = results.iter().map(|result| result.frame).collect();
+        assert_eq!(frames, vec![0, 10, 30]);
+        assert_eq!(results[1].persons.len(), 2);
+
+        assert_eq!(summary.total_detection_count, 2);
+        assert_eq!(summary.average_tracking_time, 1.5);
+    }
+
+    #[test]
+    fn builds_post_processed_results() {
+        let detections = vec![detection(0, 0), detection(400, 0)];
+        let metadata = Some(VideoMetadata { duration: 1000, frame_rate: 10.0, frame_height: 720, frame_width: 1280 });
+        let post_processing = Some(TrackingPostProcessing { max_interpolation_gap_ms: Some(500), smoothing_factor: None });
+
+        let (results, summary) = build_results(detections, &metadata, &post_processing);
+        let frames: Vec<i64> = results.iter().map(|result| result.frame).collect();
+        assert_eq!(frames, vec![0, 1, 2, 3, 4]);
+        assert!(results[1].persons[0].interpolated);
+        assert_eq!(summary.total_detection_count, 1);
+    }
+
+    fn sns_message(job_id: &str, status: &str) -> RekognitionSNSMessage {
+        analysis_sns_message(job_id, status, "StartPersonTracking")
+    }
+
+    fn analysis_sns_message(job_id: &str, status: &str, api: &str) -> RekognitionSNSMessage {
+        parse_message(&format!(r#"{{
+            "JobId": "{}",
+            "Status": "{}",
+            "API": "{}",
+            "Video": {{
+                "S3ObjectName": "folder-1/video.mp4",
+                "S3Bucket": "{}"
+            }}
+        }}"#, job_id, status, api, BUCKET_NAME)).unwrap()
+    }
+
+    // start a job on the in-memory analyzer and register it the same way `start_analysis` does
+    async fn start_job(service: &TestService) -> String {
+        let job_id = service.rekognition.start_tracking(BUCKET_NAME, "folder-1/video.mp4", "role", "topic", None).await.unwrap();
+        service.dynamo.register_entry(TABLE_NAME, "user-1", &job_id, "folder-1", "video.mp4").await.unwrap();
+        job_id
+    }
+
+    async fn get_entry(service: &TestService, job_id: &str) -> RekognitionJobTableEntry {
+        service.dynamo.get_entry_single(TABLE_NAME, job_id).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn process_message_saves_results() {
+        let detections = vec![detection(0, 0), detection(2000, 0), detection(1000, 1)];
+        let metadata = VideoMetadata { duration: 2000, frame_rate: 10.0, frame_height: 720, frame_width: 1280 };
+        let service = CommonService::with_services(
+            InMemoryObjectStore::new(),
+            InMemoryJobRepository::new(),
+            InMemoryVideoAnalyzer::new(detections, Some(metadata)),
+        );
+        let job_id = start_job(&service).await;
+
+        process_message(&service, TABLE_NAME, &sns_message(&job_id, "SUCCEEDED")).await.unwrap();
+
+        let entry = get_entry(&service, &job_id).await;
+        assert_eq!(entry.job_status, JobStatus::Succeeded);
+        assert_eq!(entry.tracking_summary.unwrap().total_detection_count, 2);
+        assert_eq!(entry.video_metadata.unwrap().frame_rate, 10.0);
+
+        let (bytes, content_type) = service.s3.get_object(BUCKET_NAME, &format!("folder-1/{}", RESULTS_JSON_KEY)).await.unwrap();
+        assert_eq!(content_type, "application/json");
+        let results = TrackingResults::from_slice(&bytes, 10.0).unwrap();
+        let frames: Vec<i64> = results.results.iter().map(|result| result.frame).collect();
+        assert_eq!(frames, vec![0, 10, 20]);
+
+        let (bytes, _) = service.s3.get_object(BUCKET_NAME, &format!("folder-1/{}", RESULTS_MANIFEST_JSON_KEY)).await.unwrap();
+        let manifest: ResultsManifest = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(manifest.chunks.len(), 1);
+        let (bytes, _) = service.s3.get_object(BUCKET_NAME, &format!("folder-1/{}", manifest.chunks[0].key)).await.unwrap();
+        assert_eq!(TrackingResults::from_slice(&bytes, 10.0).unwrap().results.len(), 3);
+
+        let (bytes, content_type) = service.s3.get_object(BUCKET_NAME, &format!("folder-1/{}", RESULTS_CSV_KEY)).await.unwrap();
+        assert_eq!(content_type, "text/csv");
+        assert_eq!(String::from_utf8(bytes).unwrap().lines().count(), 4);
+        let (bytes, _) = service.s3.get_object(BUCKET_NAME, &format!("folder-1/{}", PERSON_SUMMARIES_CSV_KEY)).await.unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap().lines().nth(1), Some("0,0,2000,2"));
+
+        let (bytes, content_type) = service.s3.get_object(BUCKET_NAME, &format!("folder-1/{}", DETECTIONS_VTT_KEY)).await.unwrap();
+        assert_eq!(content_type, "text/vtt");
+        assert_eq!(String::from_utf8(bytes).unwrap().matches(" --> ").count(), 3);
+        assert!(service.s3.contains_object(BUCKET_NAME, &format!("folder-1/{}", CAPTIONS_VTT_KEY)));
+
+        let (bytes, _) = service.s3.get_object(BUCKET_NAME, &format!("folder-1/{}", MOT_GT_TXT_KEY)).await.unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap().lines().count(), 3);
+        let (bytes, _) = service.s3.get_object(BUCKET_NAME, &format!("folder-1/{}", COCO_JSON_KEY)).await.unwrap();
+        let coco: CocoVideo = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!((coco.videos[0].width, coco.videos[0].height), (1280, 720));
+        assert_eq!(coco.annotations.len(), 3);
+
+        // nobody is tracked at the same time
+        let (bytes, _) = service.s3.get_object(BUCKET_NAME, &format!("folder-1/{}", OCCUPANCY_JSON_KEY)).await.unwrap();
+        let occupancy: Occupancy = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(occupancy.peak.count, 1);
+        assert_eq!(occupancy.series[0].buckets.len(), 3);
+
+        let (bytes, _) = service.s3.get_object(BUCKET_NAME, &format!("folder-1/{}", TRAJECTORIES_JSON_KEY)).await.unwrap();
+        let trajectories: Vec<Trajectory> = serde_json::from_slice(&bytes).unwrap();
+        let indexes: Vec<i64> = trajectories.iter().map(|trajectory| trajectory.index).collect();
+        assert_eq!(indexes, vec![0, 1]);
+
+        let (bytes, _) = service.s3.get_object(BUCKET_NAME, &format!("folder-1/{}", HEATMAP_JSON_KEY)).await.unwrap();
+        let heatmap: Heatmap = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!((heatmap.columns, heatmap.rows), (32, 18));
+        let (_, content_type) = service.s3.get_object(BUCKET_NAME, &format!("folder-1/{}", HEATMAP_PNG_KEY)).await.unwrap();
+        assert_eq!(content_type, "image/png");
+    }
+
+    #[tokio::test]
+    async fn process_message_saves_zone_summaries() {
+        // all detections are on the left half of the frame
+        let detections = vec![detection(0, 0), detection(1000, 0), detection(2000, 1)];
+        let service = CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), InMemoryVideoAnalyzer::new(detections, None));
+        let job_id = start_job(&service).await;
+        let zones = vec![Zone {
+            name: "left".to_owned(),
+            polygon: vec![
+                Point { x: 0.0, y: 0.0 },
+                Point { x: 0.5, y: 0.0 },
+                Point { x: 0.5, y: 1.0 },
+                Point { x: 0.0, y: 1.0 },
+            ],
+        }];
+        service.dynamo.update_zones(TABLE_NAME, &job_id, &zones).await.unwrap();
+
+        process_message(&service, TABLE_NAME, &sns_message(&job_id, "SUCCEEDED")).await.unwrap();
+
+        let zone_summaries = get_entry(&service, &job_id).await.zone_summaries.unwrap();
+        assert_eq!(zone_summaries.len(), 1);
+        assert_eq!(zone_summaries[0].unique_visitors, 2);
+        assert!(service.s3.contains_object(BUCKET_NAME, &format!("folder-1/{}", ZONES_JSON_KEY)));
+    }
+
+    #[tokio::test]
+    async fn process_message_saves_tripwire_summaries() {
+        // person 0 stays at the same place, person 1 is not tracked long enough to cross
+        let detections = vec![detection(0, 0), detection(1000, 0), detection(2000, 1)];
+        let service = CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), InMemoryVideoAnalyzer::new(detections, None));
+        let job_id = start_job(&service).await;
+        let tripwires = vec![Tripwire {
+            name: "door".to_owned(),
+            start: Point { x: 0.5, y: 0.0 },
+            end: Point { x: 0.5, y: 1.0 },
+        }];
+        service.dynamo.update_tripwires(TABLE_NAME, &job_id, &tripwires).await.unwrap();
+
+        process_message(&service, TABLE_NAME, &sns_message(&job_id, "SUCCEEDED")).await.unwrap();
+
+        let tripwire_summaries = get_entry(&service, &job_id).await.tripwire_summaries.unwrap();
+        assert_eq!(tripwire_summaries.len(), 1);
+        assert_eq!(tripwire_summaries[0].in_count, 0);
+        assert!(service.s3.contains_object(BUCKET_NAME, &format!("folder-1/{}", TRIPWIRES_JSON_KEY)));
+    }
+
+    #[tokio::test]
+    async fn process_message_saves_columnar_results() {
+        let detections = vec![detection(0, 0), detection(1000, 0), detection(2000, 1)];
+        let service = CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), InMemoryVideoAnalyzer::new(detections, None));
+        let job_id = start_job(&service).await;
+
+        // not requested
+        process_message(&service, TABLE_NAME, &sns_message(&job_id, "SUCCEEDED")).await.unwrap();
+        assert!(!service.s3.contains_object(BUCKET_NAME, &format!("folder-1/{}", RESULTS_PARQUET_KEY)));
+
+        service.dynamo.update_columnar_formats(TABLE_NAME, &job_id, &[ColumnarFormat::Parquet]).await.unwrap();
+        process_message(&service, TABLE_NAME, &sns_message(&job_id, "SUCCEEDED")).await.unwrap();
+
+        let (bytes, content_type) = service.s3.get_object(BUCKET_NAME, &format!("folder-1/{}", RESULTS_PARQUET_KEY)).await.unwrap();
+        assert_eq!(content_type, "application/vnd.apache.parquet");
+        assert_eq!(&bytes[0..4], b"PAR1");
+        assert!(!service.s3.contains_object(BUCKET_NAME, &format!("folder-1/{}", RESULTS_ARROW_KEY)));
+    }
+
+    #[tokio::test]
+    async fn process_message_saves_label_results() {
+        let labels = vec![
+            LabelDetection { timestamp: 0, name: "Cart".to_owned(), confidence: 90.0, bounding_boxes: vec![] },
+            LabelDetection { timestamp: 500, name: "Dog".to_owned(), confidence: 80.0, bounding_boxes: vec![] },
+            LabelDetection { timestamp: 1000, name: "Cart".to_owned(), confidence: 95.0, bounding_boxes: vec![] },
+        ];
+        let analyzer = InMemoryVideoAnalyzer::new(vec![], None).with_labels(labels);
+        let service = CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), analyzer);
+        let job_id = service.rekognition.start_label_detection(BUCKET_NAME, "folder-1/video.mp4", "role", "topic", None).await.unwrap();
+        service.dynamo.register_entry(TABLE_NAME, "user-1", &job_id, "folder-1", "video.mp4").await.unwrap();
+
+        process_message(&service, TABLE_NAME, &analysis_sns_message(&job_id, "SUCCEEDED", "StartLabelDetection")).await.unwrap();
+
+        let entry = get_entry(&service, &job_id).await;
+        assert_eq!(entry.job_status, JobStatus::Succeeded);
+        let Some(AnalysisSummary::Labels(label_summaries)) = entry.analysis_summary else {
+            panic!("label summaries not saved");
+        };
+        assert_eq!(label_summaries[0].name, "Cart");
+        assert_eq!(label_summaries[0].detection_count, 2);
+
+        let (bytes, _) = service.s3.get_object(BUCKET_NAME, &format!("folder-1/{}", LABELS_JSON_KEY)).await.unwrap();
+        let saved: Vec<LabelDetection> = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(saved.len(), 3);
+        // person tracking results only
+        assert!(!service.s3.contains_object(BUCKET_NAME, &format!("folder-1/{}", RESULTS_JSON_KEY)));
+    }
+
+    #[tokio::test]
+    async fn process_message_dispatches_on_api() {
+        let segments = vec![Segment { kind: SegmentKind::Shot, start_ms: 0, end_ms: 2000, shot_index: Some(0), technical_cue: None, confidence: 99.0 }];
+        let analyzer = InMemoryVideoAnalyzer::new(vec![], None).with_segments(segments);
+        let service = CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), analyzer);
+        let job_id = service.rekognition.start_segment_detection(BUCKET_NAME, "folder-1/video.mp4", "role", "topic", None).await.unwrap();
+        service.dynamo.register_entry(TABLE_NAME, "user-1", &job_id, "folder-1", "video.mp4").await.unwrap();
+
+        // not a person tracking job
+        assert!(process_message(&service, TABLE_NAME, &sns_message(&job_id, "SUCCEEDED")).await.is_err());
+        assert!(process_message(&service, TABLE_NAME, &analysis_sns_message(&job_id, "SUCCEEDED", "StartCelebrityRecognition")).await.is_err());
+        assert_eq!(get_entry(&service, &job_id).await.job_status, JobStatus::Failed);
+
+        // only in progress jobs finish
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::InProgress).await.unwrap();
+        process_message(&service, TABLE_NAME, &analysis_sns_message(&job_id, "SUCCEEDED", "StartSegmentDetection")).await.unwrap();
+        let entry = get_entry(&service, &job_id).await;
+        assert_eq!(entry.job_status, JobStatus::Succeeded);
+        let Some(AnalysisSummary::Segments(segment_summary)) = entry.analysis_summary else {
+            panic!("segment summary not saved");
+        };
+        assert_eq!(segment_summary.shot_count, 1);
+        assert!(service.s3.contains_object(BUCKET_NAME, &format!("folder-1/{}", SEGMENTS_JSON_KEY)));
+    }
+
+    // parent with a person tracking and a label detection child, registered the same way `start_analysis` does
+    async fn start_multi_analysis_job(service: &TestService) -> (String, String) {
+        service.dynamo.register_entry(TABLE_NAME, "user-1", "parent-1", "folder-1", "video.mp4").await.unwrap();
+        service.dynamo.update_children(TABLE_NAME, "parent-1", &Default::default()).await.unwrap();
+        let tracking_job_id = start_job(service).await;
+        let label_job_id = service.rekognition.start_label_detection(BUCKET_NAME, "folder-1/video.mp4", "role", "topic", None).await.unwrap();
+        service.dynamo.register_entry(TABLE_NAME, "user-1", &label_job_id, "folder-1", "video.mp4").await.unwrap();
+        service.dynamo.update_analysis_type(TABLE_NAME, &label_job_id, AnalysisType::LabelDetection).await.unwrap();
+        for (job_id, analysis_type) in [(&tracking_job_id, AnalysisType::PersonTracking), (&label_job_id, AnalysisType::LabelDetection)] {
+            service.dynamo.update_parent_job_id(TABLE_NAME, job_id, "parent-1").await.unwrap();
+            service.dynamo.update_child_job(TABLE_NAME, "parent-1", job_id, &ChildJob { analysis_type, job_status: JobStatus::InProgress }).await.unwrap();
+        }
+        (tracking_job_id, label_job_id)
+    }
+
+    #[tokio::test]
+    async fn process_message_finishes_parent_with_last_child() {
+        let service = CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), InMemoryVideoAnalyzer::new(vec![detection(0, 0)], None));
+        let (tracking_job_id, label_job_id) = start_multi_analysis_job(&service).await;
+
+        process_message(&service, TABLE_NAME, &sns_message(&tracking_job_id, "SUCCEEDED")).await.unwrap();
+        let parent = get_entry(&service, "parent-1").await;
+        assert_eq!(parent.job_status, JobStatus::InProgress);
+        assert_eq!(parent.children.unwrap()[&tracking_job_id].job_status, JobStatus::Succeeded);
+
+        process_message(&service, TABLE_NAME, &analysis_sns_message(&label_job_id, "SUCCEEDED", "StartLabelDetection")).await.unwrap();
+        let parent = get_entry(&service, "parent-1").await;
+        assert_eq!(parent.job_status, JobStatus::Succeeded);
+        assert!(parent.children.unwrap().values().all(|child| child.job_status == JobStatus::Succeeded));
+    }
+
+    #[tokio::test]
+    async fn process_message_marks_parent_partially_succeeded() {
+        let service = CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), InMemoryVideoAnalyzer::new(vec![detection(0, 0)], None));
+        let (tracking_job_id, label_job_id) = start_multi_analysis_job(&service).await;
+
+        process_message(&service, TABLE_NAME, &analysis_sns_message(&label_job_id, "FAILED", "StartLabelDetection")).await.unwrap();
+        assert_eq!(get_entry(&service, "parent-1").await.job_status, JobStatus::InProgress);
+
+        process_message(&service, TABLE_NAME, &sns_message(&tracking_job_id, "SUCCEEDED")).await.unwrap();
+        assert_eq!(get_entry(&service, "parent-1").await.job_status, JobStatus::PartiallySucceeded);
+    }
+
+    #[tokio::test]
+    async fn parent_status_of_outdated_child_update_is_not_written() {
+        let service = CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), InMemoryVideoAnalyzer::new(vec![], None));
+        let (tracking_job_id, label_job_id) = start_multi_analysis_job(&service).await;
+
+        // both children finish at the same time: the first status is computed before the second child update
+        let first = service.dynamo.update_child_job(TABLE_NAME, "parent-1", &tracking_job_id, &ChildJob { analysis_type: AnalysisType::PersonTracking, job_status: JobStatus::Succeeded }).await.unwrap();
+        let second = service.dynamo.update_child_job(TABLE_NAME, "parent-1", &label_job_id, &ChildJob { analysis_type: AnalysisType::LabelDetection, job_status: JobStatus::Succeeded }).await.unwrap();
+        assert!(service.dynamo.update_parent_job_status(TABLE_NAME, "parent-1", JobStatus::Succeeded, second.children_version.unwrap()).await.unwrap());
+        assert!(!service.dynamo.update_parent_job_status(TABLE_NAME, "parent-1", JobStatus::InProgress, first.children_version.unwrap()).await.unwrap());
+
+        assert_eq!(get_entry(&service, "parent-1").await.job_status, JobStatus::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn process_message_marks_failed_job() {
+        let service = CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), InMemoryVideoAnalyzer::new(vec![], None));
+        let job_id = start_job(&service).await;
+
+        process_message(&service, TABLE_NAME, &sns_message(&job_id, "FAILED")).await.unwrap();
+
+        let entry = get_entry(&service, &job_id).await;
+        assert_eq!(entry.job_status, JobStatus::Failed);
+        assert!(entry.tracking_summary.is_none());
+        assert!(!service.s3.contains_object(BUCKET_NAME, &format!("folder-1/{}", RESULTS_JSON_KEY)));
+    }
+
+    #[tokio::test]
+    async fn process_message_marks_job_failed_on_error() {
+        let service = CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), InMemoryVideoAnalyzer::new(vec![], None));
+        service.dynamo.register_entry(TABLE_NAME, "user-1", "job-1", "folder-1", "video.mp4").await.unwrap();
+
+        // the analyzer does not know this job: fetching detections fails
+        let result = process_message(&service, TABLE_NAME, &sns_message("job-1", "SUCCEEDED")).await;
+        assert!(result.is_err());
+        assert_eq!(get_entry(&service, "job-1").await.job_status, JobStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn process_message_ignores_cancelled_job() {
+        let service = CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), InMemoryVideoAnalyzer::new(vec![detection(0, 0)], None));
+        let job_id = start_job(&service).await;
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::Cancelled).await.unwrap();
+
+        process_message(&service, TABLE_NAME, &sns_message(&job_id, "SUCCEEDED")).await.unwrap();
+
+        let entry = get_entry(&service, &job_id).await;
+        assert_eq!(entry.job_status, JobStatus::Cancelled);
+        assert!(entry.tracking_summary.is_none());
+        assert!(!service.s3.contains_object(BUCKET_NAME, &format!("folder-1/{}", RESULTS_JSON_KEY)));
+    }
+
+    #[tokio::test]
+    async fn status_of_job_cancelled_while_processing_is_kept() {
+        let service = CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), InMemoryVideoAnalyzer::new(vec![], None));
+        let (tracking_job_id, label_job_id) = start_multi_analysis_job(&service).await;
+
+        // cancelled after process_message read the entry, before it writes the final status
+        service.dynamo.update_job_status(TABLE_NAME, &tracking_job_id, JobStatus::Cancelled).await.unwrap();
+        assert!(!service.finish_job(TABLE_NAME, &tracking_job_id, JobStatus::Succeeded).await.unwrap());
+        assert_eq!(get_entry(&service, &tracking_job_id).await.job_status, JobStatus::Cancelled);
+        assert_eq!(get_entry(&service, "parent-1").await.children.unwrap()[&tracking_job_id].job_status, JobStatus::InProgress);
+
+        assert!(service.finish_job(TABLE_NAME, &label_job_id, JobStatus::Succeeded).await.unwrap());
+        assert_eq!(get_entry(&service, &label_job_id).await.job_status, JobStatus::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn process_message_ignores_previous_attempt_of_retried_job() {
+        let service = CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), InMemoryVideoAnalyzer::new(vec![detection(0, 0)], None));
+        let job_id = start_job(&service).await;
+        service.dynamo.update_job_status(TABLE_NAME, &job_id, JobStatus::Failed).await.unwrap();
+        let entry = get_entry(&service, &job_id).await;
+        assert!(service.dynamo.claim_retry(TABLE_NAME, &job_id, &JobAttempt::new(&entry)).await.unwrap());
+        let rekognition_job_id = service.rekognition.start_tracking(BUCKET_NAME, "folder-1/video.mp4", "role", "topic", Some(&job_id)).await.unwrap();
+        assert!(service.dynamo.restart_job(TABLE_NAME, &job_id, &rekognition_job_id, &JobAttempt::new(&entry)).await.unwrap());
+
+        // late notification of the first attempt
+        process_message(&service, TABLE_NAME, &sns_message(&job_id, "FAILED")).await.unwrap();
+        assert_eq!(get_entry(&service, &job_id).await.job_status, JobStatus::InProgress);
+
+        let message = RekognitionSNSMessage { job_tag: Some(job_id.clone()), ..sns_message(&rekognition_job_id, "SUCCEEDED") };
+        process_message(&service, TABLE_NAME, &message).await.unwrap();
+
+        let entry = get_entry(&service, &job_id).await;
+        assert_eq!(entry.job_status, JobStatus::Succeeded);
+        assert_eq!(entry.rekognition_job_id(), rekognition_job_id);
+        assert_eq!(entry.attempts.unwrap()[0].job_status, JobStatus::Failed);
+        assert!(entry.tracking_summary.is_some());
+    }
+}
@@ -0,0 +1,30 @@
+use aws_lambda_events::event::sns::SnsEvent;
+use lambda_runtime::{run, service_fn, tracing, Error, LambdaEvent};
+use lib::common_service::CommonService;
+use lib::env_keys::TABLE_NAME_KEY;
+use process_results_lambda::handlers::{parse_message, process_message};
+
+
+async fn function_handler(service: &CommonService, event: LambdaEvent<SnsEvent>) -> Result<(), Error> {
+    let table_name = std::env::var(TABLE_NAME_KEY)?;
+
+    for record in event.payload.records {
+        let message = parse_message(&record.sns.message)?;
+        process_message(service, &table_name, &message).await?;
+    }
+
+    Ok(())
+}
+
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing::init_default_subscriber();
+
+    let config = aws_config::load_from_env().await;
+    let common_service = CommonService::new(&config);
+
+    run(service_fn(|event: LambdaEvent<SnsEvent>| {
+        function_handler(&common_service, event)
+    })).await
+}
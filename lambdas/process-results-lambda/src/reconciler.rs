@@ -0,0 +1,243 @@
+use anyhow::Result;
+use lib::common_service::{CommonService, JobRepository, ObjectStore, VideoAnalyzer};
+use lib::common_structs::{current_timestamp, JobStatus, RekognitionJobTableEntry, RekognitionSNSMessage, VideoObject};
+use serde::Serialize;
+
+use crate::handlers::process_message;
+
+// jobs still in progress after this are checked against Rekognition
+pub const DEFAULT_RECONCILE_AFTER_MINUTES: u64 = 30;
+// and marked as timed out after this
+pub const DEFAULT_JOB_TIMEOUT_MINUTES: u64 = 180;
+
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct ReconcileReport {
+    // in progress jobs checked against Rekognition
+    pub checked: usize,
+    pub succeeded: Vec<String>,
+    pub failed: Vec<String>,
+    pub timed_out: Vec<String>,
+    // job_id: error
+    pub errors: Vec<(String, String)>,
+}
+
+// request timestamp (seconds) of the jobs older than the given minutes
+pub fn requested_before(older_than_minutes: u64) -> u64 {
+    current_timestamp().saturating_sub(older_than_minutes * 60)
+}
+
+
+// Polling alternative to the SNS completion message, for jobs whose message was lost.
+// In progress jobs requested before `requested_before` get their status from Rekognition,
+// finished ones go through `process_message` like the SNS message (running both is harmless).
+// Jobs requested before `timed_out_before` and still not finished (or unknown to Rekognition) are marked as `TimedOut`.
+// Retried jobs use the start of their current attempt instead of the request.
+pub async fn reconcile_jobs<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: &CommonService<S, D, R>,
+    table_name: &str,
+    bucket_name: &str,
+    requested_before: u64,
+    timed_out_before: u64
+) -> Result<ReconcileReport> {
+    let entries = service.dynamo.scan_in_progress_entries(table_name, requested_before).await?;
+
+    let mut report = ReconcileReport::default();
+    // multi-analysis jobs finish with their children
+    for entry in entries.into_iter().filter(|entry| entry.children.is_none()) {
+        report.checked += 1;
+        match reconcile_job(service, table_name, bucket_name, &entry, timed_out_before).await {
+            Ok(Some(JobStatus::Succeeded)) => report.succeeded.push(entry.job_id),
+            Ok(Some(JobStatus::TimedOut)) => report.timed_out.push(entry.job_id),
+            Ok(Some(_)) => report.failed.push(entry.job_id),
+            Ok(None) => {},
+            Err(err) => report.errors.push((entry.job_id, err.to_string())),
+        }
+    }
+
+    println!("Reconciled jobs: {:?}", report);
+    Ok(report)
+}
+
+// the final status of the job, None if still in progress (or finished in the meantime)
+async fn reconcile_job<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: &CommonService<S, D, R>,
+    table_name: &str,
+    bucket_name: &str,
+    entry: &RekognitionJobTableEntry,
+    timed_out_before: u64
+) -> Result<Option<JobStatus>> {
+    let analysis_type = entry.analysis_type.unwrap_or_default();
+    let timed_out = entry.attempt_started_at() < timed_out_before;
+    let (status, timeout_reason) = match service.rekognition.get_job_status(entry.rekognition_job_id(), analysis_type).await {
+        Ok(status) if matches!(status, JobStatus::Succeeded | JobStatus::Failed) => (status, None),
+        Ok(status) if timed_out => (JobStatus::TimedOut, Some(format!("Rekognition job still {:?} at the job timeout", status))),
+        Ok(_) => return Ok(None),
+        Err(err) if timed_out => (JobStatus::TimedOut, Some(format!("Rekognition job status not available at the job timeout: {}", err))),
+        Err(err) => return Err(err),
+    };
+
+    // the SNS message may have arrived since the scan
+    if let Some(timeout_reason) = timeout_reason {
+        if !service.finish_job(table_name, &entry.job_id, JobStatus::TimedOut).await? {
+            return Ok(None);
+        }
+        service.dynamo.update_timeout_reason(table_name, &entry.job_id, &timeout_reason).await?;
+        return Ok(Some(JobStatus::TimedOut));
+    }
+    if service.dynamo.get_entry_single(table_name, &entry.job_id).await?.job_status != JobStatus::InProgress {
+        return Ok(None);
+    }
+
+    let message = RekognitionSNSMessage {
+        job_id: entry.rekognition_job_id().to_owned(),
+        status: status.clone(),
+        api: analysis_type.api().to_owned(),
+        video: VideoObject {
+            s3_object_name: format!("{}/{}", entry.s3_folder_name, entry.filename),
+            s3_bucket: bucket_name.to_owned(),
+        },
+        job_tag: Some(entry.job_id.clone()),
+    };
+    process_message(service, table_name, &message).await?;
+    Ok(Some(status))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use aws_sdk_rekognition::types::{BoundingBox, PersonDetail, PersonDetection};
+    use lib::common_service::memory_service::{InMemoryJobRepository, InMemoryObjectStore, InMemoryVideoAnalyzer};
+    use lib::common_structs::JobAttempt;
+    use lib::constants::RESULTS_JSON_KEY;
+
+    use super::*;
+
+    const TABLE_NAME: &str = "test-table";
+    const BUCKET_NAME: &str = "test-bucket";
+
+    type TestService = CommonService<InMemoryObjectStore, InMemoryJobRepository, InMemoryVideoAnalyzer>;
+
+    fn test_service() -> TestService {
+        let bounding_box = BoundingBox::builder().width(0.1).height(0.3).left(0.2).top(0.4).build();
+        let detection = PersonDetection::builder()
+            .timestamp(0)
+            .person(PersonDetail::builder().index(0).bounding_box(bounding_box).build())
+            .build();
+        CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), InMemoryVideoAnalyzer::new(vec![detection], None))
+    }
+
+    async fn start_job(service: &TestService, status: JobStatus) -> String {
+        let job_id = service.rekognition.start_tracking(BUCKET_NAME, "folder-1/video.mp4", "role", "topic", None).await.unwrap();
+        service.rekognition.set_job_status(&job_id, status);
+        service.dynamo.register_entry(TABLE_NAME, "user-1", &job_id, "folder-1", "video.mp4").await.unwrap();
+        job_id
+    }
+
+    async fn job_status(service: &TestService, job_id: &str) -> JobStatus {
+        service.dynamo.get_entry_single(TABLE_NAME, job_id).await.unwrap().job_status
+    }
+
+    #[tokio::test]
+    async fn finalizes_jobs_with_lost_messages() {
+        let service = test_service();
+        let succeeded_job_id = start_job(&service, JobStatus::Succeeded).await;
+        let failed_job_id = start_job(&service, JobStatus::Failed).await;
+        let in_progress_job_id = start_job(&service, JobStatus::InProgress).await;
+        service.dynamo.register_entry(TABLE_NAME, "user-1", "unknown-job", "folder-1", "video.mp4").await.unwrap();
+
+        // not old enough
+        let report = reconcile_jobs(&service, TABLE_NAME, BUCKET_NAME, requested_before(DEFAULT_RECONCILE_AFTER_MINUTES), 0).await.unwrap();
+        assert_eq!(report.checked, 0);
+
+        let report = reconcile_jobs(&service, TABLE_NAME, BUCKET_NAME, u64::MAX, 0).await.unwrap();
+        assert_eq!(report.checked, 4);
+        assert_eq!(report.succeeded, vec![succeeded_job_id.clone()]);
+        assert_eq!(report.failed, vec![failed_job_id.clone()]);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].0, "unknown-job");
+
+        assert_eq!(job_status(&service, &succeeded_job_id).await, JobStatus::Succeeded);
+        assert!(service.s3.contains_object(BUCKET_NAME, &format!("folder-1/{}", RESULTS_JSON_KEY)));
+        assert_eq!(job_status(&service, &failed_job_id).await, JobStatus::Failed);
+        assert_eq!(job_status(&service, &in_progress_job_id).await, JobStatus::InProgress);
+    }
+
+    #[tokio::test]
+    async fn skips_jobs_finished_by_sns() {
+        let service = test_service();
+        let job_id = start_job(&service, JobStatus::Succeeded).await;
+
+        let report = reconcile_jobs(&service, TABLE_NAME, BUCKET_NAME, u64::MAX, 0).await.unwrap();
+        assert_eq!(report.succeeded, vec![job_id.clone()]);
+
+        // a late SNS message, then another run
+        let message = RekognitionSNSMessage {
+            job_id: job_id.clone(),
+            status: JobStatus::Succeeded,
+            api: "StartPersonTracking".to_owned(),
+            video: VideoObject { s3_object_name: "folder-1/video.mp4".to_owned(), s3_bucket: BUCKET_NAME.to_owned() },
+            job_tag: None,
+        };
+        process_message(&service, TABLE_NAME, &message).await.unwrap();
+        let report = reconcile_jobs(&service, TABLE_NAME, BUCKET_NAME, u64::MAX, 0).await.unwrap();
+        assert_eq!(report.checked, 0);
+        assert_eq!(job_status(&service, &job_id).await, JobStatus::Succeeded);
+    }
+
+    #[tokio::test]
+    async fn times_out_stale_jobs() {
+        let service = test_service();
+        let in_progress_job_id = start_job(&service, JobStatus::InProgress).await;
+        let succeeded_job_id = start_job(&service, JobStatus::Succeeded).await;
+        service.dynamo.register_entry(TABLE_NAME, "user-1", "unknown-job", "folder-1", "video.mp4").await.unwrap();
+
+        let report = reconcile_jobs(&service, TABLE_NAME, BUCKET_NAME, u64::MAX, u64::MAX).await.unwrap();
+        assert_eq!(report.succeeded, vec![succeeded_job_id]);
+        let mut timed_out = report.timed_out.clone();
+        timed_out.sort();
+        let mut expected = vec![in_progress_job_id.clone(), "unknown-job".to_owned()];
+        expected.sort();
+        assert_eq!(timed_out, expected);
+        assert!(report.errors.is_empty());
+
+        let entry = service.dynamo.get_entry_single(TABLE_NAME, &in_progress_job_id).await.unwrap();
+        assert_eq!(entry.job_status, JobStatus::TimedOut);
+        assert_eq!(entry.timeout_reason.unwrap(), "Rekognition job still InProgress at the job timeout");
+        assert!(service.dynamo.get_entry_single(TABLE_NAME, "unknown-job").await.unwrap().timeout_reason.unwrap().starts_with("Rekognition job status not available"));
+
+        // timed out jobs are no longer in progress
+        let report = reconcile_jobs(&service, TABLE_NAME, BUCKET_NAME, u64::MAX, u64::MAX).await.unwrap();
+        assert_eq!(report.checked, 0);
+    }
+
+    #[tokio::test]
+    async fn retried_jobs_time_out_from_their_current_attempt() {
+        let service = test_service();
+        let job_id = start_job(&service, JobStatus::Failed).await;
+        let mut entry = service.dynamo.get_entry_single(TABLE_NAME, &job_id).await.unwrap();
+        entry.request_timestamp = 1;
+        entry.job_status = JobStatus::Failed;
+        service.dynamo.put_entry(TABLE_NAME, entry.clone());
+
+        let previous_attempt = JobAttempt::new(&entry);
+        assert!(service.dynamo.claim_retry(TABLE_NAME, &job_id, &previous_attempt).await.unwrap());
+        let rekognition_job_id = service.rekognition.start_tracking(BUCKET_NAME, "folder-1/video.mp4", "role", "topic", Some(&job_id)).await.unwrap();
+        service.rekognition.set_job_status(&rekognition_job_id, JobStatus::InProgress);
+        assert!(service.dynamo.restart_job(TABLE_NAME, &job_id, &rekognition_job_id, &previous_attempt).await.unwrap());
+
+        // requested long ago, but the current attempt is not old enough
+        let old = requested_before(DEFAULT_RECONCILE_AFTER_MINUTES);
+        let report = reconcile_jobs(&service, TABLE_NAME, BUCKET_NAME, old, old).await.unwrap();
+        assert_eq!(report.checked, 0);
+        let report = reconcile_jobs(&service, TABLE_NAME, BUCKET_NAME, u64::MAX, old).await.unwrap();
+        assert!(report.timed_out.is_empty());
+
+        // still at its place in the job list
+        let entry = service.dynamo.get_entry_single(TABLE_NAME, &job_id).await.unwrap();
+        assert_eq!(entry.job_status, JobStatus::InProgress);
+        assert_eq!(entry.request_timestamp, 1);
+        assert_eq!(entry.attempts.unwrap()[0].request_timestamp, 1);
+    }
+}
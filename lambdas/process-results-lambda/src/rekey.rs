@@ -0,0 +1,68 @@
+use anyhow::Result;
+use lib::common_service::{CommonService, JobRepository, ObjectStore, VideoAnalyzer};
+use lib::common_structs::{JobQuery, LastEvaluatedKey};
+
+
+// Jobs created before the API required a token belong to the random user id of the browser (local storage),
+// the API uses the `sub` claim of the token since: moves the jobs of `previous_user_id` to `user_id`, with their children.
+// Returns the moved job ids.
+pub async fn rekey_user_jobs<S: ObjectStore, D: JobRepository, R: VideoAnalyzer>(
+    service: &CommonService<S, D, R>,
+    table_name: &str,
+    previous_user_id: &str,
+    user_id: &str
+) -> Result<Vec<String>> {
+    // all pages are read first, the moved jobs leave the queried user
+    let mut job_ids: Vec<String> = vec![];
+    let mut last_evaluated_key: Option<LastEvaluatedKey> = None;
+    loop {
+        let (entries, next_key) = service.dynamo.query_entries(table_name, previous_user_id, &JobQuery::default(), last_evaluated_key).await?;
+        for entry in entries {
+            if let Some(children) = &entry.children {
+                job_ids.extend(children.keys().cloned());
+            }
+            job_ids.push(entry.job_id);
+        }
+        last_evaluated_key = next_key;
+        if last_evaluated_key.is_none() {
+            break;
+        }
+    }
+
+    for job_id in &job_ids {
+        service.dynamo.update_user_id(table_name, job_id, user_id).await?;
+    }
+    Ok(job_ids)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use lib::common_service::memory_service::{InMemoryJobRepository, InMemoryObjectStore, InMemoryVideoAnalyzer};
+    use lib::common_structs::{AnalysisType, ChildJob, JobStatus};
+
+    use super::*;
+
+    const TABLE_NAME: &str = "test-table";
+
+    #[tokio::test]
+    async fn moves_jobs_and_children_of_previous_user_id() {
+        let service = CommonService::with_services(InMemoryObjectStore::new(), InMemoryJobRepository::new(), InMemoryVideoAnalyzer::new(vec![], None));
+        service.dynamo.register_entry(TABLE_NAME, "browser-uuid", "job-1", "browser-uuid", "video.mp4").await.unwrap();
+        service.dynamo.register_entry(TABLE_NAME, "browser-uuid", "parent-1", "browser-uuid", "video.mp4").await.unwrap();
+        service.dynamo.register_entry(TABLE_NAME, "browser-uuid", "child-1", "browser-uuid", "video.mp4").await.unwrap();
+        service.dynamo.update_parent_job_id(TABLE_NAME, "child-1", "parent-1").await.unwrap();
+        let child = ChildJob { analysis_type: AnalysisType::LabelDetection, job_status: JobStatus::InProgress };
+        service.dynamo.update_child_job(TABLE_NAME, "parent-1", "child-1", &child).await.unwrap();
+        service.dynamo.register_entry(TABLE_NAME, "other-uuid", "job-2", "other-uuid", "video.mp4").await.unwrap();
+
+        let mut job_ids = rekey_user_jobs(&service, TABLE_NAME, "browser-uuid", "oidc-sub").await.unwrap();
+        job_ids.sort();
+        assert_eq!(job_ids, vec!["child-1", "job-1", "parent-1"]);
+
+        for job_id in job_ids {
+            assert_eq!(service.dynamo.get_entry_single(TABLE_NAME, &job_id).await.unwrap().user_id, "oidc-sub");
+        }
+        assert_eq!(service.dynamo.get_entry_single(TABLE_NAME, "job-2").await.unwrap().user_id, "other-uuid");
+    }
+}
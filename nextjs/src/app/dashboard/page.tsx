@@ -3,14 +3,13 @@ import React from 'react';
 import {Button, Dropdown, DropdownItem, DropdownMenu, DropdownTrigger, Input, SharedSelection, SortDescriptor, Modal, ModalContent, ModalHeader, ModalBody, ModalFooter, useDisclosure} from "@nextui-org/react";
 
 import { SearchIcon } from '@/icons/searchIcon';
-import { JobEntry, JobStatus, LastEvaluatedKey } from '@/lib/types/dynamoTypes';
+import { JobEntry, JobStatus } from '@/lib/types/dynamoTypes';
 import { RotateIcon } from "@/icons/rotateIcon";
 import { ChevronDownIcon } from "@/icons/cheveronDownIcon";
 import JobTable from "@/components/jobTable";
 import Footer, { routeLookUp, routeNew } from "@/components/footer";
 import Header from "@/components/header";
 import Metadata from '../metadata';
-import { UserContext } from '../../lib/UserService';
 import { RekognitionService } from '../../lib/RekognitionService';
 
 
@@ -19,12 +18,11 @@ const rekognitionService = new RekognitionService()
 const statusOptions = Object.values(JobStatus)
 
 export default function Dashboard() {
-    const {userId, dispatch} = React.useContext(UserContext)
     const {isOpen, onOpenChange, onClose} = useDisclosure();
     const [jobIdToDelete, setJobIdToDelete] = React.useState<string|null>(null);
 
 
-    const [lastKey, setLastKey] = React.useState<LastEvaluatedKey|null>(null)
+    const [lastKey, setLastKey] = React.useState<string|null>(null)
 
     const [jobIdFilterValue, setJobIdFilterValue] = React.useState<string>("")
     const [jobs, setJobs] = React.useState<JobEntry[]>([])
@@ -42,7 +40,7 @@ export default function Dashboard() {
         setIsLoading(true)
 
         try {
-            const [newJobs, newKey] = await rekognitionService.fetchJobs(userId, null)
+            const [newJobs, newKey] = await rekognitionService.fetchJobs(null)
             console.log(`${newJobs.length} jobs fetched. Last key: ${newKey}`)
             setJobs(newJobs)
             setLastKey(newKey)
@@ -57,7 +55,7 @@ export default function Dashboard() {
         setIsLoading(true)
 
         try {
-            const [newJobs, newKey] = await rekognitionService.fetchJobs(userId, lastKey)
+            const [newJobs, newKey] = await rekognitionService.fetchJobs(lastKey)
             console.log(`${newJobs.length} jobs fetched. Last key: ${newKey}`)
             setJobs([...jobs, ...newJobs])
             setLastKey(newKey)
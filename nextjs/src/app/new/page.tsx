@@ -2,7 +2,6 @@
 import React from 'react';
 import { Button } from "@nextui-org/react";
 
-import { UserContext } from '../../lib/UserService';
 import { RekognitionService } from '../../lib/RekognitionService';
 import Footer, { routeDashboard, routeLookUp } from '@/components/footer';
 import Header from '@/components/header';
@@ -12,7 +11,6 @@ const rekognitionService = new RekognitionService()
 
 export default function UploadPage() {
     const [file, setFile] = React.useState<File|null>(null)
-    const {userId, dispatch} = React.useContext(UserContext)
     const [isLoading, setIsLoading] = React.useState(false);
     const [error, setError] = React.useState<string|null>(null);
     const [jobId, setJobId] = React.useState<string|null>(null);
@@ -27,7 +25,7 @@ export default function UploadPage() {
         try {
             const form = new FormData()
             form.append('file', file)
-            const jobId = await rekognitionService.startJob(userId, form)
+            const jobId = await rekognitionService.startJob(form)
             console.log("jobId: ", jobId)
             setJobId(jobId)
         } catch (error) {
@@ -6,20 +6,35 @@ import {NextUIProvider} from "@nextui-org/react";
 const userService = new UserService()
 
 export function Providers({ children }: { children: React.ReactNode }) {
-    const [userId, setUserId] = React.useState<string|null>(null);
+    const [token, setToken] = React.useState<string|null>(null);
+    const [error, setError] = React.useState<string|null>(null);
 
     React.useEffect(() => {
-        const userId = userService.getUserId()
-        setUserId(userId)
+        const signIn = async () => {
+            try {
+                const token = await userService.getToken()
+                if (token !== null) {
+                    setToken(token)
+                }
+            } catch (error) {
+                setError(`${error}`)
+            }
+        }
+        signIn()
     }, []);
 
     const value = React.useMemo(() => (
-        {userId: userId, dispatch: setUserId}
-    ), [userId, setUserId])
+        {token: token, dispatch: setToken}
+    ), [token, setToken])
 
     return (
         <>
-        {(userId === null) ?
+        {(error !== null) ?
+            <div className={`font-mono text-red-500 font-semibold text-lg text-center`}>
+                Error signing in: {error} <br/>
+                Please Refresh the page or check back later!
+            </div> :
+        (token === null) ?
             <></> :
             <UserContext.Provider value={value}>
                 <NextUIProvider>
@@ -29,4 +44,4 @@ export function Providers({ children }: { children: React.ReactNode }) {
         }
         </>
     );
-}
\ No newline at end of file
+}
@@ -35,7 +35,7 @@ export default function JobTable(
         },
         {
             key: "filename",
-            label: "FILENAME",
+            label: "TITLE",
         },
         {
             key: "jobStatus",
@@ -84,6 +84,18 @@ export default function JobTable(
                         backgroundColor = 'bg-green-500'
                         break
                     }
+                    case JobStatus.TIMEDOUT: {
+                        backgroundColor = 'bg-amber-500'
+                        break
+                    }
+                    case JobStatus.CANCELLED: {
+                        backgroundColor = 'bg-gray-500'
+                        break
+                    }
+                    case JobStatus.PARTIALLYSUCCEEDED: {
+                        backgroundColor = 'bg-lime-600'
+                        break
+                    }
                 }
                 return (
                     <div className={`${backgroundColor} text-center px-2 rounded text-white/80 font-medium`}>{cellValue}</div>
@@ -95,6 +107,15 @@ export default function JobTable(
                     </div>
                 )
 
+            case 'filename':
+                return (
+                    <Tooltip content={job.filename} color="secondary" offset={-8}>
+                        <div className=''>
+                            {job.title ?? job.filename}
+                        </div>
+                    </Tooltip>
+                )
+
             case 'delete':
                 return (
                     <Button type="button" color="primary"
@@ -1,26 +1,28 @@
-import { JobEntry, LastEvaluatedKey } from "./types/dynamoTypes"
+import { JobEntry } from "./types/dynamoTypes"
 import { deleteTrackingJob, fetchJobSummary, fetchTrackingJobs, startTrackingJob } from "./serverFunctions"
+import { UserService } from "./UserService"
 
 export class RekognitionService {
     endpoint: string = process.env.API_ENDPOINT ?? ""
+    userService = new UserService()
 
-    async startJob(userId: string, form: FormData): Promise<string> {
-        const jobId = await startTrackingJob(userId, form)
+    async startJob(form: FormData): Promise<string> {
+        const jobId = await startTrackingJob(await this.userService.requireToken(), form)
         return jobId
     }
 
-    async fetchJobs(userId: string, lastEvaluatedKey: LastEvaluatedKey|null): Promise<[JobEntry[], LastEvaluatedKey|null]> {
-        const [jobs, newLastEvaluatedKey] = await fetchTrackingJobs(userId, lastEvaluatedKey)
-        return [jobs, newLastEvaluatedKey]
+    async fetchJobs(cursor: string|null): Promise<[JobEntry[], string|null]> {
+        const [jobs, nextCursor] = await fetchTrackingJobs(await this.userService.requireToken(), cursor)
+        return [jobs, nextCursor]
     }
 
     async lookUpJob(jobId: string): Promise<JobEntry>  {
-        const job = await fetchJobSummary(jobId)
+        const job = await fetchJobSummary(await this.userService.requireToken(), jobId)
         return job
     }
 
     async deleteJob(jobId: string) {
-        await deleteTrackingJob(jobId)
+        await deleteTrackingJob(await this.userService.requireToken(), jobId)
         return
     }
 }
\ No newline at end of file
@@ -1,19 +1,38 @@
 import { createContext, Dispatch, SetStateAction } from "react";
 import { v4 as uuidv4 } from 'uuid';
+import { AuthConfig, exchangeAuthCode, fetchAuthConfig, fetchLocalToken } from "./serverFunctions";
 
 export type USERCONTEXT = {
-    userId: string| null
+    // token of the signed in user, sent to the API as a bearer token
+    token: string| null
     dispatch: Dispatch<string>
 }
 export const UserContext = createContext<USERCONTEXT>({
-    userId: null,
+    token: null,
     dispatch: () => {}
 });
 
+type StoredToken = {
+    token: string,
+    // timestamp in millisecond
+    expiresAt: number
+}
+
+type PendingLogin = {
+    state: string,
+    codeVerifier: string
+}
+
+// tokens expiring within this duration (millisecond) are renewed
+const tokenExpiryMargin = 60 * 1000
+
 
 export class UserService {
     storageKey = "itsukiAnalyzerUserId"
+    tokenStorageKey = "itsukiAnalyzerToken"
+    loginStorageKey = "itsukiAnalyzerLogin"
 
+    // anonymous user id of the browser, only used for the tokens of the local server
     getUserId(): string {
         const value = localStorage.getItem(this.storageKey)
         if (!value) {
@@ -24,4 +43,108 @@ export class UserService {
             return value
         }
     }
+
+    // the stored token, the token of a login redirect, or a new one
+    // null while redirecting to the login page of the OIDC provider
+    async getToken(): Promise<string|null> {
+        const redirectToken = await this.handleLoginRedirect()
+        if (redirectToken !== null) {
+            return redirectToken
+        }
+
+        const storedToken = this.getStoredToken()
+        if (storedToken !== null) {
+            return storedToken
+        }
+
+        const config = await fetchAuthConfig()
+        if (config === null) {
+            // local server: tokens are issued for any user id
+            const [token, expiresIn] = await fetchLocalToken(this.getUserId())
+            this.storeToken(token, expiresIn)
+            return token
+        }
+
+        await this.login(config)
+        return null
+    }
+
+    // a valid token before each API call: expired tokens are renewed (by signing in again)
+    async requireToken(): Promise<string> {
+        const token = await this.getToken()
+        if (token === null) {
+            throw Error('The session expired, signing in again.')
+        }
+        return token
+    }
+
+    // authorization code flow with PKCE, the provider redirects back to the top page
+    async login(config: AuthConfig) {
+        const login: PendingLogin = {
+            state: uuidv4(),
+            codeVerifier: base64UrlEncode(crypto.getRandomValues(new Uint8Array(32)))
+        }
+        sessionStorage.setItem(this.loginStorageKey, JSON.stringify(login))
+
+        const codeChallenge = base64UrlEncode(new Uint8Array(await crypto.subtle.digest('SHA-256', new TextEncoder().encode(login.codeVerifier))))
+        const url = new URL(config.authorizeUrl)
+        url.searchParams.append('response_type', 'code')
+        url.searchParams.append('client_id', config.clientId)
+        url.searchParams.append('redirect_uri', this.redirectUri())
+        url.searchParams.append('scope', 'openid')
+        url.searchParams.append('state', login.state)
+        url.searchParams.append('code_challenge', codeChallenge)
+        url.searchParams.append('code_challenge_method', 'S256')
+        window.location.assign(url)
+    }
+
+    private async handleLoginRedirect(): Promise<string|null> {
+        const params = new URLSearchParams(window.location.search)
+        const code = params.get('code')
+        const value = sessionStorage.getItem(this.loginStorageKey)
+        if (code === null || value === null) {
+            return null
+        }
+        sessionStorage.removeItem(this.loginStorageKey)
+        const login: PendingLogin = JSON.parse(value)
+        if (params.get('state') !== login.state) {
+            return null
+        }
+
+        const [token, expiresIn] = await exchangeAuthCode(code, login.codeVerifier, this.redirectUri())
+        this.storeToken(token, expiresIn)
+        window.history.replaceState(null, '', window.location.pathname)
+        return token
+    }
+
+    private redirectUri(): string {
+        return `${window.location.origin}/`
+    }
+
+    private getStoredToken(): string|null {
+        const value = localStorage.getItem(this.tokenStorageKey)
+        if (!value) {
+            return null
+        }
+        const storedToken: StoredToken = JSON.parse(value)
+        if (storedToken.expiresAt - tokenExpiryMargin < Date.now()) {
+            return null
+        }
+        return storedToken.token
+    }
+
+    // expiresIn in second
+    private storeToken(token: string, expiresIn: number) {
+        const storedToken: StoredToken = {
+            token: token,
+            expiresAt: Date.now() + expiresIn * 1000
+        }
+        localStorage.setItem(this.tokenStorageKey, JSON.stringify(storedToken))
+    }
+}
+
+
+function base64UrlEncode(bytes: Uint8Array): string {
+    const base64 = btoa(String.fromCharCode(...Array.from(bytes)))
+    return base64.replace(/\+/g, '-').replace(/\//g, '_').replace(/=+$/, '')
 }
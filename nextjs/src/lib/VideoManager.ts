@@ -1,16 +1,18 @@
 import {  fetchTrackingResults, fetchVideoUrl } from "./serverFunctions"
 import { TrackingSummary } from "./types/dynamoTypes"
 import { PersonDetectionResult, TrackingResult } from "./types/resultTypes"
+import { UserService } from "./UserService"
 
 export class VideoManager {
+    userService = new UserService()
 
     async fetchVideoUrl(jobId: string): Promise<string> {
-        const videoUrl = await fetchVideoUrl(jobId)
+        const videoUrl = await fetchVideoUrl(await this.userService.requireToken(), jobId)
         return videoUrl
     }
 
     async fetchResults(jobId: string) {
-        const trackingResults = await fetchTrackingResults(jobId)
+        const trackingResults = await fetchTrackingResults(await this.userService.requireToken(), jobId)
         const sortedResults = trackingResults.sort((a, b) => a.frame - b.frame)
         return sortedResults
     }
@@ -50,7 +52,8 @@ export class VideoManager {
     getRangeSummary(personMap: Map<number, [number, number]>, startFrame: number, endFrame: number, frameDuration: number): TrackingSummary {
         // console.log(personMap)
         const personsInRange = Array.from(personMap.values()).filter((person) => {
-            return person[0]<=endFrame
+            // same as TrackingSummary::new_in_range: tracks overlapping the range, clipped to it
+            return person[0]<=endFrame && person[1]>=startFrame
         });
         console.log(personsInRange)
         if (personsInRange.length == 0) {
@@ -2,18 +2,77 @@
 
 import { objectToCamel } from "ts-case-convert"
 import { TrackingResult } from "./types/resultTypes"
-import { JobEntry, LastEvaluatedKey } from "./types/dynamoTypes"
+import { JobEntry } from "./types/dynamoTypes"
 
 const endpoint: string = process.env.API_ENDPOINT ?? ""
+// OIDC provider of the API tokens (e.g. the Cognito hosted UI), not set for the local server
+const authAuthorizeUrl: string = process.env.AUTH_AUTHORIZE_URL ?? ""
+const authTokenUrl: string = process.env.AUTH_TOKEN_URL ?? ""
+const authClientId: string = process.env.AUTH_CLIENT_ID ?? ""
+
+export type AuthConfig = {
+    authorizeUrl: string,
+    clientId: string
+}
+
+export async function fetchAuthConfig(): Promise<AuthConfig|null> {
+    if (authAuthorizeUrl === "" || authTokenUrl === "" || authClientId === "") {
+        return null
+    }
+    return {
+        authorizeUrl: authAuthorizeUrl,
+        clientId: authClientId
+    }
+}
+
+// returns the ID token (its audience is the client id, as verified by the API) and its validity in second
+export async function exchangeAuthCode(code: string, codeVerifier: string, redirectUri: string): Promise<[string, number]> {
+    const options = {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/x-www-form-urlencoded' },
+        body: new URLSearchParams({
+            grant_type: 'authorization_code',
+            client_id: authClientId,
+            code: code,
+            code_verifier: codeVerifier,
+            redirect_uri: redirectUri
+        })
+    }
+    const response = await fetch(authTokenUrl, options)
+    const responseJson = await response.json()
+    if (!response.ok) {
+        throw Error(responseJson.error_description ?? responseJson.error ?? "Unknwon Error")
+    }
+
+    return [responseJson.id_token, responseJson.expires_in]
+}
 
-export async function fetchJobSummary(jobId: string): Promise<JobEntry> {
+// local server only: a token for any user id
+export async function fetchLocalToken(userId: string): Promise<[string, number]> {
     if (!checkEndpoint()) {
         throw Error('endpoint not available')
     }
 
-    const url = new URL(`${endpoint}${jobId}`)
+    const url = new URL(`${endpoint}local/token`)
+    url.searchParams.append('user_id', userId)
     const response = await fetch(url)
     const responseJson = await response.json()
+    if (!response.ok) {
+        throw Error(responseJson.message ?? "Unknwon Error")
+    }
+
+    return [responseJson.token, responseJson.expired_in]
+}
+
+
+export async function fetchJobSummary(token: string, jobId: string): Promise<JobEntry> {
+    if (!checkEndpoint()) {
+        throw Error('endpoint not available')
+    }
+
+    const url = new URL(`${endpoint}${jobId}`)
+    const response = await fetch(url, { headers: authHeaders(token) })
+    const responseJson = await response.json()
     console.log(responseJson)
 
     if (!response.ok) {
@@ -27,13 +86,13 @@ export async function fetchJobSummary(jobId: string): Promise<JobEntry> {
 }
 
 
-export async function fetchTrackingResults(jobId: string): Promise<TrackingResult[]> {
+export async function fetchTrackingResults(token: string, jobId: string): Promise<TrackingResult[]> {
     if (!checkEndpoint()) {
         throw Error('endpoint not available')
     }
 
     const resultsUrl = new URL(`${endpoint}${jobId}/results_url`)
-    const resultsUrlResponse = await fetch(resultsUrl)
+    const resultsUrlResponse = await fetch(resultsUrl, { headers: authHeaders(token) })
     const resultsResponseJson = await resultsUrlResponse.json()
     console.log(resultsResponseJson)
     if (!resultsUrlResponse.ok) {
@@ -46,18 +105,19 @@ export async function fetchTrackingResults(jobId: string): Promise<TrackingResul
         throw Error('Error getting json results.')
     }
     const json = await response.json()
-    const results: TrackingResult[] = objectToCamel(json) as TrackingResult[]
+    // schema version 1 is a plain array of results
+    const results: TrackingResult[] = objectToCamel(Array.isArray(json) ? json : json.results) as TrackingResult[]
 
     return results
 }
 
-export async function fetchVideoUrl(jobId: string): Promise<string> {
+export async function fetchVideoUrl(token: string, jobId: string): Promise<string> {
     if (!checkEndpoint()) {
         throw Error('endpoint not available')
     }
 
     const url = new URL(`${endpoint}${jobId}/video_url`)
-    const response = await fetch(url)
+    const response = await fetch(url, { headers: authHeaders(token) })
     const responseJson = await response.json()
     // console.log(responseJson)
 
@@ -70,7 +130,7 @@ export async function fetchVideoUrl(jobId: string): Promise<string> {
 
 
 
-export async function startTrackingJob(userId: string, formData: FormData): Promise<string> {
+export async function startTrackingJob(token: string, formData: FormData): Promise<string> {
     if (!checkEndpoint()) {
         throw Error('endpoint not available')
     }
@@ -81,7 +141,7 @@ export async function startTrackingJob(userId: string, formData: FormData): Prom
     var uploadUrl = new URL(`${endpoint}upload_url`)
     const uploadOptions = {
         method: 'GET',
-        headers: { 'Content-Type': 'application/json' },
+        headers: { 'Content-Type': 'application/json', ...authHeaders(token) },
     }
     uploadUrl.searchParams.append('filename', file.name);
     uploadUrl.searchParams.append('content_type', file.type);
@@ -113,9 +173,8 @@ export async function startTrackingJob(userId: string, formData: FormData): Prom
     var startUrl = new URL(`${endpoint}start_analysis`)
     const startOptions = {
         method: 'POST',
-        headers: { 'Content-Type': 'application/json' },
+        headers: { 'Content-Type': 'application/json', ...authHeaders(token) },
         body: JSON.stringify({
-            user_id: userId,
             s3_folder_name: objectFolder,
             filename: file.name,
         })
@@ -132,18 +191,18 @@ export async function startTrackingJob(userId: string, formData: FormData): Prom
     return jobId
 }
 
-export async function fetchTrackingJobs(userId: string, lastEvaluatedKey: LastEvaluatedKey|null): Promise<[JobEntry[], LastEvaluatedKey|null]> {
+// jobs of the user of the token
+export async function fetchTrackingJobs(token: string, cursor: string|null): Promise<[JobEntry[], string|null]> {
     if (!checkEndpoint) {
         throw Error('endpoint not available')
     }
     var options = {
         method: 'GET',
-        headers: { 'Content-Type': 'application/json' },
+        headers: { 'Content-Type': 'application/json', ...authHeaders(token) },
     }
-    var url = new URL(`${endpoint}${userId}/jobs`)
-    if (lastEvaluatedKey != null) {
-        url.searchParams.append('job_id', lastEvaluatedKey.jobId);
-        url.searchParams.append('request_timestamp', lastEvaluatedKey.requestTimestamp.toString());
+    var url = new URL(`${endpoint}jobs`)
+    if (cursor != null) {
+        url.searchParams.append('cursor', cursor);
     }
 
     const response = await fetch(url, options)
@@ -154,18 +213,19 @@ export async function fetchTrackingJobs(userId: string, lastEvaluatedKey: LastEv
     }
 
     const jobs: JobEntry[] = objectToCamel(responseJson.jobs) as JobEntry[]
-    const newLastEvaluatedKey: LastEvaluatedKey|null = objectToCamel(responseJson.last_evaluated_key) as LastEvaluatedKey|null
+    const nextCursor: string|null = responseJson.next_cursor ?? null
     console.log(jobs)
 
-    return [jobs, newLastEvaluatedKey]
+    return [jobs, nextCursor]
 }
 
-export async function deleteTrackingJob(jobId: string) {
+export async function deleteTrackingJob(token: string, jobId: string) {
     if (!checkEndpoint()) {
         throw Error('endpoint not available')
     }
     const options = {
         method: 'DELETE',
+        headers: authHeaders(token),
     }
     const url = new URL(`${endpoint}${jobId}`)
     const response = await fetch(url, options)
@@ -182,4 +242,8 @@ export async function deleteTrackingJob(jobId: string) {
 
 function checkEndpoint(): boolean {
     return (endpoint !== "")
+}
+
+function authHeaders(token: string): Record<string, string> {
+    return { 'Authorization': `Bearer ${token}` }
 }
\ No newline at end of file
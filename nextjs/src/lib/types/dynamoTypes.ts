@@ -16,6 +16,11 @@ export enum JobStatus {
     FAILED = "FAILED",
     INPROGRESS = "INPROGRESS",
     SUCCEEDED = "SUCCEEDED",
+    // no completion before the job timeout
+    TIMEDOUT = "TIMEDOUT",
+    CANCELLED = "CANCELLED",
+    // multi-analysis job with both succeeded and failed children
+    PARTIALLYSUCCEEDED = "PARTIALLYSUCCEEDED",
 }
 
 export type JobEntry = {
@@ -28,6 +33,12 @@ export type JobEntry = {
     jobStatus: JobStatus,
     trackingSummary: TrackingSummary | null,
     videoMetadata: VideoMetadata | null,
+    timeoutReason?: string | null,
+    // user editable details
+    title?: string | null,
+    description?: string | null,
+    tags?: string[] | null,
+    location?: string | null,
 }
 
 export type LastEvaluatedKey = {
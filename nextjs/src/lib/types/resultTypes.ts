@@ -1,11 +1,15 @@
 export type TrackingResult = {
     frame: number,
+    // not available for results saved before schema version 2
+    timestampMs?: number,
     persons: PersonDetectionResult[]
 }
 
 export type PersonDetectionResult = {
     index: number,
-    boundingBox: PersonBoundingBox
+    boundingBox: PersonBoundingBox,
+    // box synthesized between two detections
+    interpolated?: boolean
 }
 
 export type PersonBoundingBox = {